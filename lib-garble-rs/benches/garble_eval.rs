@@ -0,0 +1,84 @@
+//! Baseline for `garble_skcd` and `eval`; reported as gates/sec ie criterion "elements".
+//!
+//! cargo bench --bench garble_eval
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use lib_garble_rs::garble_skcd;
+use lib_garble_rs::garbled_display_circuit_prepare_garbler_inputs;
+use lib_garble_rs::parse_skcd;
+use lib_garble_rs::prepare_evaluator_inputs;
+use lib_garble_rs::EvalCache;
+
+const ADDER: &[u8] = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+const DISPLAY_120X52: &[u8] =
+    include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin");
+
+fn nb_gates(skcd_buf: &[u8]) -> u64 {
+    parse_skcd(skcd_buf).unwrap().get_gates().len() as u64
+}
+
+pub fn bench_garble(c: &mut Criterion) {
+    let mut group = c.benchmark_group("garble_skcd");
+    for (name, skcd_buf) in [
+        ("full_adder", ADDER),
+        ("display_message_120x52_2digits", DISPLAY_120X52),
+    ] {
+        group.throughput(Throughput::Elements(nb_gates(skcd_buf)));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            skcd_buf,
+            |b, skcd_buf| {
+                b.iter(|| garble_skcd(black_box(skcd_buf)).unwrap());
+            },
+        );
+    }
+    group.finish();
+}
+
+pub fn bench_eval(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eval_warm_cache");
+
+    // "generic circuit": no garbler inputs
+    let garb = garble_skcd(ADDER).unwrap();
+    let encoded_garbler_inputs = garb.encode_garbler_inputs_partial(&[], 0).unwrap();
+    let evaluator_inputs = vec![1u8; garb.num_evaluator_inputs()];
+    let mut outputs = vec![];
+    let mut eval_cache = EvalCache::new();
+    group.throughput(Throughput::Elements(nb_gates(ADDER)));
+    group.bench_function("full_adder", |b| {
+        b.iter(|| {
+            garb.eval(
+                black_box(&encoded_garbler_inputs),
+                black_box(&evaluator_inputs),
+                black_box(&mut outputs),
+                black_box(&mut eval_cache),
+            )
+            .unwrap();
+        });
+    });
+
+    let garb = garble_skcd(DISPLAY_120X52).unwrap();
+    let encoded_garbler_inputs =
+        garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "").unwrap();
+    let evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+    let mut outputs = vec![];
+    let mut eval_cache = EvalCache::new();
+    group.throughput(Throughput::Elements(nb_gates(DISPLAY_120X52)));
+    group.bench_function("display_message_120x52_2digits", |b| {
+        b.iter(|| {
+            garb.eval(
+                black_box(&encoded_garbler_inputs),
+                black_box(&evaluator_inputs),
+                black_box(&mut outputs),
+                black_box(&mut eval_cache),
+            )
+            .unwrap();
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_garble, bench_eval);
+criterion_main!(benches);
@@ -0,0 +1,596 @@
+//! Build a `Circuit` from its parts(inputs, gates, outputs).
+//!
+//! `Circuit` is owned by circuit-types-rs and this crate can ONLY get one by deserializing it;
+//! so the parts are serialized here with the same postcard layout(cf `CircuitLayout`),
+//! and the result is given to `deserialize_from_buffer` like any .skcd.
+//! cf `test_build_full_adder_matches_skcd` which pins that layout.
+//!
+//! That layout is private to circuit-types-rs, and can change with it: `build_circuit` reads back
+//! the deserialized `Circuit` and returns an error if it does NOT match the given parts,
+//! instead of a silently wrong circuit.
+//! TODO(interstellar) move this to a constructor in circuit-types-rs; and until then keep
+//! the circuit-types-rs dependency pinned, cf Cargo.toml.
+//!
+//! `DisplayConfig` is built the same way; cf `DisplayConfigBuilder`.
+
+use alloc::vec::Vec;
+use serde::Serialize;
+
+use circuit_types_rs::{
+    Circuit, DisplayConfig, EvaluatorInputsType, GarblerInputsType, GateType, KindBinary,
+    KindUnary, WireRef,
+};
+
+use crate::circuit_validation::CircuitValidationError;
+use crate::InterstellarError;
+
+/// Same postcard layout as `Circuit`
+#[derive(Serialize)]
+struct CircuitLayout {
+    inputs: Vec<WireRef>,
+    outputs: Vec<WireRef>,
+    gates: Vec<GateLayout>,
+    /// every wire id, sorted
+    wires: Vec<WireRef>,
+    metadata: MetadataLayout,
+    config: Option<DisplayConfig>,
+}
+
+/// Same postcard layout as `Gate`
+#[derive(Serialize)]
+struct GateLayout {
+    internal: GateType,
+    output: WireRef,
+}
+
+/// Same postcard layout as `Metadata`
+/// NOTE: the gates counts are `HashMap` in circuit-types-rs; in the postcard wire format a map is
+/// "varint(len) then each (key, value)" and a seq is "varint(len) then each element", with a tuple
+/// being its elements in order; ie a map is encoded exactly like a `Vec` of pairs.
+/// The order of the pairs does not matter: they are read back into a `HashMap`.
+#[derive(Serialize)]
+struct MetadataLayout {
+    /// ids of the first and last(inclusive) outputs; cf `gate_idx_is_output`
+    outputs_start_end_indexes: (usize, usize),
+    gates_unary: Vec<(KindUnary, usize)>,
+    gates_binary: Vec<(KindBinary, usize)>,
+    /// number of constant gates: (false, true)
+    gates_constant: (usize, usize),
+    max_gate_id: usize,
+}
+
+fn count<K: PartialEq>(counts: &mut Vec<(K, usize)>, kind: K) {
+    if let Some((_, nb)) = counts.iter_mut().find(|(k, _)| *k == kind) {
+        *nb += 1;
+    } else {
+        counts.push((kind, 1));
+    }
+}
+
+/// Build a `Circuit`
+///
+/// `gates`: (type, output wire) of each Gate, in topological order
+/// `outputs`: their ids MUST be contiguous, in order(eg `[3, 4]`); cf `Metadata::convert_gate_id_to_outputs_index`
+///
+/// # Errors
+/// - `InvalidCircuit` if `outputs` is empty, or NOT contiguous
+/// - `SkcdParserError` if circuit-types-rs rejects the result, or if it does NOT match the given parts
+///   ie the postcard layout of `Circuit` changed(cf `CircuitLayout`)
+///
+pub(crate) fn build_circuit(
+    inputs: Vec<WireRef>,
+    gates: Vec<(GateType, WireRef)>,
+    outputs: Vec<WireRef>,
+    config: Option<DisplayConfig>,
+) -> Result<Circuit, InterstellarError> {
+    let first_output_id = outputs
+        .first()
+        .ok_or(InterstellarError::InvalidCircuit {
+            err: CircuitValidationError::NoOutputs,
+        })?
+        .id;
+    if let Some((idx, output)) = outputs
+        .iter()
+        .enumerate()
+        .find(|(idx, output)| output.id != first_output_id + idx)
+    {
+        return Err(InterstellarError::InvalidCircuit {
+            err: CircuitValidationError::OutputsNotContiguous {
+                idx,
+                wire_id: output.id,
+            },
+        });
+    }
+
+    let mut gates_unary = Vec::new();
+    let mut gates_binary = Vec::new();
+    let mut gates_constant = (0, 0);
+    for (gate_type, _) in &gates {
+        match gate_type {
+            GateType::Binary { gate_type, .. } => count(&mut gates_binary, gate_type.clone()),
+            GateType::Unary { gate_type, .. } => count(&mut gates_unary, gate_type.clone()),
+            GateType::Constant { value: false } => gates_constant.0 += 1,
+            GateType::Constant { value: true } => gates_constant.1 += 1,
+        }
+    }
+
+    let mut wires: Vec<WireRef> = inputs
+        .iter()
+        .chain(gates.iter().map(|(_, output)| output))
+        .cloned()
+        .collect();
+    wires.sort_unstable_by_key(|wire| wire.id);
+    let max_gate_id = wires.last().map_or(0, |wire| wire.id);
+
+    let layout = CircuitLayout {
+        metadata: MetadataLayout {
+            outputs_start_end_indexes: (first_output_id, first_output_id + outputs.len() - 1),
+            gates_unary,
+            gates_binary,
+            gates_constant,
+            max_gate_id,
+        },
+        inputs,
+        outputs,
+        gates: gates
+            .into_iter()
+            .map(|(internal, output)| GateLayout { internal, output })
+            .collect(),
+        wires,
+        config,
+    };
+
+    let buf = postcard::to_allocvec(&layout)
+        .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })?;
+    let circuit = circuit_types_rs::deserialize_from_buffer(&buf)
+        .map_err(|_e| InterstellarError::SkcdParserError)?;
+
+    if matches_layout(&circuit, &layout) {
+        Ok(circuit)
+    } else {
+        Err(InterstellarError::SkcdParserError)
+    }
+}
+
+/// Whether `circuit` has the parts `layout` was serialized from.
+fn matches_layout(circuit: &Circuit, layout: &CircuitLayout) -> bool {
+    circuit.get_inputs() == layout.inputs.as_slice()
+        && circuit.get_outputs() == layout.outputs.as_slice()
+        && circuit.get_nb_wires() == layout.wires.len()
+        && circuit.get_metadata().get_max_gate_id() == layout.metadata.max_gate_id
+        && circuit.get_config().is_some() == layout.config.is_some()
+        && circuit.get_gates().len() == layout.gates.len()
+        && circuit
+            .get_gates()
+            .iter()
+            .zip(&layout.gates)
+            .all(|(gate, gate_layout)| {
+                gate.get_output() == &gate_layout.output
+                    && same_gate_type(gate.get_type(), &gate_layout.internal)
+            })
+}
+
+fn same_gate_type(a: &GateType, b: &GateType) -> bool {
+    match (a, b) {
+        (
+            GateType::Binary {
+                gate_type,
+                input_a,
+                input_b,
+            },
+            GateType::Binary {
+                gate_type: other_gate_type,
+                input_a: other_input_a,
+                input_b: other_input_b,
+            },
+        ) => gate_type == other_gate_type && input_a == other_input_a && input_b == other_input_b,
+        (
+            GateType::Unary { gate_type, input_a },
+            GateType::Unary {
+                gate_type: other_gate_type,
+                input_a: other_input_a,
+            },
+        ) => gate_type == other_gate_type && input_a == other_input_a,
+        (GateType::Constant { value }, GateType::Constant { value: other_value }) => {
+            value == other_value
+        }
+        _ => false,
+    }
+}
+
+/// Same postcard layout as `DisplayConfig`; each input is `(type, length)`
+#[derive(Serialize)]
+struct DisplayConfigLayout {
+    garbler_inputs: Vec<(GarblerInputsType, u32)>,
+    evaluator_inputs: Vec<(EvaluatorInputsType, u32)>,
+    width: u32,
+    height: u32,
+}
+
+/// `DisplayConfig::builder()`, eg to build a "display circuit" with `build_circuit`
+/// instead of a .skcd fixture.
+pub trait DisplayConfigWithBuilder {
+    fn builder() -> DisplayConfigBuilder;
+}
+
+impl DisplayConfigWithBuilder for DisplayConfig {
+    fn builder() -> DisplayConfigBuilder {
+        DisplayConfigBuilder::default()
+    }
+}
+
+/// cf `DisplayConfigWithBuilder`
+/// The `add_*_input` take the `length` of the input ie its number of bits.
+/// The garbler inputs are ordered like in the .skcd(buf, then segments, then watermark; cf
+/// `prepare_display_garbler_inputs_bits`) whatever the order of the calls;
+/// the evaluator inputs are in the order of the calls.
+#[derive(Default)]
+pub struct DisplayConfigBuilder {
+    width: u32,
+    height: u32,
+    garbler_inputs: Vec<(GarblerInputsType, u32)>,
+    evaluator_inputs: Vec<(EvaluatorInputsType, u32)>,
+}
+
+impl DisplayConfigBuilder {
+    #[must_use]
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = width;
+        self
+    }
+
+    #[must_use]
+    pub fn height(mut self, height: u32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// MUST be `width * height` to be prepared by `prepare_display_garbler_inputs_bits`
+    #[must_use]
+    pub fn add_watermark_input(mut self, length: u32) -> Self {
+        self.garbler_inputs
+            .push((GarblerInputsType::Watermark, length));
+        self
+    }
+
+    /// 7 bits per digit
+    #[must_use]
+    pub fn add_segments_input(mut self, length: u32) -> Self {
+        self.garbler_inputs
+            .push((GarblerInputsType::SevenSegments, length));
+        self
+    }
+
+    /// The "buf" gate is always one bit; cf `GarblerInputsInvalidBufLength`
+    #[must_use]
+    pub fn add_buf_input(mut self) -> Self {
+        self.garbler_inputs.push((GarblerInputsType::Buf, 1));
+        self
+    }
+
+    #[must_use]
+    pub fn add_rnd_evaluator_input(mut self, length: u32) -> Self {
+        self.evaluator_inputs
+            .push((EvaluatorInputsType::Rnd, length));
+        self
+    }
+
+    /// # Errors
+    /// - `SkcdParserError` if circuit-types-rs rejects the result, or if it does NOT match
+    ///   ie the postcard layout of `DisplayConfig` changed(cf `DisplayConfigLayout`)
+    ///
+    pub fn build(self) -> Result<DisplayConfig, InterstellarError> {
+        let mut layout = DisplayConfigLayout {
+            garbler_inputs: self.garbler_inputs,
+            evaluator_inputs: self.evaluator_inputs,
+            width: self.width,
+            height: self.height,
+        };
+        layout
+            .garbler_inputs
+            .sort_by_key(|(input_type, _)| garbler_input_order(input_type));
+
+        let buf = postcard::to_allocvec(&layout)
+            .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })?;
+        let config: DisplayConfig =
+            postcard::from_bytes(&buf).map_err(|_e| InterstellarError::SkcdParserError)?;
+
+        if config_matches_layout(&config, &layout) {
+            Ok(config)
+        } else {
+            Err(InterstellarError::SkcdParserError)
+        }
+    }
+}
+
+/// The order of the garbler inputs in the .skcd
+fn garbler_input_order(input_type: &GarblerInputsType) -> u8 {
+    match input_type {
+        GarblerInputsType::Buf => 0,
+        GarblerInputsType::SevenSegments => 1,
+        GarblerInputsType::Watermark => 2,
+    }
+}
+
+/// cf `matches_layout`
+fn config_matches_layout(config: &DisplayConfig, layout: &DisplayConfigLayout) -> bool {
+    u64::from(config.width) == u64::from(layout.width)
+        && u64::from(config.height) == u64::from(layout.height)
+        && config.garbler_inputs.len() == layout.garbler_inputs.len()
+        && config
+            .garbler_inputs
+            .iter()
+            .zip(&layout.garbler_inputs)
+            .all(|(garbler_input, (input_type, length))| {
+                garbler_input_order(&garbler_input.r#type) == garbler_input_order(input_type)
+                    && u64::from(garbler_input.length) == u64::from(*length)
+            })
+        && config.evaluator_inputs.len() == layout.evaluator_inputs.len()
+        && config
+            .evaluator_inputs
+            .iter()
+            .zip(&layout.evaluator_inputs)
+            .all(|(evaluator_input, (input_type, length))| {
+                matches!(
+                    (&evaluator_input.r#type, input_type),
+                    (EvaluatorInputsType::Rnd, EvaluatorInputsType::Rnd)
+                ) && u64::from(evaluator_input.length) == u64::from(*length)
+            })
+}
+
+/// Build a random(but valid) "generic" circuit: `num_inputs` inputs then `num_gates` gates of random types,
+/// each one reading random previous wires. The outputs are all the gates NOT read by another one.
+/// The ids are the standard ones: inputs first, then the outputs(contiguous), then the other gates.
+///
+/// Meant for property tests/fuzzing; eg garble+eval MUST match `eval_plain`.
+///
+/// # Errors
+/// - `InvalidCircuit` if `num_gates` is 0 ie there would be no output
+///
+#[cfg(any(test, feature = "fuzz"))]
+pub fn random_circuit<R: rand::RngCore>(
+    rng: &mut R,
+    num_inputs: usize,
+    num_gates: usize,
+) -> Result<Circuit, InterstellarError> {
+    use rand::Rng;
+
+    const KINDS_BINARY: [KindBinary; 6] = [
+        KindBinary::XOR,
+        KindBinary::XNOR,
+        KindBinary::AND,
+        KindBinary::NAND,
+        KindBinary::OR,
+        KindBinary::NOR,
+    ];
+
+    // first pass: the wires are numbered in topological order ie inputs then gates
+    let mut gates = Vec::with_capacity(num_gates);
+    let mut is_read = alloc::vec![false; num_inputs + num_gates];
+    for nb_wires in num_inputs..num_inputs + num_gates {
+        let mut random_wire = |rng: &mut R| {
+            let id = rng.gen_range(0..nb_wires);
+            is_read[id] = true;
+            WireRef { id }
+        };
+        // NOTE: constants are rare on purpose: they make everything downstream constant
+        let gate_type = match rng.gen_range(0..16) {
+            _ if nb_wires == 0 => GateType::Constant { value: rng.gen() },
+            0 => GateType::Constant { value: rng.gen() },
+            1..=3 => GateType::Unary {
+                gate_type: if rng.gen() {
+                    KindUnary::INV
+                } else {
+                    KindUnary::BUF
+                },
+                input_a: random_wire(rng),
+            },
+            // a Binary gate reading twice the same wire is useless(and not what real circuits do)
+            _ if nb_wires == 1 => GateType::Unary {
+                gate_type: KindUnary::INV,
+                input_a: random_wire(rng),
+            },
+            _ => {
+                let input_a = random_wire(rng);
+                let mut input_b = random_wire(rng);
+                while input_b.id == input_a.id {
+                    input_b = random_wire(rng);
+                }
+                GateType::Binary {
+                    gate_type: KINDS_BINARY[rng.gen_range(0..KINDS_BINARY.len())].clone(),
+                    input_a,
+                    input_b,
+                }
+            }
+        };
+        gates.push(gate_type);
+    }
+
+    // second pass: renumber the outputs right after the inputs
+    let nb_outputs = is_read[num_inputs..]
+        .iter()
+        .filter(|is_read| !**is_read)
+        .count();
+    let mut new_ids = Vec::with_capacity(num_inputs + num_gates);
+    new_ids.extend(0..num_inputs);
+    let (mut next_output_id, mut next_other_id) = (num_inputs, num_inputs + nb_outputs);
+    for is_read in &is_read[num_inputs..] {
+        let next_id = if *is_read {
+            &mut next_other_id
+        } else {
+            &mut next_output_id
+        };
+        new_ids.push(*next_id);
+        *next_id += 1;
+    }
+    let renumber = |wire: &WireRef| WireRef {
+        id: new_ids[wire.id],
+    };
+
+    let gates = gates
+        .into_iter()
+        .zip(num_inputs..)
+        .map(|(gate_type, id)| {
+            let gate_type = match gate_type {
+                GateType::Binary {
+                    gate_type,
+                    input_a,
+                    input_b,
+                } => GateType::Binary {
+                    gate_type,
+                    input_a: renumber(&input_a),
+                    input_b: renumber(&input_b),
+                },
+                GateType::Unary { gate_type, input_a } => GateType::Unary {
+                    gate_type,
+                    input_a: renumber(&input_a),
+                },
+                GateType::Constant { value } => GateType::Constant { value },
+            };
+            (gate_type, WireRef { id: new_ids[id] })
+        })
+        .collect();
+
+    build_circuit(
+        (0..num_inputs).map(|id| WireRef { id }).collect(),
+        gates,
+        (num_inputs..num_inputs + nb_outputs)
+            .map(|id| WireRef { id })
+            .collect(),
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_stats::gate_type_histogram;
+    use crate::new_garbling_scheme::circuit_for_eval::CircuitForEval;
+    use crate::validate_circuit;
+    use alloc::vec;
+    use rand::SeedableRng;
+
+    /// Rebuild the full adder from its parts: it MUST be the same `Circuit` as the parsed .skcd,
+    /// including the `Metadata`(which has no getter) through `CircuitForEval`.
+    #[test]
+    fn test_build_full_adder_matches_skcd() {
+        let skcd = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        let built = build_circuit(
+            skcd.get_inputs().to_vec(),
+            skcd.get_gates()
+                .iter()
+                .map(|gate| (gate.get_type().clone(), gate.get_output().clone()))
+                .collect(),
+            skcd.get_outputs().to_vec(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(CircuitForEval::from(&built), CircuitForEval::from(&skcd));
+        assert_eq!(built.get_outputs(), skcd.get_outputs());
+        assert_eq!(gate_type_histogram(&built), gate_type_histogram(&skcd));
+    }
+
+    #[test]
+    fn test_build_circuit_outputs_not_contiguous() {
+        let built = build_circuit(
+            vec![WireRef { id: 0 }],
+            vec![
+                (
+                    GateType::Unary {
+                        gate_type: KindUnary::INV,
+                        input_a: WireRef { id: 0 },
+                    },
+                    WireRef { id: 1 },
+                ),
+                (
+                    GateType::Unary {
+                        gate_type: KindUnary::INV,
+                        input_a: WireRef { id: 1 },
+                    },
+                    WireRef { id: 2 },
+                ),
+            ],
+            vec![WireRef { id: 2 }, WireRef { id: 1 }],
+            None,
+        );
+
+        assert_eq!(
+            built.err(),
+            Some(InterstellarError::InvalidCircuit {
+                err: CircuitValidationError::OutputsNotContiguous { idx: 1, wire_id: 1 }
+            })
+        );
+    }
+
+    /// What `build_circuit` would return if the layout of `Circuit` drifted
+    #[test]
+    fn test_matches_layout_detects_a_different_circuit() {
+        let inv = |input: usize, output: usize| GateLayout {
+            internal: GateType::Unary {
+                gate_type: KindUnary::INV,
+                input_a: WireRef { id: input },
+            },
+            output: WireRef { id: output },
+        };
+        let layout = |gates: Vec<GateLayout>| CircuitLayout {
+            inputs: vec![WireRef { id: 0 }],
+            outputs: vec![WireRef { id: 2 }],
+            gates,
+            wires: (0..3).map(|id| WireRef { id }).collect(),
+            metadata: MetadataLayout {
+                outputs_start_end_indexes: (2, 2),
+                gates_unary: vec![(KindUnary::INV, 2)],
+                gates_binary: vec![],
+                gates_constant: (0, 0),
+                max_gate_id: 2,
+            },
+            config: None,
+        };
+        let circuit = build_circuit(
+            vec![WireRef { id: 0 }],
+            vec![
+                (inv(0, 1).internal, WireRef { id: 1 }),
+                (inv(1, 2).internal, WireRef { id: 2 }),
+            ],
+            vec![WireRef { id: 2 }],
+            None,
+        )
+        .unwrap();
+
+        assert!(matches_layout(
+            &circuit,
+            &layout(vec![inv(0, 1), inv(1, 2)])
+        ));
+        assert!(!matches_layout(
+            &circuit,
+            &layout(vec![inv(0, 1), inv(0, 2)])
+        ));
+        assert!(!matches_layout(&circuit, &layout(vec![inv(0, 1)])));
+    }
+
+    #[test]
+    fn test_random_circuit_is_valid() {
+        let mut rng = rand_chacha::ChaChaRng::seed_from_u64(42);
+
+        for (num_inputs, num_gates) in [(0, 1), (1, 1), (2, 10), (8, 200), (16, 1000)] {
+            let circuit = random_circuit(&mut rng, num_inputs, num_gates).unwrap();
+
+            assert_eq!(circuit.get_nb_inputs(), num_inputs);
+            assert_eq!(circuit.get_gates().len(), num_gates);
+            assert!(circuit.get_nb_outputs() >= 1);
+            assert_eq!(circuit.get_nb_wires(), num_inputs + num_gates);
+            // some inputs MAY be left unused; that is only a warning
+            assert!(matches!(
+                validate_circuit(&circuit),
+                Ok(()) | Err(CircuitValidationError::UnusedInputs { .. })
+            ));
+        }
+
+        assert!(random_circuit(&mut rng, 2, 0).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! A garbler typically fetches(eg from IPFS) and parses the same popular circuits over and over.
+//! `CircuitCache` keeps the last used parsed `Circuit`s around, keyed by their CID.
+
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use circuit_types_rs::Circuit;
+
+use crate::{parse_skcd, InterstellarError};
+
+/// Size-bounded LRU cache of parsed `Circuit`s.
+///
+/// NOTE: this is meant for a "small" number of entries(ie the popular circuits)
+/// so a linear scan is good enough.
+pub struct CircuitCache {
+    max_entries: usize,
+    /// Ordered from "least recently used" to "most recently used"
+    entries: VecDeque<(String, Circuit)>,
+}
+
+impl CircuitCache {
+    /// NOTE: `max_entries` is at least 1
+    #[must_use]
+    pub fn new(max_entries: usize) -> Self {
+        let max_entries = max_entries.max(1);
+        Self {
+            max_entries,
+            entries: VecDeque::with_capacity(max_entries),
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[must_use]
+    pub fn contains(&self, cid: &str) -> bool {
+        self.entries.iter().any(|(entry_cid, _)| entry_cid == cid)
+    }
+
+    /// Return the `Circuit` for `cid`.
+    /// `fetch` is ONLY called on a cache miss; its result is then parsed with `parse_skcd`
+    /// and the least recently used entry is evicted if needed.
+    ///
+    /// # Errors
+    /// - whatever `fetch` returns
+    /// - `SkcdParserError`(converted into `E`) if the fetched buffer is not a valid .skcd
+    ///
+    pub fn get_or_parse<E: From<InterstellarError>>(
+        &mut self,
+        cid: &str,
+        fetch: impl FnOnce() -> Result<Vec<u8>, E>,
+    ) -> Result<&Circuit, E> {
+        if let Some(idx) = self
+            .entries
+            .iter()
+            .position(|(entry_cid, _)| entry_cid == cid)
+        {
+            // move it to the "most recently used" end
+            if let Some(entry) = self.entries.remove(idx) {
+                self.entries.push_back(entry);
+            }
+        } else {
+            let circuit = parse_skcd(&fetch()?)?;
+
+            while self.entries.len() >= self.max_entries {
+                self.entries.pop_front();
+            }
+            self.entries.push_back((cid.to_string(), circuit));
+        }
+
+        Ok(&self.entries[self.entries.len() - 1].1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const ADDER_SKCD: &[u8] = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+
+    #[test]
+    fn test_get_or_parse_second_call_does_not_fetch() {
+        let mut cache = CircuitCache::new(2);
+        let mut nb_fetch = 0;
+
+        let nb_wires = cache
+            .get_or_parse("adder", || -> Result<_, InterstellarError> {
+                nb_fetch += 1;
+                Ok(ADDER_SKCD.to_vec())
+            })
+            .unwrap()
+            .get_nb_wires();
+        let circuit = cache
+            .get_or_parse("adder", || -> Result<_, InterstellarError> {
+                nb_fetch += 1;
+                Ok(ADDER_SKCD.to_vec())
+            })
+            .unwrap();
+
+        assert_eq!(nb_fetch, 1);
+        assert_eq!(circuit.get_nb_wires(), nb_wires);
+        assert_eq!(circuit.get_nb_inputs(), 3);
+    }
+
+    #[test]
+    fn test_get_or_parse_evicts_least_recently_used() {
+        let mut cache = CircuitCache::new(2);
+        let fetch = || -> Result<_, InterstellarError> { Ok(ADDER_SKCD.to_vec()) };
+
+        cache.get_or_parse("a", fetch).unwrap();
+        cache.get_or_parse("b", fetch).unwrap();
+        // "a" is now the most recently used
+        cache.get_or_parse("a", fetch).unwrap();
+        cache.get_or_parse("c", fetch).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains("a"));
+        assert!(!cache.contains("b"));
+        assert!(cache.contains("c"));
+    }
+
+    #[test]
+    fn test_get_or_parse_errors_are_not_cached() {
+        let mut cache = CircuitCache::new(2);
+
+        assert!(matches!(
+            cache.get_or_parse("bad", || -> Result<_, InterstellarError> { Ok(vec![42]) }),
+            Err(InterstellarError::SkcdParserError)
+        ));
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+//! When regenerating the .skcd fixtures, we want to confirm that only the intended changes occurred.
+//! `diff_circuits` compares two parsed `Circuit`s using the wire ids(ie the Gate's output) as the join key.
+
+use alloc::vec::Vec;
+
+use circuit_types_rs::{Circuit, GateType};
+
+use crate::new_garbling_scheme::{gate_truth_table, GateTruthTable};
+
+/// Result of `diff_circuits(a, b)`; all the `Vec` contain gate ids and are sorted.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct CircuitDiff {
+    /// Gates present in `b` but not in `a`
+    pub added_gates: Vec<usize>,
+    /// Gates present in `a` but not in `b`
+    pub removed_gates: Vec<usize>,
+    /// Gates present in both, but with a different type or different inputs
+    pub changed_gates: Vec<usize>,
+    /// Number of inputs of (`a`, `b`)
+    pub nb_inputs: (usize, usize),
+    /// Number of outputs of (`a`, `b`)
+    pub nb_outputs: (usize, usize),
+}
+
+impl CircuitDiff {
+    /// True if both circuits have the same gates, inputs and outputs counts
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added_gates.is_empty()
+            && self.removed_gates.is_empty()
+            && self.changed_gates.is_empty()
+            && self.nb_inputs.0 == self.nb_inputs.1
+            && self.nb_outputs.0 == self.nb_outputs.1
+    }
+}
+
+/// What we compare for a given gate id: its truth table and its inputs
+#[derive(Debug, PartialEq, Clone)]
+struct GateSignature {
+    truth_table: GateTruthTable,
+    inputs: Vec<usize>,
+}
+
+/// Compare two `Circuit`s, cf `CircuitDiff`
+#[must_use]
+pub fn diff_circuits(a: &Circuit, b: &Circuit) -> CircuitDiff {
+    let mut diff = diff_gates(gates_signatures(a), gates_signatures(b));
+    diff.nb_inputs = (a.get_nb_inputs(), b.get_nb_inputs());
+    diff.nb_outputs = (a.get_nb_outputs(), b.get_nb_outputs());
+
+    diff
+}
+
+fn gates_signatures(circuit: &Circuit) -> hashbrown::HashMap<usize, GateSignature> {
+    circuit
+        .get_gates()
+        .iter()
+        .map(|gate| {
+            let inputs = match gate.get_type() {
+                GateType::Binary {
+                    input_a, input_b, ..
+                } => alloc::vec![input_a.id, input_b.id],
+                GateType::Unary { input_a, .. } => alloc::vec![input_a.id],
+                GateType::Constant { .. } => alloc::vec![],
+            };
+
+            (
+                gate.get_id(),
+                GateSignature {
+                    truth_table: gate_truth_table(gate.get_type()),
+                    inputs,
+                },
+            )
+        })
+        .collect()
+}
+
+/// NOTE: only fills the gates part of `CircuitDiff`
+fn diff_gates(
+    a: hashbrown::HashMap<usize, GateSignature>,
+    b: hashbrown::HashMap<usize, GateSignature>,
+) -> CircuitDiff {
+    let mut diff = CircuitDiff::default();
+
+    for (id, signature_a) in &a {
+        match b.get(id) {
+            None => diff.removed_gates.push(*id),
+            Some(signature_b) if signature_b != signature_a => diff.changed_gates.push(*id),
+            Some(_) => {}
+        }
+    }
+    diff.added_gates = b
+        .keys()
+        .filter(|id| !a.contains_key(*id))
+        .copied()
+        .collect();
+
+    diff.added_gates.sort_unstable();
+    diff.removed_gates.sort_unstable();
+    diff.changed_gates.sort_unstable();
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const ADDER_SKCD: &[u8] = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+
+    #[test]
+    fn test_diff_circuits_same() {
+        let a = crate::parse_skcd(ADDER_SKCD).unwrap();
+        let b = crate::parse_skcd(ADDER_SKCD).unwrap();
+
+        let diff = diff_circuits(&a, &b);
+
+        assert!(diff.is_empty(), "{diff:?}");
+        assert_eq!(diff.nb_inputs, (3, 3));
+        assert_eq!(diff.nb_outputs, (2, 2));
+    }
+
+    #[test]
+    fn test_diff_gates_one_gate_type_flipped() {
+        let a = gates_signatures(&crate::parse_skcd(ADDER_SKCD).unwrap());
+        let mut b = a.clone();
+        let flipped_id = *a
+            .iter()
+            .find(|(_, signature)| matches!(signature.truth_table, GateTruthTable::Binary(_)))
+            .unwrap()
+            .0;
+        let signature = b.get_mut(&flipped_id).unwrap();
+        let GateTruthTable::Binary(bits) = signature.truth_table else {
+            unreachable!()
+        };
+        signature.truth_table = GateTruthTable::Binary(bits.map(|bit| !bit));
+
+        let diff = diff_gates(a, b);
+
+        assert_eq!(diff.changed_gates, vec![flipped_id]);
+        assert!(diff.added_gates.is_empty());
+        assert!(diff.removed_gates.is_empty());
+    }
+
+    #[test]
+    fn test_diff_gates_added_removed() {
+        let signature = GateSignature {
+            truth_table: GateTruthTable::Unary([true, false]),
+            inputs: vec![0],
+        };
+        let a = [(1, signature.clone()), (2, signature.clone())]
+            .into_iter()
+            .collect();
+        let b = [(2, signature.clone()), (3, signature)]
+            .into_iter()
+            .collect();
+
+        let diff = diff_gates(a, b);
+
+        assert_eq!(diff.added_gates, vec![3]);
+        assert_eq!(diff.removed_gates, vec![1]);
+        assert!(diff.changed_gates.is_empty());
+    }
+}
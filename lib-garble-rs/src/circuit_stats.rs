@@ -0,0 +1,261 @@
+//! Statistics on a parsed `Circuit`, eg to decide which optimizations are worth it
+//! (Free-XOR only helps if XORs dominate).
+//!
+//! NOTE: this works on the `Circuit` ie garbler-side; the gate types are NOT sent to
+//! the evaluators(cf `circuit_for_eval`).
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use arrayvec::ArrayVec;
+
+use circuit_types_rs::{Circuit, Gate, GateType, WireRef};
+use hashbrown::HashMap;
+
+use crate::new_garbling_scheme::FreeXorWires;
+use crate::{is_gate_type_free, GarblingMode};
+
+/// Key used for the `GateType::Constant` gates
+pub const GATE_TYPE_CONSTANT: &str = "CONSTANT";
+
+/// Number of gates per type.
+/// The keys are stable: the name of the `KindBinary`/`KindUnary`(eg "XOR", "AND", "INV")
+/// and `GATE_TYPE_CONSTANT` for the constant gates.
+/// The counts sum to the number of gates.
+#[must_use]
+pub fn gate_type_histogram(circuit: &Circuit) -> BTreeMap<String, usize> {
+    let mut histogram = BTreeMap::new();
+
+    for gate in circuit.get_gates() {
+        let key = match gate.get_type() {
+            GateType::Binary { gate_type, .. } => format!("{gate_type:?}"),
+            GateType::Unary { gate_type, .. } => format!("{gate_type:?}"),
+            GateType::Constant { .. } => GATE_TYPE_CONSTANT.to_string(),
+        };
+        *histogram.entry(key).or_insert(0) += 1;
+    }
+
+    histogram
+}
+
+/// Number of gates which will have a garbled representation(ie a `Delta`) once garbled with `mode`;
+/// cf `is_gate_type_free`.
+/// With `GarblingMode::FreeXor` a XOR/XNOR is only free if both its inputs are on the Free-XOR delta
+/// ie NOT downstream of a materialized gate; the same rule as the garbler.
+#[must_use]
+pub fn materialized_gate_count(circuit: &Circuit, mode: GarblingMode) -> usize {
+    let mut free_xor_wires = FreeXorWires::new(circuit.get_inputs());
+    circuit
+        .get_gates()
+        .iter()
+        .filter(|gate| match mode {
+            GarblingMode::Standard => !is_gate_type_free(gate.get_type(), mode),
+            GarblingMode::FreeXor => {
+                // NOTE: called for every gate, to follow the wires on the delta
+                let is_free_xor = free_xor_wires.insert_gate(gate);
+                !is_free_xor && !is_gate_type_free(gate.get_type(), GarblingMode::Standard)
+            }
+        })
+        .count()
+}
+
+/// All the wires referenced by `gate`: its inputs(in order) then its output.
+/// The same for every `GateType`, so callers do not have to match on it;
+/// a `GateType::Constant` only references its output.
+/// NOTE: does NOT allocate, so it can be called for each Gate in a hot loop.
+#[must_use]
+pub fn gate_referenced_wires(gate: &Gate) -> ArrayVec<WireRef, 3> {
+    let mut wires = ArrayVec::new();
+    match gate.get_type() {
+        GateType::Binary {
+            input_a, input_b, ..
+        } => {
+            wires.push(input_a.clone());
+            wires.push(input_b.clone());
+        }
+        GateType::Unary { input_a, .. } => wires.push(input_a.clone()),
+        GateType::Constant { .. } => {}
+    }
+    wires.push(gate.get_output().clone());
+
+    wires
+}
+
+/// The outputs driven directly by a `GateType::Constant`, with their value; in outputs order.
+/// These are hardwired ie their value is public whatever the inputs.
+#[must_use]
+pub fn constant_outputs(circuit: &Circuit) -> Vec<(WireRef, bool)> {
+    let constant_wires: HashMap<usize, bool> = circuit
+        .get_gates()
+        .iter()
+        .filter_map(|gate| match gate.get_type() {
+            GateType::Constant { value } => Some((gate.get_output().id, *value)),
+            GateType::Binary { .. } | GateType::Unary { .. } => None,
+        })
+        .collect();
+
+    circuit
+        .get_outputs()
+        .iter()
+        .filter_map(|output| {
+            constant_wires
+                .get(&output.id)
+                .map(|value| (output.clone(), *value))
+        })
+        .collect()
+}
+
+/// The "cone" of `output`: the indices(in `get_gates`) of the gates in its transitive fan-in,
+/// including the one driving it; in topological order.
+/// Empty if `output` is not driven by a Gate(eg an input directly wired to an output).
+/// Useful to debug a wrong output eg pixel: only those gates can be the culprit.
+#[must_use]
+pub fn output_cone(circuit: &Circuit, output: &WireRef) -> Vec<usize> {
+    let mut live_wires: hashbrown::HashSet<usize> = hashbrown::HashSet::new();
+    live_wires.insert(output.id);
+
+    // same backward walk as `evaluator_input_influence`
+    let mut cone = Vec::new();
+    for (gate_idx, gate) in circuit.get_gates().iter().enumerate().rev() {
+        if live_wires.contains(&gate.get_output().id) {
+            live_wires.extend(gate_referenced_wires(gate).iter().map(|wire| wire.id));
+            cone.push(gate_idx);
+        }
+    }
+    cone.reverse();
+
+    cone
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use circuit_types_rs::{KindBinary, KindUnary};
+
+    #[test]
+    fn test_gate_type_histogram_full_adder() {
+        let circuit = crate::parse_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        let histogram = gate_type_histogram(&circuit);
+
+        // a full adder is mostly XOR(or XNOR depending on the synthesis)
+        assert!(
+            histogram.contains_key("XOR") || histogram.contains_key("XNOR"),
+            "{histogram:?}"
+        );
+        assert!(histogram.values().all(|count| *count > 0));
+        assert_eq!(histogram.values().sum::<usize>(), circuit.get_gates().len());
+    }
+
+    /// the predicate MUST agree with what is actually garbled ie the `Delta` in `F`; in both modes
+    #[test]
+    fn test_materialized_gate_count_matches_garbled() {
+        use rand::SeedableRng;
+
+        let mut rng = rand_chacha::ChaChaRng::seed_from_u64(42);
+        for circuit in [
+            crate::parse_skcd(include_bytes!(
+                "../examples/data/result_abc_full_adder.postcard.bin"
+            ))
+            .unwrap(),
+            crate::parse_skcd(include_bytes!(
+                "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+            ))
+            .unwrap(),
+            Circuit::new_test_circuit(KindBinary::XOR),
+            Circuit::new_test_circuit(KindBinary::AND),
+            Circuit::new_test_circuit_unary(KindUnary::INV),
+            Circuit::new_test_circuit_constant(false),
+            crate::random_circuit(&mut rng, 8, 200).unwrap(),
+        ] {
+            let garb =
+                crate::garble_circuit_with_delta(&circuit, [42u8; crate::KAPPA_BYTES], &mut rng)
+                    .unwrap();
+            assert_eq!(
+                garb.garbled.nb_materialized_gates(),
+                materialized_gate_count(&circuit, GarblingMode::FreeXor)
+            );
+
+            let expected = materialized_gate_count(&circuit, GarblingMode::Standard);
+            let garb = crate::garble_circuit(circuit, Some(42)).unwrap();
+            assert_eq!(garb.garbled.nb_materialized_gates(), expected);
+        }
+
+        // the same XOR: free ONLY with Free-XOR
+        let circuit = Circuit::new_test_circuit(KindBinary::XOR);
+        assert_eq!(materialized_gate_count(&circuit, GarblingMode::FreeXor), 0);
+        assert_eq!(materialized_gate_count(&circuit, GarblingMode::Standard), 1);
+    }
+
+    #[test]
+    fn test_gate_referenced_wires() {
+        for (circuit, expected_len) in [
+            (Circuit::new_test_circuit(KindBinary::AND), 3),
+            (Circuit::new_test_circuit_unary(KindUnary::INV), 2),
+            (Circuit::new_test_circuit_constant(true), 1),
+        ] {
+            let gate = &circuit.get_gates()[0];
+
+            let wires = gate_referenced_wires(gate);
+
+            assert_eq!(wires.len(), expected_len);
+            assert_eq!(wires.last(), Some(gate.get_output()));
+        }
+    }
+
+    #[test]
+    fn test_constant_outputs() {
+        let circuit = Circuit::new_test_circuit_constant(true);
+
+        assert_eq!(
+            constant_outputs(&circuit),
+            vec![(circuit.get_outputs()[0].clone(), true)]
+        );
+        let circuit = Circuit::new_test_circuit_constant(false);
+        assert_eq!(
+            constant_outputs(&circuit),
+            vec![(circuit.get_outputs()[0].clone(), false)]
+        );
+        assert!(constant_outputs(&Circuit::new_test_circuit(KindBinary::AND)).is_empty());
+    }
+
+    #[test]
+    fn test_output_cone_full_adder() {
+        let circuit = crate::parse_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let gates = circuit.get_gates();
+        let driver_idx = |output: &WireRef| {
+            gates
+                .iter()
+                .position(|gate| gate.get_output() == output)
+                .unwrap()
+        };
+        // cf `FULL_ADDER_2BITS_ALL_EXPECTED_OUTPUTS`
+        let o_sum = &circuit.get_outputs()[0];
+        let o_carry = &circuit.get_outputs()[1];
+
+        let cone = output_cone(&circuit, o_carry);
+
+        assert!(cone.windows(2).all(|w| w[0] < w[1]), "{cone:?}");
+        assert_eq!(cone.last(), Some(&driver_idx(o_carry)));
+        assert!(!cone.contains(&driver_idx(o_sum)), "{cone:?}");
+        // closed: every gate input is either a circuit input, or driven by a gate in the cone
+        for gate_idx in &cone {
+            let wires = gate_referenced_wires(&gates[*gate_idx]);
+            for wire in &wires[..wires.len() - 1] {
+                assert!(
+                    circuit.get_inputs().contains(wire)
+                        || cone.iter().any(|idx| gates[*idx].get_output() == wire),
+                    "{wire:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,626 @@
+//! Structural checks on a `Circuit`, meant to be run BEFORE garbling it.
+//!
+//! The garbling scheme makes a few assumptions about the shape of the circuit
+//! (eg the inputs are the first wires); a circuit coming from another toolchain
+//! (or a malformed .skcd) may not respect them.
+//! Better to reject those early with a proper error than panic in the middle of `garble`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use snafu::prelude::*;
+
+use circuit_types_rs::{Circuit, DisplayConfig, Gate, GateType, WireRef};
+
+use crate::circuit_builder::build_circuit;
+use crate::circuit_stats::gate_referenced_wires;
+use crate::InterstellarError;
+
+#[derive(Debug, Snafu, PartialEq)]
+pub enum CircuitValidationError {
+    /// The inputs MUST be the first wires of the circuit; ie they MUST occupy exactly the wire ids `0..n`.
+    /// cf `init_internal` and `encoding_internal`
+    InputsNotLeading { idx: usize, wire_id: usize },
+    /// The outputs MUST occupy contiguous wire ids, in order; cf `Metadata::convert_gate_id_to_outputs_index`
+    /// NOTE: only checked when building a `Circuit`(cf `build_circuit`); a parsed one always respects it.
+    OutputsNotContiguous { idx: usize, wire_id: usize },
+    /// A circuit MUST have at least one output; cf `build_circuit`
+    NoOutputs,
+    /// A Gate reads a wire which is neither an input nor written by a Gate; cf `reorder_inputs_first`
+    UndefinedWire { wire_id: usize },
+    /// For "display circuits": the number of inputs declared in the config
+    /// (ie garbler inputs + evaluator inputs) MUST match the number of inputs of the circuit
+    InputConfigMismatch { declared: usize, actual: usize },
+    /// For "display circuits": there MUST be exactly one output per pixel ie `width * height`
+    OutputCountMismatch { outputs: usize, expected: usize },
+    /// Each wire MUST be written once: by the inputs, or by a single Gate.
+    /// Else `garble_internal` silently overwrites the labels of the first Gate, and the results are wrong.
+    DuplicateGateOutput { wire_id: usize },
+    /// WARNING-level: some inputs are never used by any Gate.
+    /// The circuit can still be garbled(those inputs are simply wasted) but it may signal a malformed .skcd.
+    /// NOTE: checked last by `validate_circuit` so callers can safely ignore it.
+    UnusedInputs { inputs: Vec<WireRef> },
+}
+
+/// Check that a `Circuit` can be garbled.
+///
+/// # Errors
+/// - `InputsNotLeading` if the inputs are not the wires `0..n`
+/// - `InputConfigMismatch` if the config does not match the inputs
+/// - `OutputCountMismatch` if the config does not match the outputs
+/// - `DuplicateGateOutput` if two gates(or a Gate and an input) write the same wire
+/// - `UnusedInputs`(warning-level) if some inputs are not used by any Gate; cf `unused_inputs`
+///
+pub fn validate_circuit(circuit: &Circuit) -> Result<(), CircuitValidationError> {
+    check_inputs_leading(circuit.get_inputs())?;
+    check_circuit_input_config(circuit)?;
+    check_circuit_output_config(circuit)?;
+    check_gate_outputs_unique(
+        circuit.get_inputs(),
+        circuit.get_gates().iter().map(|gate| gate.get_output().id),
+    )?;
+
+    let inputs = unused_inputs(circuit);
+    ensure!(inputs.is_empty(), UnusedInputsSnafu { inputs });
+
+    Ok(())
+}
+
+/// Rebuild `circuit` with the standard wire ids: the inputs first(`0..n`, in order), then the
+/// outputs(contiguous, in order), then the other gates(in order).
+/// eg to fix a circuit from another toolchain rejected with `InputsNotLeading`.
+/// The gates(and their order) and the config are kept as is, so the result computes the same function.
+///
+/// # Errors
+/// `InvalidCircuit` with:
+/// - `InputsNotLeading` if an input is listed twice
+/// - `DuplicateGateOutput` if two gates(or a Gate and an input) write the same wire
+/// - `OutputsNotContiguous` if an output is NOT written by a Gate(eg it is an input), or is listed twice
+/// - `UndefinedWire` if a Gate reads a wire which is neither an input nor written by a Gate
+/// - `NoOutputs` if the circuit has no output
+///
+pub fn reorder_inputs_first(circuit: &Circuit) -> Result<Circuit, InterstellarError> {
+    let (inputs, gates, outputs) =
+        renumber_wires(circuit).map_err(|err| InterstellarError::InvalidCircuit { err })?;
+
+    build_circuit(inputs, gates, outputs, circuit.get_config().cloned())
+}
+
+/// The parts of a `Circuit`(inputs, gates, outputs), ready for `build_circuit`
+type CircuitParts = (Vec<WireRef>, Vec<(GateType, WireRef)>, Vec<WireRef>);
+
+/// cf `reorder_inputs_first`
+fn renumber_wires(circuit: &Circuit) -> Result<CircuitParts, CircuitValidationError> {
+    let gates = circuit.get_gates();
+    check_gate_outputs_unique(
+        circuit.get_inputs(),
+        gates.iter().map(|gate| gate.get_output().id),
+    )?;
+
+    // old id -> new id
+    let mut new_ids: hashbrown::HashMap<usize, usize> = hashbrown::HashMap::new();
+    for (idx, input) in circuit.get_inputs().iter().enumerate() {
+        ensure!(
+            new_ids.insert(input.id, idx).is_none(),
+            InputsNotLeadingSnafu {
+                idx,
+                wire_id: input.id
+            }
+        );
+    }
+    let mut next_id = new_ids.len();
+    let gate_outputs: hashbrown::HashSet<usize> =
+        gates.iter().map(|gate| gate.get_output().id).collect();
+    for (idx, output) in circuit.get_outputs().iter().enumerate() {
+        ensure!(
+            gate_outputs.contains(&output.id) && new_ids.insert(output.id, next_id).is_none(),
+            OutputsNotContiguousSnafu {
+                idx,
+                wire_id: output.id
+            }
+        );
+        next_id += 1;
+    }
+    for gate in gates {
+        new_ids.entry(gate.get_output().id).or_insert_with(|| {
+            next_id += 1;
+            next_id - 1
+        });
+    }
+
+    let renumber = |wire: &WireRef| {
+        new_ids
+            .get(&wire.id)
+            .map(|&id| WireRef { id })
+            .ok_or(CircuitValidationError::UndefinedWire { wire_id: wire.id })
+    };
+    let new_gates = gates
+        .iter()
+        .map(|gate| {
+            let gate_type = match gate.get_type() {
+                GateType::Binary {
+                    gate_type,
+                    input_a,
+                    input_b,
+                } => GateType::Binary {
+                    gate_type: gate_type.clone(),
+                    input_a: renumber(input_a)?,
+                    input_b: renumber(input_b)?,
+                },
+                GateType::Unary { gate_type, input_a } => GateType::Unary {
+                    gate_type: gate_type.clone(),
+                    input_a: renumber(input_a)?,
+                },
+                GateType::Constant { value } => GateType::Constant { value: *value },
+            };
+            Ok((gate_type, renumber(gate.get_output())?))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((
+        circuit
+            .get_inputs()
+            .iter()
+            .map(renumber)
+            .collect::<Result<_, _>>()?,
+        new_gates,
+        circuit
+            .get_outputs()
+            .iter()
+            .map(renumber)
+            .collect::<Result<_, _>>()?,
+    ))
+}
+
+/// Return the inputs which are NOT referenced by any Gate
+#[must_use]
+pub fn unused_inputs(circuit: &Circuit) -> Vec<WireRef> {
+    find_unused_inputs(
+        circuit.get_inputs(),
+        circuit.get_gates().iter().flat_map(gate_input_ids),
+    )
+}
+
+/// The ids of the wires `gate` reads, without allocating; cf `gate_referenced_wires`
+fn gate_input_ids(gate: &Gate) -> impl Iterator<Item = usize> {
+    let mut wires = gate_referenced_wires(gate);
+    // the last one is the Gate's output
+    wires.pop();
+    wires.into_iter().map(|wire| wire.id)
+}
+
+/// For each evaluator input(in order): whether it can influence at least one output.
+/// The others are "don't care": eg a client can skip randomizing those `Rnd` inputs.
+/// NOTE: this is purely structural(reachability through the gates); an input which is
+/// reachable but logically masked(eg AND with a constant 0) is still reported as influencing.
+#[must_use]
+pub fn evaluator_input_influence(circuit: &Circuit) -> Vec<bool> {
+    // "generic circuits": all the inputs are evaluator inputs; cf `GarbledCircuit::num_evaluator_inputs`
+    let nb_garbler_inputs = circuit
+        .get_config()
+        .map_or(0, |config| config.num_garbler_inputs() as usize);
+    let evaluator_inputs = circuit
+        .get_inputs()
+        .get(nb_garbler_inputs..)
+        .unwrap_or_default();
+
+    find_inputs_influence(
+        evaluator_inputs,
+        circuit
+            .get_gates()
+            .iter()
+            .map(|gate| (gate_input_ids(gate), gate.get_id())),
+        circuit.get_outputs(),
+    )
+}
+
+/// `gates`: (inputs, output) of each Gate in topological order
+/// Walk them backward from the outputs, marking the inputs of every "live" Gate as live.
+fn find_inputs_influence<GateInputs: IntoIterator<Item = usize>>(
+    inputs: &[WireRef],
+    gates: impl DoubleEndedIterator<Item = (GateInputs, usize)>,
+    outputs: &[WireRef],
+) -> Vec<bool> {
+    let mut live_wires: hashbrown::HashSet<usize> =
+        outputs.iter().map(|output| output.id).collect();
+    for (gate_inputs, gate_output) in gates.rev() {
+        if live_wires.contains(&gate_output) {
+            live_wires.extend(gate_inputs);
+        }
+    }
+
+    inputs
+        .iter()
+        .map(|input| live_wires.contains(&input.id))
+        .collect()
+}
+
+/// `used_wires`: all the wires referenced as a Gate input
+fn find_unused_inputs(inputs: &[WireRef], used_wires: impl Iterator<Item = usize>) -> Vec<WireRef> {
+    let mut is_used = vec![false; inputs.len()];
+    let input_idx_by_id: hashbrown::HashMap<usize, usize> = inputs
+        .iter()
+        .enumerate()
+        .map(|(idx, input)| (input.id, idx))
+        .collect();
+    for wire_id in used_wires {
+        if let Some(&idx) = input_idx_by_id.get(&wire_id) {
+            is_used[idx] = true;
+        }
+    }
+
+    inputs
+        .iter()
+        .zip(is_used)
+        .filter(|(_, is_used)| !is_used)
+        .map(|(input, _)| input.clone())
+        .collect()
+}
+
+/// Called by `parse_skcd`: a mismatch would otherwise only be detected(with a panic!)
+/// much later, when encoding the inputs.
+pub(crate) fn check_circuit_input_config(circuit: &Circuit) -> Result<(), CircuitValidationError> {
+    check_input_config(
+        circuit
+            .get_config()
+            .map(|config| (config.num_garbler_inputs() + config.num_evaluator_inputs()) as usize),
+        circuit.get_nb_inputs(),
+    )
+}
+
+/// `Circuit` is owned by circuit-types-rs; so its extra methods are here
+pub trait CircuitWithConfig {
+    /// Swap the `DisplayConfig` while keeping the same gates, eg to test another split of the
+    /// garbler inputs(watermark/segments) without re-encoding the .skcd.
+    /// This is a new `Circuit`: garble it as usual(eg `garble_circuit`).
+    ///
+    /// # Errors
+    /// - `InvalidCircuit` if the total length of the inputs of `config` does not match the
+    ///   number of inputs of the circuit, or its `width * height` the number of outputs
+    /// - `NotAValidDisplayCircuit` if `config` has a zero width(or height)
+    ///
+    fn with_config(self, config: DisplayConfig) -> Result<Circuit, InterstellarError>;
+}
+
+impl CircuitWithConfig for Circuit {
+    fn with_config(self, config: DisplayConfig) -> Result<Circuit, InterstellarError> {
+        check_display_config(&config, self.get_nb_inputs(), self.get_nb_outputs())
+            .map_err(|err| InterstellarError::InvalidCircuit { err })?;
+        crate::garble::check_display_config_dimensions(&config)?;
+
+        build_circuit(
+            self.get_inputs().to_vec(),
+            self.get_gates()
+                .iter()
+                .map(|gate| (gate.get_type().clone(), gate.get_output().clone()))
+                .collect(),
+            self.get_outputs().to_vec(),
+            Some(config),
+        )
+    }
+}
+
+/// Check a `DisplayConfig` against a circuit with `nb_inputs` inputs and `nb_outputs` outputs;
+/// cf `CircuitWithConfig::with_config`
+fn check_display_config(
+    config: &DisplayConfig,
+    nb_inputs: usize,
+    nb_outputs: usize,
+) -> Result<(), CircuitValidationError> {
+    check_input_config(
+        Some((config.num_garbler_inputs() + config.num_evaluator_inputs()) as usize),
+        nb_inputs,
+    )?;
+    check_output_config(
+        Some(config.width as usize * config.height as usize),
+        nb_outputs,
+    )
+}
+
+/// "generic circuits" (ie no config) are always OK
+fn check_input_config(
+    declared: Option<usize>,
+    actual: usize,
+) -> Result<(), CircuitValidationError> {
+    match declared {
+        Some(declared) if declared != actual => {
+            Err(CircuitValidationError::InputConfigMismatch { declared, actual })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Called by `parse_skcd`: a mismatch would otherwise silently produce wrong-sized frames
+/// that only fail later when rendering them(cf `GarbledCircuit::eval_packed_out`).
+pub(crate) fn check_circuit_output_config(circuit: &Circuit) -> Result<(), CircuitValidationError> {
+    check_output_config(
+        circuit
+            .get_config()
+            .map(|config| config.width as usize * config.height as usize),
+        circuit.get_nb_outputs(),
+    )
+}
+
+/// "generic circuits" (ie no config) are always OK
+fn check_output_config(
+    expected: Option<usize>,
+    outputs: usize,
+) -> Result<(), CircuitValidationError> {
+    match expected {
+        Some(expected) if expected != outputs => {
+            Err(CircuitValidationError::OutputCountMismatch { outputs, expected })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check that the inputs are exactly the wires `0..n`, each one used once.
+/// NOTE: the order does not matter: `InputEncodingSet` is indexed by wire id.
+fn check_inputs_leading(inputs: &[WireRef]) -> Result<(), CircuitValidationError> {
+    let mut seen = vec![false; inputs.len()];
+    for (idx, input) in inputs.iter().enumerate() {
+        match seen.get_mut(input.id) {
+            Some(is_seen) if !*is_seen => *is_seen = true,
+            _ => {
+                return Err(CircuitValidationError::InputsNotLeading {
+                    idx,
+                    wire_id: input.id,
+                })
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// `gate_outputs`: the output wire of each Gate
+fn check_gate_outputs_unique(
+    inputs: &[WireRef],
+    gate_outputs: impl Iterator<Item = usize>,
+) -> Result<(), CircuitValidationError> {
+    let mut written: hashbrown::HashSet<usize> = inputs.iter().map(|input| input.id).collect();
+    for wire_id in gate_outputs {
+        ensure!(
+            written.insert(wire_id),
+            DuplicateGateOutputSnafu { wire_id }
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functionally_equals;
+    use circuit_types_rs::KindUnary;
+
+    #[test]
+    fn test_validate_full_adder_ok() {
+        let circuit = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        assert_eq!(validate_circuit(&circuit), Ok(()));
+        assert!(unused_inputs(&circuit).is_empty());
+    }
+
+    #[test]
+    fn test_evaluator_input_influence_full_adder() {
+        let circuit = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            evaluator_input_influence(&circuit),
+            vec![true; circuit.get_nb_inputs()]
+        );
+    }
+
+    #[test]
+    fn test_find_inputs_influence_disconnected() {
+        let inputs = vec![WireRef { id: 0 }, WireRef { id: 1 }, WireRef { id: 2 }];
+        // 3: AND(0, 1) is the only output; 4: INV(2) is dangling
+        let gates = vec![(vec![0, 1], 3), (vec![2], 4)];
+
+        assert_eq!(
+            find_inputs_influence(&inputs, gates.into_iter(), &[WireRef { id: 3 }]),
+            vec![true, true, false]
+        );
+    }
+
+    #[test]
+    fn test_find_unused_inputs() {
+        let inputs = vec![WireRef { id: 0 }, WireRef { id: 1 }, WireRef { id: 2 }];
+
+        // eg 3: AND(0, 1); 4: INV(3)
+        assert_eq!(
+            find_unused_inputs(&inputs, [0, 1, 3].into_iter()),
+            vec![WireRef { id: 2 }]
+        );
+        assert!(find_unused_inputs(&inputs, [2, 1, 0, 0].into_iter()).is_empty());
+    }
+
+    /// The full adder, with its wire ids shifted(`id -> (id + 3) % 8`): inputs `[3, 4, 5]`, outputs `[6, 7]`
+    /// ie what another toolchain could give. Rejected as is, fixed by `reorder_inputs_first`.
+    #[test]
+    fn test_reorder_inputs_first_full_adder() {
+        let circuit = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let shift = |wire: &WireRef| WireRef {
+            id: (wire.id + 3) % 8,
+        };
+        let shifted = build_circuit(
+            circuit.get_inputs().iter().map(shift).collect(),
+            circuit
+                .get_gates()
+                .iter()
+                .map(|gate| {
+                    let gate_type = match gate.get_type() {
+                        GateType::Binary {
+                            gate_type,
+                            input_a,
+                            input_b,
+                        } => GateType::Binary {
+                            gate_type: gate_type.clone(),
+                            input_a: shift(input_a),
+                            input_b: shift(input_b),
+                        },
+                        GateType::Unary { gate_type, input_a } => GateType::Unary {
+                            gate_type: gate_type.clone(),
+                            input_a: shift(input_a),
+                        },
+                        GateType::Constant { value } => GateType::Constant { value: *value },
+                    };
+                    (gate_type, shift(gate.get_output()))
+                })
+                .collect(),
+            circuit.get_outputs().iter().map(shift).collect(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            validate_circuit(&shifted),
+            Err(CircuitValidationError::InputsNotLeading { idx: 0, wire_id: 3 })
+        );
+
+        let reordered = reorder_inputs_first(&shifted).unwrap();
+
+        assert_eq!(validate_circuit(&reordered), Ok(()));
+        assert_eq!(reordered.get_inputs(), circuit.get_inputs());
+        assert_eq!(reordered.get_outputs(), circuit.get_outputs());
+        assert_eq!(functionally_equals(&reordered, &circuit, 3), Ok(true));
+    }
+
+    #[test]
+    fn test_reorder_inputs_first_errors() {
+        let inv = |id| GateType::Unary {
+            gate_type: KindUnary::INV,
+            input_a: WireRef { id },
+        };
+
+        // the output is an input, not a Gate
+        let circuit = build_circuit(
+            vec![WireRef { id: 0 }],
+            vec![(inv(0), WireRef { id: 1 })],
+            vec![WireRef { id: 0 }],
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            reorder_inputs_first(&circuit).err(),
+            Some(InterstellarError::InvalidCircuit {
+                err: CircuitValidationError::OutputsNotContiguous { idx: 0, wire_id: 0 }
+            })
+        );
+
+        // the Gate reads a wire which is never written
+        let circuit = build_circuit(
+            vec![WireRef { id: 0 }],
+            vec![(inv(5), WireRef { id: 1 })],
+            vec![WireRef { id: 1 }],
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            reorder_inputs_first(&circuit).err(),
+            Some(InterstellarError::InvalidCircuit {
+                err: CircuitValidationError::UndefinedWire { wire_id: 5 }
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_inputs_leading_ok() {
+        let inputs = vec![WireRef { id: 0 }, WireRef { id: 1 }, WireRef { id: 2 }];
+
+        assert_eq!(check_inputs_leading(&inputs), Ok(()));
+    }
+
+    #[test]
+    fn test_check_inputs_leading_interleaved() {
+        let inputs = vec![WireRef { id: 0 }, WireRef { id: 5 }, WireRef { id: 1 }];
+
+        assert_eq!(
+            check_inputs_leading(&inputs),
+            Err(CircuitValidationError::InputsNotLeading { idx: 1, wire_id: 5 })
+        );
+    }
+
+    #[test]
+    fn test_check_inputs_leading_duplicated() {
+        let inputs = vec![WireRef { id: 1 }, WireRef { id: 0 }, WireRef { id: 1 }];
+
+        assert_eq!(
+            check_inputs_leading(&inputs),
+            Err(CircuitValidationError::InputsNotLeading { idx: 2, wire_id: 1 })
+        );
+    }
+
+    #[test]
+    fn test_check_inputs_leading_any_order_ok() {
+        let inputs = vec![WireRef { id: 2 }, WireRef { id: 0 }, WireRef { id: 1 }];
+
+        assert_eq!(check_inputs_leading(&inputs), Ok(()));
+    }
+
+    #[test]
+    fn test_check_gate_outputs_unique() {
+        let inputs = vec![WireRef { id: 0 }, WireRef { id: 1 }, WireRef { id: 2 }];
+
+        // eg 3: AND(0, 1); 4: INV(2)
+        assert_eq!(
+            check_gate_outputs_unique(&inputs, [3, 4].into_iter()),
+            Ok(())
+        );
+        // eg 3: AND(0, 1); 3: XOR(1, 2)
+        assert_eq!(
+            check_gate_outputs_unique(&inputs, [3, 4, 3].into_iter()),
+            Err(CircuitValidationError::DuplicateGateOutput { wire_id: 3 })
+        );
+        // a Gate overwriting an input
+        assert_eq!(
+            check_gate_outputs_unique(&inputs, [3, 1].into_iter()),
+            Err(CircuitValidationError::DuplicateGateOutput { wire_id: 1 })
+        );
+    }
+
+    #[test]
+    fn test_validate_display_circuit_ok() {
+        let circuit = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+
+        assert_eq!(validate_circuit(&circuit), Ok(()));
+    }
+
+    #[test]
+    fn test_check_input_config() {
+        assert_eq!(check_input_config(None, 3), Ok(()));
+        assert_eq!(check_input_config(Some(3), 3), Ok(()));
+        assert_eq!(
+            check_input_config(Some(4), 3),
+            Err(CircuitValidationError::InputConfigMismatch {
+                declared: 4,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_output_config() {
+        assert_eq!(check_output_config(None, 3), Ok(()));
+        assert_eq!(check_output_config(Some(120 * 52), 120 * 52), Ok(()));
+        assert_eq!(
+            check_output_config(Some(120 * 52), 120 * 51),
+            Err(CircuitValidationError::OutputCountMismatch {
+                outputs: 120 * 51,
+                expected: 120 * 52
+            })
+        );
+    }
+}
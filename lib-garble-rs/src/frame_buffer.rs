@@ -0,0 +1,132 @@
+//! Animation loops hold a lot of frames of evaluator inputs; as `Vec<EvaluatorInput>`
+//! that is one `u8` per bit.
+//! `FrameBuffer` stores them bit-packed instead, with the same layout as `pack_evaluator_inputs`.
+
+use alloc::vec::Vec;
+
+use crate::{EvaluatorInput, InterstellarError};
+
+/// Bit-packed store of fixed-size evaluator input frames.
+/// Each frame occupies `ceil(nbits_per_frame / 8)` bytes, LSB first.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FrameBuffer {
+    nbits_per_frame: usize,
+    bytes: Vec<u8>,
+}
+
+impl FrameBuffer {
+    /// `nbits_per_frame`: typically `GarbledCircuit::num_evaluator_inputs`
+    #[must_use]
+    pub fn new(nbits_per_frame: usize) -> Self {
+        Self {
+            nbits_per_frame,
+            bytes: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn nbits_per_frame(&self) -> usize {
+        self.nbits_per_frame
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bytes
+            .len()
+            .checked_div(self.nb_bytes_per_frame())
+            .unwrap_or(0)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn nb_bytes_per_frame(&self) -> usize {
+        self.nbits_per_frame.div_ceil(8)
+    }
+
+    /// Append a frame at the end.
+    ///
+    /// # Errors
+    /// - `EvaluatorInputsWrongLength` if `frame` does not contain exactly `nbits_per_frame` bits
+    ///
+    pub fn push_frame(&mut self, frame: &[bool]) -> Result<(), InterstellarError> {
+        if frame.len() != self.nbits_per_frame {
+            return Err(InterstellarError::EvaluatorInputsWrongLength {
+                inputs_len: frame.len(),
+                expected_len: self.nbits_per_frame,
+            });
+        }
+
+        self.bytes.extend(frame.chunks(8).map(|bits| {
+            bits.iter()
+                .enumerate()
+                .fold(0u8, |byte, (idx, bit)| byte | (u8::from(*bit) << idx))
+        }));
+
+        Ok(())
+    }
+
+    /// Iterate on the bits of the frame `idx`, unpacked on the fly.
+    ///
+    /// # Panics
+    /// - if `idx >= len()`
+    ///
+    pub fn frame(&self, idx: usize) -> impl Iterator<Item = EvaluatorInput> + '_ {
+        assert!(
+            idx < self.len(),
+            "FrameBuffer: frame {idx} out of range [0,{})",
+            self.len()
+        );
+        let start = idx * self.nb_bytes_per_frame();
+        let frame_bytes = &self.bytes[start..start + self.nb_bytes_per_frame()];
+
+        (0..self.nbits_per_frame)
+            .map(move |bit_idx| (frame_bytes[bit_idx / 8] >> (bit_idx % 8)) & 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_push_frame_round_trip() {
+        let frames = [
+            vec![
+                true, false, true, true, false, false, false, true, true, false, true,
+            ],
+            vec![false; 11],
+            vec![true; 11],
+        ];
+        let mut frame_buffer = FrameBuffer::new(11);
+
+        for frame in &frames {
+            frame_buffer.push_frame(frame).unwrap();
+        }
+
+        assert_eq!(frame_buffer.len(), 3);
+        // 11 bits -> 2 bytes per frame instead of 11
+        assert_eq!(frame_buffer.bytes.len(), 3 * 2);
+        for (idx, frame) in frames.iter().enumerate() {
+            let expected: Vec<EvaluatorInput> = frame.iter().map(|bit| u8::from(*bit)).collect();
+            assert_eq!(frame_buffer.frame(idx).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_push_frame_wrong_length() {
+        let mut frame_buffer = FrameBuffer::new(3);
+
+        assert_eq!(
+            frame_buffer.push_frame(&[true, false]),
+            Err(InterstellarError::EvaluatorInputsWrongLength {
+                inputs_len: 2,
+                expected_len: 3,
+            })
+        );
+        assert!(frame_buffer.is_empty());
+    }
+}
@@ -1,14 +1,21 @@
+use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
+use core::ops::Range;
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use xxhash_rust::xxh3::xxh3_64;
 
-use circuit_types_rs::DisplayConfig;
+use circuit_types_rs::{Circuit, DisplayConfig, EvaluatorInputsType, GarblerInputsType};
 
+use crate::new_garbling_scheme::circuit_for_eval::GateTypeForEval;
 use crate::new_garbling_scheme::evaluate::EncodedInfo;
 use crate::new_garbling_scheme::garble::GarbledCircuitFinal;
 use crate::new_garbling_scheme::wire_value::WireValue;
-use crate::new_garbling_scheme::{self};
+use crate::new_garbling_scheme::{self, KAPPA_BYTES};
 use crate::InterstellarEvaluatorError;
-use crate::{EvalCache, InterstellarError};
+use crate::{EvalCache, FrameBuffer, InterstellarError, WatermarkOptions};
 
 pub type EvaluatorInput = u8;
 pub(super) type GarblerInput = u8;
@@ -16,16 +23,61 @@ pub(super) type GarblerInput = u8;
 // pub struct EvaluatorInput(u8);
 // pub(super) struct GarblerInput(u8);
 
+/// Whether a set output bit means a lit pixel(`ActiveHigh`) or a dark one(`ActiveLow`).
+/// Only used when rendering the decoded outputs to pixels(cf `eval_packed_out`, `decode_region`);
+/// the garbled evaluation itself is NOT affected.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum DisplayPolarity {
+    #[default]
+    ActiveHigh,
+    ActiveLow,
+}
+
+impl DisplayPolarity {
+    /// Map a decoded output bit to a pixel bit; ie invert it when `ActiveLow`
+    fn pixel(self, output: bool) -> bool {
+        match self {
+            DisplayPolarity::ActiveHigh => output,
+            DisplayPolarity::ActiveLow => !output,
+        }
+    }
+}
+
 /// The main garbling part in mod `new_garbling_scheme` only handles "raw" circuits.
 /// But using `SkcdConfig` we have added the concept of `GarblerInputs`(for the watermark/otp)
 /// vs `EvaluatorInputs`(ie the random inputs during each render loop).
 /// This struct is here to bridge the gap.
-#[derive(PartialEq, Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct GarbledCircuit {
     // TODO DO NOT Serialize the full `GarbleCircuit`[at least not entirely]
     // MUST NOT be sent to the client-side b/c that probably leaks data
     // Instead we should just send the list of labels pair (0,1) for each EvaluatorInput only
     pub(super) garbled: GarbledCircuitFinal,
+    /// The `Circuit` this was garbled from; cf `verify_against_plain`.
+    /// Garbler-side ONLY: it has the gate types so it is NOT serialized.
+    /// None when garbled from a borrowed `Circuit`(eg `regarble`), or once deserialized.
+    #[serde(skip)]
+    plain_circuit: Option<PlainCircuit>,
+}
+
+/// NOTE: `plain_circuit` is ignored, the same way it is not serialized;
+/// ie a deserialized `GarbledCircuit` is equal to the original.
+impl PartialEq for GarbledCircuit {
+    fn eq(&self, other: &Self) -> bool {
+        self.garbled == other.garbled
+    }
+}
+
+/// cf `GarbledCircuit::plain_circuit`; `Arc` b/c a `GarbledCircuit` is `Clone`
+#[derive(Clone)]
+struct PlainCircuit(Arc<Circuit>);
+
+impl core::fmt::Debug for PlainCircuit {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PlainCircuit")
+            .field("nb_gates", &self.0.get_gates().len())
+            .finish_non_exhaustive()
+    }
 }
 
 /// The logic of the inputs handling MUST be consistant (cf `num_evaluator_inputs`,`num_inputs` AND `eval`)
@@ -41,7 +93,41 @@ pub struct GarbledCircuit {
 ///
 impl GarbledCircuit {
     pub(super) fn new(garbled: GarbledCircuitFinal) -> Self {
-        Self { garbled }
+        Self {
+            garbled,
+            plain_circuit: None,
+        }
+    }
+
+    /// Same as `new`, but keep `circuit` around for `verify_against_plain`
+    pub(super) fn new_with_plain_circuit(garbled: GarbledCircuitFinal, circuit: Circuit) -> Self {
+        Self {
+            garbled,
+            plain_circuit: Some(PlainCircuit(Arc::new(circuit))),
+        }
+    }
+
+    /// Garble again an already parsed `Circuit`, eg for rapid key rotation:
+    /// fresh input labels `e`, and consistently re-derived `F` and `d`.
+    /// Contrary to `garble_circuit` the `Circuit` is only borrowed so it can be kept around
+    /// and re-garbled without being re-parsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng_seed` - cf `garble_skcd_with_seed`
+    ///
+    /// # Errors
+    /// - something went wrong during `garble`
+    ///
+    pub fn regarble(circuit: &Circuit, rng_seed: Option<u64>) -> Result<Self, InterstellarError> {
+        let garbled = new_garbling_scheme::garble::garble_reuse(
+            circuit,
+            rng_seed,
+            &mut new_garbling_scheme::garble::GarbleScratch::new(),
+        )
+        .map_err(|_e| InterstellarError::GarblerError)?;
+
+        Ok(Self::new(garbled))
     }
 
     /// [INTERNAL]
@@ -75,15 +161,320 @@ impl GarbledCircuit {
         self.garbled.eval_metadata.nb_outputs
     }
 
+    /// Longest path(in number of gates) from any input to any output.
+    /// The latency of `eval` is bounded by it; and so is the speedup of a parallel evaluator.
+    #[must_use]
+    pub fn circuit_depth(&self) -> usize {
+        self.garbled.circuit.depth()
+    }
+
+    /// Number of gates, including the "free" ones; cf `is_gate_free`
+    #[must_use]
+    pub fn gate_count(&self) -> usize {
+        self.garbled.circuit.get_gates().len()
+    }
+
+    /// Number of wire ids ie the size of the labels storage during `eval`
+    #[must_use]
+    pub fn wire_count(&self) -> usize {
+        self.garbled.circuit.get_nb_wires()
+    }
+
+    /// ALL the inputs of the circuit ie garbler inputs AND evaluator inputs;
+    /// contrary to `num_inputs`, the same for "generic circuits" and "display circuits"
+    #[must_use]
+    pub fn input_count(&self) -> usize {
+        self.garbled.circuit.get_nb_inputs()
+    }
+
+    /// Same as `num_outputs`, for both "generic circuits" and "display circuits"
+    #[must_use]
+    pub fn output_count(&self) -> usize {
+        self.garbled.eval_metadata.nb_outputs
+    }
+
+    /// Predicted size in bytes of the `F` part(ie the garbled gates) of a serialized circuit;
+    /// eg for bandwidth planning.
+    /// This is an upper bound: each `Delta` is counted at its max varint-encoded size,
+    /// a serialized `F` is typically a few percent smaller.
+    #[must_use]
+    pub fn f_byte_size(&self) -> usize {
+        self.garbled.f_byte_size()
+    }
+
+    /// Whether the gate `gate_id` is "free" ie it has no garbled representation(no `Delta` in `F`)
+    /// and costs nothing at eval time. That is the case for unary(INV/BUF) and constant gates,
+    /// and for XOR/XNOR gates garbled for free with `garble_circuit_with_delta`;
+    /// cf `GateKindIsFree` with `GarblingMode::FreeXor`
+    ///
+    /// Return: None if `gate_id` is not a gate of the circuit(eg an input, or out of range)
+    #[must_use]
+    pub fn is_gate_free(&self, gate_id: usize) -> Option<bool> {
+        self.garbled.is_gate_free(gate_id)
+    }
+
+    /// The number of 7 segments digits a "display circuit" expects;
+    /// ie the total length of its `SevenSegments` garbler inputs / 7
+    ///
+    /// # Errors
+    /// - `NotAValidDisplayCircuit` cf `get_display_config`
+    /// - `GarblerInputs7SegmentsNotMod7`
+    ///
+    pub fn num_digits(&self) -> Result<usize, InterstellarError> {
+        let nb_segments: usize = self
+            .get_display_config()?
+            .garbler_inputs
+            .iter()
+            .filter(|garbler_input| {
+                matches!(garbler_input.r#type, GarblerInputsType::SevenSegments)
+            })
+            .map(|garbler_input| garbler_input.length as usize)
+            .sum();
+        if nb_segments % 7 != 0 {
+            return Err(InterstellarError::GarblerInputs7SegmentsNotMod7);
+        }
+
+        Ok(nb_segments / 7)
+    }
+
+    /// ONLY for "display circuits"; meant for QA of the watermark, independently of the digits:
+    /// evaluate with ALL the segments off, the watermark `text`, and all the evaluator inputs at 0.
+    /// Return: the output pixels; cf `watermark::new_watermark` for the corresponding watermark bits
+    ///
+    /// # Errors
+    /// - `NotAValidDisplayCircuit` cf `get_display_config`
+    /// - `WatermarkError` if the watermark could not be rendered
+    /// - cf `eval_all_plaintext`
+    ///
+    pub fn eval_watermark_only(&self, text: &str) -> Result<Vec<u8>, InterstellarEvaluatorError> {
+        let display_config = self.get_display_config()?;
+
+        let mut garbler_inputs = Vec::new();
+        crate::prepare_display_garbler_inputs_bits_into(
+            self,
+            &vec![0; self.num_digits()?],
+            text,
+            &WatermarkOptions::default(),
+            &mut garbler_inputs,
+        )?;
+        // there is no "blank" digit so switch the segments off directly
+        for (r#type, range) in contiguous_ranges(
+            display_config
+                .garbler_inputs
+                .iter()
+                .map(|garbler_input| (&garbler_input.r#type, garbler_input.length as usize)),
+        ) {
+            if matches!(r#type, GarblerInputsType::SevenSegments) {
+                if let Some(segments) = garbler_inputs.get_mut(range) {
+                    segments.fill(0);
+                }
+            }
+        }
+
+        let mut outputs = Vec::new();
+        self.eval_all_plaintext(
+            &garbler_inputs,
+            &vec![0; self.num_evaluator_inputs()],
+            &mut outputs,
+            &mut EvalCache::new(),
+        )?;
+
+        Ok(outputs)
+    }
+
+    /// Same as `garbled_display_circuit_prepare_garbler_inputs` but the number of digits
+    /// is checked upfront against the config; cf `num_digits`
+    ///
+    /// # Errors
+    /// - `GarblerInputs7SegmentsWrongLength` with both the given and expected number of digits
+    /// - cf `garbled_display_circuit_prepare_garbler_inputs`
+    ///
+    pub fn prepare_garbler_inputs_auto_digits(
+        &self,
+        digits: &[u8],
+        watermark_text: &str,
+    ) -> Result<EncodedGarblerInputs, InterstellarError> {
+        let expected = self.num_digits()?;
+        if digits.len() != expected {
+            return Err(InterstellarError::GarblerInputs7SegmentsWrongLength {
+                got: digits.len(),
+                expected,
+            });
+        }
+
+        crate::garbled_display_circuit_prepare_garbler_inputs(self, digits, watermark_text)
+    }
+
+    /// Same as `garbled_display_circuit_prepare_garbler_inputs` but the intermediate buffers
+    /// are kept in `scratch`; for servers preparing a lot of messages for the same circuit
+    ///
+    /// # Errors
+    /// cf `garbled_display_circuit_prepare_garbler_inputs`
+    ///
+    pub fn prepare_garbler_inputs_reuse(
+        &self,
+        digits: &[u8],
+        watermark_text: &str,
+        scratch: &mut GarblerInputScratch,
+    ) -> Result<EncodedGarblerInputs, InterstellarError> {
+        self.prepare_garbler_inputs_reuse_with_options(
+            digits,
+            watermark_text,
+            &WatermarkOptions::default(),
+            scratch,
+        )
+    }
+
+    /// cf `prepare_garbler_inputs_reuse` and `garbled_display_circuit_prepare_garbler_inputs_with_options`
+    pub(crate) fn prepare_garbler_inputs_reuse_with_options(
+        &self,
+        digits: &[u8],
+        watermark_text: &str,
+        watermark_options: &WatermarkOptions,
+        scratch: &mut GarblerInputScratch,
+    ) -> Result<EncodedGarblerInputs, InterstellarError> {
+        crate::prepare_display_garbler_inputs_bits_into(
+            self,
+            digits,
+            watermark_text,
+            watermark_options,
+            &mut scratch.garbler_inputs,
+        )?;
+
+        self.encode_garbler_inputs_into(&scratch.garbler_inputs, &mut scratch.wire_values)
+    }
+
+    /// Cheap consistency check of a(typically just deserialized) garbled circuit;
+    /// ie the internal sizes match the embedded circuit, so `eval` will NOT panic.
+    /// cf feature "`validate_on_deserialize`" to do it directly in `deserialize_for_evaluator`
+    ///
+    /// # Errors
+    /// - `GarbledCircuitInconsistent` if some internal size does not match
+    ///
+    pub fn self_check(&self) -> Result<(), InterstellarError> {
+        self.garbled.self_check()
+    }
+
     /// Return the `display_config`, originally cloned from the original `Circuit`
     ///
     /// # Errors
     /// - `NotAValidDisplayCircuit`: DO NOT call on a "generic circuit", ONLY use on "display circuits"!
+    ///   Also returned if the display has a zero width or height.
     ///
     pub fn get_display_config(&self) -> Result<&DisplayConfig, InterstellarError> {
-        self.get_config_internal()
+        let display_config = self
+            .get_config_internal()
             .as_ref()
-            .ok_or(InterstellarError::NotAValidDisplayCircuit)
+            .ok_or(InterstellarError::NotAValidDisplayCircuit)?;
+        check_display_config_dimensions(display_config)?;
+
+        Ok(display_config)
+    }
+
+    /// ONLY for "display circuits"
+    /// Return the (x,y) pixel coordinates for each output index.
+    /// The outputs of a display circuit are the pixels of the framebuffer in row-major order
+    /// ie output `idx` is the pixel `(idx % width, idx / width)`.
+    ///
+    /// # Errors
+    /// - `NotAValidDisplayCircuit`: DO NOT call on a "generic circuit"
+    ///
+    pub fn output_pixel_map(&self) -> Result<Vec<(u32, u32)>, InterstellarError> {
+        let display_config = self.get_display_config()?;
+
+        Ok((0..display_config.height)
+            .flat_map(|y| (0..display_config.width).map(move |x| (x, y)))
+            .collect())
+    }
+
+    /// ONLY for "display circuits"
+    /// Prepare a new frame of evaluator inputs: each `Rnd` input is filled with
+    /// random bits of its declared length.
+    /// This is meant to be called once per frame in the render loop;
+    /// cf `prepare_evaluator_inputs` for the "all zeros" version.
+    ///
+    /// # Errors
+    /// - `NotAValidDisplayCircuit`: DO NOT call on a "generic circuit"
+    ///
+    pub fn random_evaluator_inputs(
+        &self,
+        rng: &mut impl RngCore,
+    ) -> Result<Vec<EvaluatorInput>, InterstellarError> {
+        let display_config = self.get_display_config()?;
+        let mut evaluator_inputs = Vec::with_capacity(self.num_evaluator_inputs());
+
+        for evaluator_input in &display_config.evaluator_inputs {
+            match evaluator_input.r#type {
+                EvaluatorInputsType::Rnd => {
+                    evaluator_inputs.extend(
+                        (0..evaluator_input.length).map(|_| u8::from(rng.next_u32() & 1 == 1)),
+                    );
+                }
+            }
+        }
+
+        Ok(evaluator_inputs)
+    }
+
+    /// Stable hash of the interface of the circuit ONLY, ie NOT its gates:
+    /// the numbers of inputs/outputs, and for "display circuits" the dimensions and the
+    /// type/length of every garbler and evaluator inputs group.
+    /// Circuits with the same signature can be used interchangeably by a client, eg for caching/dispatch.
+    #[must_use]
+    pub fn interface_signature(&self) -> u64 {
+        interface_signature(
+            self.garbled.circuit.get_nb_inputs(),
+            self.num_outputs(),
+            self.get_config_internal().as_ref(),
+        )
+    }
+
+    /// The type of each evaluator input group, and its indexes in the flat evaluator inputs
+    /// (cf `prepare_evaluator_inputs`/`random_evaluator_inputs`), in the order of the config.
+    /// The ranges are contiguous and cover `0..num_evaluator_inputs()`.
+    ///
+    /// # Errors
+    /// - `NotAValidDisplayCircuit`: DO NOT call on a "generic circuit"
+    ///
+    pub fn evaluator_input_groups(
+        &self,
+    ) -> Result<Vec<(EvaluatorInputsType, Range<usize>)>, InterstellarError> {
+        let display_config = self.get_display_config()?;
+
+        Ok(contiguous_ranges(
+            display_config
+                .evaluator_inputs
+                .iter()
+                .map(|evaluator_input| match evaluator_input.r#type {
+                    EvaluatorInputsType::Rnd => {
+                        (EvaluatorInputsType::Rnd, evaluator_input.length as usize)
+                    }
+                }),
+        ))
+    }
+
+    /// Inverse of `pack_evaluator_inputs`: exactly `num_evaluator_inputs` bits are unpacked,
+    /// the padding bits of the last byte are ignored.
+    ///
+    /// # Errors
+    /// - `EvaluatorInputsWrongLength` if `bytes` does not contain exactly `ceil(num_evaluator_inputs / 8)`
+    ///   bytes; `inputs_len` is then the number of bits of `bytes`
+    ///
+    pub fn unpack_evaluator_inputs(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Vec<EvaluatorInput>, InterstellarError> {
+        let nbits = self.num_evaluator_inputs();
+        if bytes.len() != nbits.div_ceil(8) {
+            return Err(InterstellarError::EvaluatorInputsWrongLength {
+                inputs_len: bytes.len() * 8,
+                expected_len: nbits,
+            });
+        }
+
+        Ok((0..nbits)
+            .map(|idx| (bytes[idx / 8] >> (idx % 8)) & 1)
+            .collect())
     }
 
     /// (Sort of) ONLY for "display circuits"
@@ -91,7 +482,14 @@ impl GarbledCircuit {
     /// cf struct docstring for details.
     /// For "generic circuits" this is a simple noop; needed b/c we still need the output for serialization.
     ///
-    pub(super) fn encode_inputs(&self, inputs: &[GarblerInput]) -> EncodedGarblerInputs {
+    /// # Errors
+    /// - `GarblerInputsWrongLength` if `inputs` does not match `num_inputs`
+    /// - `NonBinaryInput` if one of `inputs` is neither 0 nor 1
+    ///
+    pub(super) fn encode_inputs(
+        &self,
+        inputs: &[GarblerInput],
+    ) -> Result<EncodedGarblerInputs, InterstellarError> {
         if self.get_config_internal().is_some() {
             self.encode_garbler_inputs_internal(inputs)
         } else {
@@ -101,17 +499,77 @@ impl GarbledCircuit {
 
     /// ONLY for "display circuits"
     /// for "generic circuits" use the corresponding `encode_inputs`
+    ///
+    /// # Errors
+    /// cf `encode_inputs`
+    ///
     pub(super) fn encode_garbler_inputs_internal(
         &self,
         garbler_inputs: &[GarblerInput],
-    ) -> EncodedGarblerInputs {
-        // TODO(interstellar)? but is this the correct time to CHECK?
+    ) -> Result<EncodedGarblerInputs, InterstellarError> {
+        self.encode_garbler_inputs_into(garbler_inputs, &mut Vec::new())
+    }
+
+    /// Same as `encode_garbler_inputs_internal` but reusing `garbler_inputs_wire_value`
+    /// for the conversion to `WireValue`
+    ///
+    /// # Errors
+    /// cf `encode_inputs`
+    ///
+    pub(super) fn encode_garbler_inputs_into(
+        &self,
+        garbler_inputs: &[GarblerInput],
+        garbler_inputs_wire_value: &mut Vec<WireValue>,
+    ) -> Result<EncodedGarblerInputs, InterstellarError> {
         let expected_inputs_len = self.num_inputs();
-        assert_eq!(
-            expected_inputs_len,
-            garbler_inputs.len(),
-            "wrong garbler_inputs len!"
-        );
+        if garbler_inputs.len() != expected_inputs_len {
+            return Err(InterstellarError::GarblerInputsWrongLength {
+                inputs_len: garbler_inputs.len(),
+                expected_len: expected_inputs_len,
+            });
+        }
+        check_binary_inputs(garbler_inputs, 0)?;
+
+        // convert param `garbler_inputs` into `WireValue`
+        garbler_inputs_wire_value.clear();
+        garbler_inputs_wire_value.extend(garbler_inputs.iter().map(WireValue::from));
+
+        Ok(EncodedGarblerInputs {
+            encoded: new_garbling_scheme::evaluate::encode_garbler_inputs(
+                &self.garbled,
+                garbler_inputs_wire_value,
+                0,
+                expected_inputs_len,
+            )
+            .map_err(|_e| InterstellarError::GarblerError)?,
+            inputs_start_index: 0,
+        })
+    }
+
+    /// Encode only a part of the garbler inputs
+    /// ie the ones in `inputs_start_index..inputs_start_index + garbler_inputs.len()`
+    ///
+    /// This is meant for setups where multiple parties each hold some of the garbler inputs
+    /// (eg one supplies the watermark, another the digits); cf `EncodedGarblerInputs::merge`
+    ///
+    /// # Errors
+    /// - `GarblerInputsPartialOutOfRange` if the range is not within `0..num_inputs()`
+    /// - `NonBinaryInput` if one of `garbler_inputs` is neither 0 nor 1
+    ///
+    pub fn encode_garbler_inputs_partial(
+        &self,
+        garbler_inputs: &[u8],
+        inputs_start_index: usize,
+    ) -> Result<EncodedGarblerInputs, InterstellarError> {
+        let inputs_end_index = inputs_start_index + garbler_inputs.len();
+        if inputs_end_index > self.num_inputs() {
+            return Err(InterstellarError::GarblerInputsPartialOutOfRange {
+                start: inputs_start_index,
+                len: garbler_inputs.len(),
+                nb_inputs: self.num_inputs(),
+            });
+        }
+        check_binary_inputs(garbler_inputs, inputs_start_index)?;
 
         // convert param `garbler_inputs` into `WireValue`
         let garbler_inputs_wire_value: Vec<WireValue> = garbler_inputs
@@ -119,14 +577,16 @@ impl GarbledCircuit {
             .map(core::convert::Into::into)
             .collect();
 
-        EncodedGarblerInputs {
+        Ok(EncodedGarblerInputs {
             encoded: new_garbling_scheme::evaluate::encode_garbler_inputs(
                 &self.garbled,
                 &garbler_inputs_wire_value,
-                0,
-                expected_inputs_len,
-            ),
-        }
+                inputs_start_index,
+                inputs_end_index,
+            )
+            .map_err(|_e| InterstellarError::GarblerError)?,
+            inputs_start_index,
+        })
     }
 
     /// Evaluate
@@ -151,16 +611,264 @@ impl GarbledCircuit {
             .map(core::convert::Into::into)
             .collect();
 
-        // TODO(opt) remove clone
-        let mut encoded_info = encoded_garbler_inputs.encoded.clone();
+        self.eval_wire_values(
+            encoded_garbler_inputs,
+            &evaluator_inputs_wire_value,
+            outputs,
+            eval_cache,
+        )
+    }
 
-        new_garbling_scheme::evaluate::encode_evaluator_inputs(
+    /// Same as `eval`, but the outputs are written bit-packed into `packed`: 8 outputs per byte,
+    /// MSB first(ie 1bpp, as expected by most display backends).
+    /// The padding bits of the last byte are set to 0.
+    ///
+    /// NOTE: contrary to `pack_evaluator_inputs`, which is LSB first.
+    ///
+    /// With `DisplayPolarity::ActiveLow` every output bit is inverted before packing;
+    /// the padding bits are NOT.
+    ///
+    /// # Errors
+    /// - `OutputsPackedWrongLength` if `packed` is not exactly `ceil(num_outputs / 8)` bytes
+    /// - cf `eval`
+    ///
+    pub fn eval_packed_out(
+        &self,
+        encoded_garbler_inputs: &EncodedGarblerInputs,
+        evaluator_inputs: &[EvaluatorInput],
+        packed: &mut [u8],
+        polarity: DisplayPolarity,
+        eval_cache: &mut EvalCache,
+    ) -> Result<(), InterstellarEvaluatorError> {
+        if packed.len() != self.num_outputs().div_ceil(8) {
+            return Err(InterstellarError::OutputsPackedWrongLength {
+                bytes_len: packed.len(),
+                nb_outputs: self.num_outputs(),
+            }
+            .into());
+        }
+
+        let evaluator_inputs_wire_value: Vec<WireValue> = evaluator_inputs
+            .iter()
+            .map(core::convert::Into::into)
+            .collect();
+        let encoded_info =
+            self.encode_all_inputs(encoded_garbler_inputs, &evaluator_inputs_wire_value)?;
+
+        // decoded directly into `packed`, one byte(ie 8 outputs) at a time
+        packed.fill(0);
+        new_garbling_scheme::evaluate::evaluate_with_encoded_info_chunked(
             &self.garbled,
+            &encoded_info,
+            eval_cache,
+            8,
+            |chunk_start, outputs_chunk| {
+                for (idx, output) in (chunk_start..).zip(outputs_chunk) {
+                    packed[idx / 8] |= u8::from(polarity.pixel(output.value)) << (7 - idx % 8);
+                }
+            },
+        )
+    }
+
+    /// Same as `eval`, but the evaluator inputs are pulled from `bits`, eg a PRNG stream,
+    /// instead of having to be collected into a `&[EvaluatorInput]` first.
+    /// Exactly `num_evaluator_inputs` bits are consumed; the rest of `bits` is left untouched.
+    ///
+    /// # Errors
+    /// - `EvaluatorInputsWrongLength` if `bits` is exhausted before `num_evaluator_inputs` bits
+    /// - cf `eval`
+    ///
+    pub fn eval_from_bits(
+        &self,
+        encoded_garbler_inputs: &EncodedGarblerInputs,
+        bits: impl Iterator<Item = bool>,
+        outputs: &mut Vec<u8>,
+        eval_cache: &mut EvalCache,
+    ) -> Result<(), InterstellarEvaluatorError> {
+        let evaluator_inputs_wire_value: Vec<WireValue> = bits
+            .take(self.num_evaluator_inputs())
+            .map(WireValue::from)
+            .collect();
+        if evaluator_inputs_wire_value.len() != self.num_evaluator_inputs() {
+            return Err(InterstellarError::EvaluatorInputsWrongLength {
+                inputs_len: evaluator_inputs_wire_value.len(),
+                expected_len: self.num_evaluator_inputs(),
+            }
+            .into());
+        }
+
+        self.eval_wire_values(
+            encoded_garbler_inputs,
             &evaluator_inputs_wire_value,
-            &mut encoded_info,
-            self.num_inputs(),
-            self.num_inputs() + self.num_evaluator_inputs(),
-        );
+            outputs,
+            eval_cache,
+        )
+    }
+
+    /// Same as `eval`, but the evaluator inputs are read directly from the frame `frame_idx`
+    /// of a bit-packed `FrameBuffer`.
+    ///
+    /// # Errors
+    /// - `FrameOutOfRange` if `frame_idx` is not a valid frame of `frame_buffer`
+    /// - `EvaluatorInputsWrongLength` if the frames do not match `num_evaluator_inputs`
+    /// - cf `eval`
+    ///
+    pub fn eval_frame(
+        &self,
+        encoded_garbler_inputs: &EncodedGarblerInputs,
+        frame_buffer: &FrameBuffer,
+        frame_idx: usize,
+        outputs: &mut Vec<u8>,
+        eval_cache: &mut EvalCache,
+    ) -> Result<(), InterstellarEvaluatorError> {
+        if frame_idx >= frame_buffer.len() {
+            return Err(InterstellarError::FrameOutOfRange {
+                frame_idx,
+                nb_frames: frame_buffer.len(),
+            }
+            .into());
+        }
+        if frame_buffer.nbits_per_frame() != self.num_evaluator_inputs() {
+            return Err(InterstellarError::EvaluatorInputsWrongLength {
+                inputs_len: frame_buffer.nbits_per_frame(),
+                expected_len: self.num_evaluator_inputs(),
+            }
+            .into());
+        }
+
+        let evaluator_inputs_wire_value: Vec<WireValue> =
+            frame_buffer.frame(frame_idx).map(WireValue::from).collect();
+
+        self.eval_wire_values(
+            encoded_garbler_inputs,
+            &evaluator_inputs_wire_value,
+            outputs,
+            eval_cache,
+        )
+    }
+
+    /// Same as `eval`, but return the output labels(as little-endian bytes) instead of decoding them.
+    /// Meant for label-level composition ie feeding the outputs to another circuit;
+    /// this works on circuits garbled with `garble_circuit_no_decoding`.
+    ///
+    /// # Errors
+    /// cf `eval`
+    pub fn eval_to_labels(
+        &self,
+        encoded_garbler_inputs: &EncodedGarblerInputs,
+        evaluator_inputs: &[EvaluatorInput],
+        eval_cache: &mut EvalCache,
+    ) -> Result<Vec<[u8; KAPPA_BYTES]>, InterstellarEvaluatorError> {
+        let evaluator_inputs_wire_value: Vec<WireValue> = evaluator_inputs
+            .iter()
+            .map(core::convert::Into::into)
+            .collect();
+
+        let encoded_info =
+            self.encode_all_inputs(encoded_garbler_inputs, &evaluator_inputs_wire_value)?;
+
+        new_garbling_scheme::evaluate::evaluate_with_encoded_info_to_labels(
+            &self.garbled,
+            &encoded_info,
+            eval_cache,
+        )
+    }
+
+    /// Evaluate using already-encoded inputs: one label per input(garbler AND evaluator inputs, in order)
+    /// as little-endian bytes, eg the output labels of another circuit from `eval_to_labels`.
+    /// This is the other half of label-level composition: the garbler MUST have garbled this circuit
+    /// with the output label pairs of the previous circuit as input labels; cf `garble_circuit_with_labels`.
+    ///
+    /// # Errors
+    /// - `EvaluateErrorWrongInputsLength` if there is not exactly one label per input
+    /// - cf `eval`
+    ///
+    pub fn eval_with_input_labels(
+        &self,
+        input_labels: &[[u8; KAPPA_BYTES]],
+        eval_cache: &mut EvalCache,
+    ) -> Result<Vec<u8>, InterstellarEvaluatorError> {
+        if input_labels.len() != self.garbled.nb_input_labels() {
+            return Err(InterstellarEvaluatorError::EvaluateErrorWrongInputsLength {
+                inputs_len: input_labels.len(),
+                expected_len: self.garbled.nb_input_labels(),
+            });
+        }
+
+        let outputs_wire_value = new_garbling_scheme::evaluate::evaluate_with_encoded_info(
+            &self.garbled,
+            &EncodedInfo::from_le_bytes(input_labels),
+            eval_cache,
+        )?;
+
+        Ok(outputs_wire_value
+            .into_iter()
+            .map(core::convert::Into::into)
+            .collect())
+    }
+
+    /// ONLY for "display circuits"
+    /// Decode only the pixels in the rectangle `(x, y, w, h)` from the labels of `eval_to_labels`,
+    /// eg for a client rendering the display tile by tile.
+    /// Return: the pixels of the region in row-major order ie `w * h` values;
+    /// inverted when `polarity` is `ActiveLow`
+    ///
+    /// # Errors
+    /// - `NotAValidDisplayCircuit`: DO NOT call on a "generic circuit"
+    /// - `RegionOutOfRange` if the rectangle does not fit in the display
+    /// - `DecodingErrorMissingOutputLabel` if `output_labels` is too short
+    ///
+    pub fn decode_region(
+        &self,
+        output_labels: &[[u8; KAPPA_BYTES]],
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+        polarity: DisplayPolarity,
+    ) -> Result<Vec<u8>, InterstellarEvaluatorError> {
+        let display_config = self.get_display_config()?;
+        if u64::from(x) + u64::from(w) > u64::from(display_config.width)
+            || u64::from(y) + u64::from(h) > u64::from(display_config.height)
+        {
+            return Err(InterstellarError::RegionOutOfRange {
+                x,
+                y,
+                w,
+                h,
+                width: display_config.width,
+                height: display_config.height,
+            }
+            .into());
+        }
+
+        let outputs_indices = self
+            .output_pixel_map()?
+            .into_iter()
+            .enumerate()
+            .filter(|(_idx, (px, py))| (x..x + w).contains(px) && (y..y + h).contains(py))
+            .map(|(idx, _pixel)| idx);
+
+        Ok(new_garbling_scheme::evaluate::decode_labels(
+            &self.garbled,
+            output_labels,
+            outputs_indices,
+        )?
+        .iter()
+        .map(|output| u8::from(polarity.pixel(output.value)))
+        .collect())
+    }
+
+    /// Common part of `eval` and `eval_frame`
+    fn eval_wire_values(
+        &self,
+        encoded_garbler_inputs: &EncodedGarblerInputs,
+        evaluator_inputs_wire_value: &[WireValue],
+        outputs: &mut Vec<u8>,
+        eval_cache: &mut EvalCache,
+    ) -> Result<(), InterstellarEvaluatorError> {
+        let encoded_info =
+            self.encode_all_inputs(encoded_garbler_inputs, evaluator_inputs_wire_value)?;
 
         // TODO this SHOULD have `outputs` in-place [1]
         let outputs_wire_value = new_garbling_scheme::evaluate::evaluate_with_encoded_info(
@@ -178,10 +886,559 @@ impl GarbledCircuit {
 
         Ok(())
     }
+
+    /// `encoded_garbler_inputs` followed by the encoded `evaluator_inputs_wire_value`
+    fn encode_all_inputs(
+        &self,
+        encoded_garbler_inputs: &EncodedGarblerInputs,
+        evaluator_inputs_wire_value: &[WireValue],
+    ) -> Result<EncodedInfo, InterstellarEvaluatorError> {
+        strict_assert!(
+            evaluator_inputs_wire_value.len() == self.num_evaluator_inputs(),
+            InterstellarError::EvaluatorInputsWrongLength {
+                inputs_len: evaluator_inputs_wire_value.len(),
+                expected_len: self.num_evaluator_inputs(),
+            },
+            "encoding: `x` inputs len MUST match the Circuit's inputs len!"
+        );
+
+        // TODO(opt) remove clone
+        let mut encoded_info = encoded_garbler_inputs.encoded.clone();
+
+        new_garbling_scheme::evaluate::encode_evaluator_inputs(
+            &self.garbled,
+            evaluator_inputs_wire_value,
+            &mut encoded_info,
+            self.num_inputs(),
+            self.num_inputs() + self.num_evaluator_inputs(),
+        )?;
+
+        Ok(encoded_info)
+    }
+
+    /// Single-shot version of `encode_inputs` + `eval`, for when both the garbler
+    /// and evaluator inputs are available as plaintext bits(eg tests and tooling).
+    /// NOT meant for the render loop: the garbler inputs are re-encoded on each call.
+    ///
+    /// # Errors
+    /// - `GarblerInputsWrongLength`/`EvaluatorInputsWrongLength` if the inputs do not match
+    ///   `num_inputs`/`num_evaluator_inputs`
+    /// - `NonBinaryInput` if one of `garbler_inputs` is neither 0 nor 1
+    /// - cf `eval`
+    ///
+    pub fn eval_all_plaintext(
+        &self,
+        garbler_inputs: &[u8],
+        evaluator_inputs: &[EvaluatorInput],
+        outputs: &mut Vec<u8>,
+        eval_cache: &mut EvalCache,
+    ) -> Result<(), InterstellarEvaluatorError> {
+        if garbler_inputs.len() != self.num_inputs() {
+            return Err(InterstellarError::GarblerInputsWrongLength {
+                inputs_len: garbler_inputs.len(),
+                expected_len: self.num_inputs(),
+            }
+            .into());
+        }
+        if evaluator_inputs.len() != self.num_evaluator_inputs() {
+            return Err(InterstellarError::EvaluatorInputsWrongLength {
+                inputs_len: evaluator_inputs.len(),
+                expected_len: self.num_evaluator_inputs(),
+            }
+            .into());
+        }
+
+        let encoded_garbler_inputs = self.encode_inputs(garbler_inputs)?;
+        self.eval(
+            &encoded_garbler_inputs,
+            evaluator_inputs,
+            outputs,
+            eval_cache,
+        )
+    }
+
+    /// Evaluate `self`, then feed its decoded outputs as the evaluator inputs of `next`
+    /// and evaluate it.
+    /// This is composition at the "decoded bits" level: `next` MUST expect exactly
+    /// `self.num_outputs()` evaluator inputs.
+    ///
+    /// Return: the outputs of `next`
+    ///
+    /// # Errors
+    /// - `ChainOutputsInputsMismatch` if the number of outputs of `self` does not match
+    ///   the number of evaluator inputs of `next`
+    /// - cf `eval`
+    ///
+    pub fn eval_chain(
+        &self,
+        next: &GarbledCircuit,
+        encoded_garbler_inputs: &EncodedGarblerInputs,
+        evaluator_inputs: &[EvaluatorInput],
+        next_encoded_garbler_inputs: &EncodedGarblerInputs,
+        eval_cache: &mut EvalCache,
+    ) -> Result<Vec<u8>, InterstellarEvaluatorError> {
+        if self.num_outputs() != next.num_evaluator_inputs() {
+            return Err(InterstellarError::ChainOutputsInputsMismatch {
+                nb_outputs: self.num_outputs(),
+                nb_evaluator_inputs: next.num_evaluator_inputs(),
+            }
+            .into());
+        }
+
+        let mut outputs = Vec::with_capacity(self.num_outputs());
+        self.eval(
+            encoded_garbler_inputs,
+            evaluator_inputs,
+            &mut outputs,
+            eval_cache,
+        )?;
+
+        let mut next_outputs = Vec::with_capacity(next.num_outputs());
+        next.eval(
+            next_encoded_garbler_inputs,
+            &outputs,
+            &mut next_outputs,
+            eval_cache,
+        )?;
+
+        Ok(next_outputs)
+    }
+
+    /// DEBUG ONLY: like `eval_all_plaintext` but some(or all) garbler inputs MAY be missing.
+    /// A `None`(or missing, if `garbler_inputs` is shorter than `num_inputs`) input uses
+    /// the `value0` label ie it is evaluated as if it was 0.
+    /// That is an INSECURE default, meant to look at the partial behavior of a circuit
+    /// instead of failing; NEVER use it in production.
+    ///
+    /// # Errors
+    /// - `EvaluatorInputsWrongLength` if the evaluator inputs do not match `num_evaluator_inputs`
+    /// - cf `eval`
+    ///
+    #[cfg(feature = "debug_eval")]
+    pub fn eval_partial(
+        &self,
+        garbler_inputs: &[Option<u8>],
+        evaluator_inputs: &[EvaluatorInput],
+        outputs: &mut Vec<u8>,
+        eval_cache: &mut EvalCache,
+    ) -> Result<(), InterstellarEvaluatorError> {
+        let garbler_inputs: Vec<u8> = (0..self.num_inputs())
+            .map(|idx| garbler_inputs.get(idx).copied().flatten().unwrap_or(0))
+            .collect();
+
+        self.eval_all_plaintext(&garbler_inputs, evaluator_inputs, outputs, eval_cache)
+    }
+
+    /// Runtime smoke test of a freshly garbled circuit, before serving it:
+    /// for each sample compare the garbled eval with `eval_plain` on the `Circuit` it was garbled from.
+    /// Each sample contains ALL the inputs: first the garbler inputs(cf `num_inputs`),
+    /// then the evaluator inputs(cf `num_evaluator_inputs`).
+    ///
+    /// NOTE: ONLY available on the garbler-side, for a circuit garbled from an owned `Circuit`
+    /// eg `garble_circuit` or `garble_skcd`.
+    ///
+    /// # Errors
+    /// - `VerifyAgainstPlainMissingCircuit` if the `Circuit` was not kept; cf `plain_circuit`
+    /// - `VerifyAgainstPlainWrongLength` with the index of the first sample of the wrong length
+    /// - `VerifyAgainstPlainMismatch` with the index of the first mismatching sample
+    /// - cf `eval_plain` and `eval_all_plaintext`
+    ///
+    pub fn verify_against_plain(
+        &self,
+        sample_inputs: &[&[u8]],
+    ) -> Result<(), InterstellarEvaluatorError> {
+        let circuit = &self
+            .plain_circuit
+            .as_ref()
+            .ok_or(InterstellarError::VerifyAgainstPlainMissingCircuit)?
+            .0;
+        let expected_len = self.num_inputs() + self.num_evaluator_inputs();
+        let mut outputs = Vec::with_capacity(self.num_outputs());
+        let mut eval_cache = EvalCache::new();
+
+        for (sample_idx, inputs) in sample_inputs.iter().enumerate() {
+            if inputs.len() != expected_len {
+                return Err(InterstellarError::VerifyAgainstPlainWrongLength {
+                    sample_idx,
+                    inputs_len: inputs.len(),
+                    expected_len,
+                }
+                .into());
+            }
+            let expected_outputs = crate::eval_plain(circuit, inputs)?;
+
+            let (garbler_inputs, evaluator_inputs) = inputs.split_at(self.num_inputs());
+            self.eval_all_plaintext(
+                garbler_inputs,
+                evaluator_inputs,
+                &mut outputs,
+                &mut eval_cache,
+            )?;
+
+            if outputs != expected_outputs {
+                return Err(InterstellarError::VerifyAgainstPlainMismatch { sample_idx }.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Commitments to the input encodings `e`, eg for cut-and-choose.
+    /// One per input wire: `SHA256(value0 || value1)` over the canonical little-endian bytes
+    /// of the labels; cf `open_input` to reveal one of them.
+    #[must_use]
+    pub fn commit_inputs(&self) -> Vec<[u8; 32]> {
+        (0..self.garbled.nb_input_labels())
+            .filter_map(|wire_idx| self.garbled.input_labels_le_bytes(wire_idx))
+            .map(|(value0, value1)| {
+                let mut hasher = Sha256::new();
+                hasher.update(value0);
+                hasher.update(value1);
+                hasher.finalize().into()
+            })
+            .collect()
+    }
+
+    /// The circuit's output wire ids in decode order; ie the output `j`(and `d[j]`)
+    /// corresponds to the wire `output_wire_ids()[j]`.
+    /// For external verifiers which only receive `d` and the output labels.
+    #[must_use]
+    pub fn output_wire_ids(&self) -> Vec<usize> {
+        self.garbled.circuit.output_wire_ids()
+    }
+
+    /// Fingerprint of this garbled circuit, eg to commit to it on-chain;
+    /// cf `verify_commitment`
+    ///
+    /// # Errors
+    /// - `SerializerDeserializerInternalError` if the serialization failed
+    ///
+    pub fn fingerprint(&self) -> Result<[u8; 32], InterstellarError> {
+        self.garbled
+            .fingerprint()
+            .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })
+    }
+
+    /// Export the decoding information `d` on its own, linked to this circuit by its `fingerprint`.
+    /// Meant for protocols revealing `d` AFTER the evaluation so that a third party can check
+    /// the decoded outputs; cf `verify_outputs`
+    ///
+    /// # Errors
+    /// - `SerializerDeserializerInternalError` if the fingerprint could not be computed
+    ///
+    pub fn export_decoding_info(&self) -> Result<DecodingInfoArtifact, InterstellarError> {
+        Ok(DecodingInfoArtifact {
+            d: self.garbled.decoding_info_le_bytes(),
+            circuit_fingerprint: self.fingerprint()?,
+        })
+    }
+
+    /// Check this garbled circuit against a committed fingerprint; cf `fingerprint`
+    ///
+    /// Typical workflow, given a commitment (skcd CID, seed, fingerprint):
+    /// - the verifier fetches the .skcd from IPFS using the CID
+    /// - re-garbles it with `garble_skcd_with_seed(skcd, seed)`
+    /// - and checks `regarbled.verify_commitment(&fingerprint)`; and/or the served
+    ///   `GarbledCircuit` directly
+    ///
+    /// This ONLY works b/c the garbling is deterministic for a given seed.
+    #[must_use]
+    pub fn verify_commitment(&self, expected_fingerprint: &[u8; 32]) -> bool {
+        self.fingerprint()
+            .is_ok_and(|fingerprint| &fingerprint == expected_fingerprint)
+    }
+
+    /// Open the commitment of the input wire `wire_idx`; cf `commit_inputs`
+    /// Return the labels (`value0`, `value1`) as canonical little-endian bytes.
+    ///
+    /// WARNING: this reveals BOTH labels; this circuit MUST NOT be evaluated afterwards!
+    ///
+    /// # Errors
+    /// - `InputWireOutOfRange` if `wire_idx` is not an input wire
+    ///
+    pub fn open_input(
+        &self,
+        wire_idx: usize,
+    ) -> Result<([u8; KAPPA_BYTES], [u8; KAPPA_BYTES]), InterstellarError> {
+        self.garbled
+            .input_labels_le_bytes(wire_idx)
+            .ok_or(InterstellarError::InputWireOutOfRange {
+                wire_idx,
+                nb_inputs: self.garbled.nb_input_labels(),
+            })
+    }
+
+    /// Write a human-readable JSON summary of what was garbled, for diagnostics.
+    /// It contains ONLY the structure of the circuit(gate histogram, free vs materialized gates,
+    /// inputs/outputs counts, display dimensions); NEVER any label.
+    ///
+    /// # Errors
+    /// - whatever `w` returns
+    ///
+    pub fn debug_summary_json(&self, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+        let circuit = &self.garbled.circuit;
+
+        let (mut nb_binary, mut nb_unary, mut nb_constant) = (0usize, 0usize, 0usize);
+        for gate in circuit.get_gates() {
+            match gate.get_type() {
+                GateTypeForEval::Binary { .. } | GateTypeForEval::FreeXor { .. } => nb_binary += 1,
+                GateTypeForEval::Unary { .. } => nb_unary += 1,
+                GateTypeForEval::Constant { .. } => nb_constant += 1,
+            }
+        }
+        let nb_gates = circuit.get_gates().len();
+        let nb_materialized_gates = self.garbled.nb_materialized_gates();
+
+        write!(
+            w,
+            r#"{{"nb_gates":{nb_gates},"gates":{{"binary":{nb_binary},"unary":{nb_unary},"constant":{nb_constant}}},"#
+        )?;
+        write!(
+            w,
+            r#""nb_free_gates":{},"nb_materialized_gates":{nb_materialized_gates},"#,
+            nb_gates - nb_materialized_gates
+        )?;
+        write!(
+            w,
+            r#""nb_wires":{},"nb_inputs":{},"nb_garbler_inputs":{},"nb_evaluator_inputs":{},"nb_outputs":{},"#,
+            circuit.get_nb_wires(),
+            circuit.get_nb_inputs(),
+            self.num_inputs(),
+            self.num_evaluator_inputs(),
+            self.num_outputs(),
+        )?;
+        match self.get_config_internal() {
+            Some(config) => write!(
+                w,
+                r#""display":{{"width":{},"height":{}}}}}"#,
+                config.width, config.height
+            ),
+            None => write!(w, r#""display":null}}"#),
+        }
+    }
+
+    /// Same as `eval`, but the outputs are decoded `chunk` at a time and given to `sink`
+    /// as `(index of the first output of the chunk, outputs)`, instead of all at once.
+    /// Meant for wide display circuits on memory constrained targets; `eval` is faster otherwise.
+    /// `chunk` is clamped to `1..=output_count()`.
+    ///
+    /// # Errors
+    /// cf `eval`
+    pub fn eval_chunked(
+        &self,
+        encoded_garbler_inputs: &EncodedGarblerInputs,
+        evaluator_inputs: &[EvaluatorInput],
+        chunk: usize,
+        eval_cache: &mut EvalCache,
+        mut sink: impl FnMut(usize, &[u8]),
+    ) -> Result<(), InterstellarEvaluatorError> {
+        let evaluator_inputs_wire_value: Vec<WireValue> = evaluator_inputs
+            .iter()
+            .map(core::convert::Into::into)
+            .collect();
+
+        let encoded_info =
+            self.encode_all_inputs(encoded_garbler_inputs, &evaluator_inputs_wire_value)?;
+
+        let mut outputs_chunk_u8: Vec<u8> =
+            Vec::with_capacity(chunk.clamp(1, self.output_count().max(1)));
+        new_garbling_scheme::evaluate::evaluate_with_encoded_info_chunked(
+            &self.garbled,
+            &encoded_info,
+            eval_cache,
+            chunk,
+            |chunk_start, outputs_chunk| {
+                outputs_chunk_u8.clear();
+                outputs_chunk_u8.extend(outputs_chunk.iter().map(u8::from));
+                sink(chunk_start, &outputs_chunk_u8);
+            },
+        )
+    }
+
+    /// Lazily evaluate a stream of "evaluator inputs" frames
+    /// eg the random inputs of a display client's render loop.
+    ///
+    /// Each frame is evaluated only when the returned iterator is advanced,
+    /// reusing `eval_cache` for all of them; cf `eval`
+    pub fn eval_stream<'a>(
+        &'a self,
+        encoded_garbler_inputs: &'a EncodedGarblerInputs,
+        frames: impl Iterator<Item = Vec<EvaluatorInput>> + 'a,
+        eval_cache: &'a mut EvalCache,
+    ) -> impl Iterator<Item = Result<Vec<u8>, InterstellarEvaluatorError>> + 'a {
+        frames.map(move |evaluator_inputs| {
+            let mut outputs = Vec::new();
+            self.eval(
+                encoded_garbler_inputs,
+                &evaluator_inputs,
+                &mut outputs,
+                eval_cache,
+            )?;
+
+            Ok(outputs)
+        })
+    }
+}
+
+/// cf `GarbledCircuit::interface_signature`
+pub(crate) fn interface_signature(
+    nb_inputs: usize,
+    nb_outputs: usize,
+    config: Option<&DisplayConfig>,
+) -> u64 {
+    let mut buf: Vec<u8> = Vec::new();
+    let mut push = |value: u64| buf.extend_from_slice(&value.to_le_bytes());
+
+    push(nb_inputs as u64);
+    push(nb_outputs as u64);
+    if let Some(config) = config {
+        push(u64::from(config.width));
+        push(u64::from(config.height));
+        // the number of groups first, so that eg [a, b] + [] != [a] + [b]
+        push(config.garbler_inputs.len() as u64);
+        for garbler_input in &config.garbler_inputs {
+            push(match garbler_input.r#type {
+                GarblerInputsType::Buf => 0,
+                GarblerInputsType::SevenSegments => 1,
+                GarblerInputsType::Watermark => 2,
+            });
+            push(u64::from(garbler_input.length));
+        }
+        push(config.evaluator_inputs.len() as u64);
+        for evaluator_input in &config.evaluator_inputs {
+            push(match evaluator_input.r#type {
+                EvaluatorInputsType::Rnd => 0,
+            });
+            push(u64::from(evaluator_input.length));
+        }
+    }
+
+    xxh3_64(&buf)
+}
+
+/// `(key, length)` -> `(key, start..start + length)`, each range starting where the previous one ended
+pub(crate) fn contiguous_ranges<T>(
+    groups: impl Iterator<Item = (T, usize)>,
+) -> Vec<(T, Range<usize>)> {
+    let mut start = 0;
+    groups
+        .map(|(key, length)| {
+            let range = start..start + length;
+            start = range.end;
+            (key, range)
+        })
+        .collect()
+}
+
+/// Reusable buffers for `GarbledCircuit::prepare_garbler_inputs_reuse`
+#[derive(Default)]
+pub struct GarblerInputScratch {
+    /// the plaintext garbler inputs: buf + segments + watermark
+    garbler_inputs: Vec<GarblerInput>,
+    /// `garbler_inputs` converted for `encode_garbler_inputs`
+    wire_values: Vec<WireValue>,
+}
+
+impl GarblerInputScratch {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
 }
 
 /// `EncodedGarblerInputs`: sent to the client as part of `EvaluableGarbledCircuit`
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct EncodedGarblerInputs {
     pub(super) encoded: EncodedInfo,
+    /// Only meaningful for partial encodings, cf `encode_garbler_inputs_partial`/`merge`
+    /// NOT serialized: what is sent to the client is always the full encoding, ie starting at 0
+    #[serde(skip)]
+    pub(super) inputs_start_index: usize,
+}
+
+/// A zero width(or height) display would otherwise silently produce a zero-length watermark etc
+pub(crate) fn check_display_config_dimensions(
+    display_config: &DisplayConfig,
+) -> Result<(), InterstellarError> {
+    if display_config.width == 0 || display_config.height == 0 {
+        return Err(InterstellarError::NotAValidDisplayCircuit);
+    }
+
+    Ok(())
+}
+
+/// The decoding information `d` of a `GarbledCircuit`, standalone; cf `GarbledCircuit::export_decoding_info`
+#[derive(PartialEq, Debug, Deserialize, Serialize, Clone)]
+pub struct DecodingInfoArtifact {
+    /// One per output, as canonical little-endian bytes
+    d: Vec<[u8; KAPPA_BYTES]>,
+    circuit_fingerprint: [u8; 32],
+}
+
+impl DecodingInfoArtifact {
+    /// The `GarbledCircuit::fingerprint` of the circuit `d` belongs to
+    #[must_use]
+    pub fn circuit_fingerprint(&self) -> &[u8; 32] {
+        &self.circuit_fingerprint
+    }
+}
+
+/// Check that `claimed_bits`(one 0/1 per output) are indeed the decoding of `output_labels`
+/// (cf `GarbledCircuit::eval_to_labels`) using the decoding information of `artifact`.
+/// NOTE: this does NOT check which circuit `artifact` belongs to; compare `circuit_fingerprint`
+/// against a commitment for that.
+#[must_use]
+pub fn verify_outputs(
+    artifact: &DecodingInfoArtifact,
+    output_labels: &[[u8; KAPPA_BYTES]],
+    claimed_bits: &[u8],
+) -> bool {
+    new_garbling_scheme::evaluate::verify_decoded_outputs(&artifact.d, output_labels, claimed_bits)
+}
+
+/// Reject anything other than 0/1 instead of silently treating every nonzero byte as `true`
+/// `inputs_start_index`: offset of `inputs` in the full garbler inputs; only used for the error
+fn check_binary_inputs(
+    inputs: &[GarblerInput],
+    inputs_start_index: usize,
+) -> Result<(), InterstellarError> {
+    match inputs.iter().enumerate().find(|(_idx, value)| **value > 1) {
+        Some((idx, value)) => Err(InterstellarError::NonBinaryInput {
+            index: inputs_start_index + idx,
+            value: *value,
+        }),
+        None => Ok(()),
+    }
+}
+
+impl EncodedGarblerInputs {
+    /// Merge two partial encodings covering disjoint and contiguous ranges
+    /// of the garbler inputs; cf `GarbledCircuit::encode_garbler_inputs_partial`
+    /// The order of the parameters does not matter.
+    ///
+    /// # Errors
+    /// - `GarblerInputsMergeNotContiguous` if the two ranges overlap or are not contiguous
+    ///
+    pub fn merge(
+        a: EncodedGarblerInputs,
+        b: EncodedGarblerInputs,
+    ) -> Result<EncodedGarblerInputs, InterstellarError> {
+        let (mut first, second) = if a.inputs_start_index <= b.inputs_start_index {
+            (a, b)
+        } else {
+            (b, a)
+        };
+
+        let first_end = first.inputs_start_index + first.encoded.len();
+        if first_end != second.inputs_start_index {
+            return Err(InterstellarError::GarblerInputsMergeNotContiguous {
+                first_end,
+                second_start: second.inputs_start_index,
+            });
+        }
+
+        first.encoded.extend(second.encoded);
+
+        Ok(first)
+    }
 }
@@ -16,17 +16,77 @@ use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec;
 use alloc::vec::Vec;
+use rand::RngCore;
 use snafu::prelude::*;
 
-use circuit_types_rs::{EvaluatorInputsType, GarblerInputsType};
+use circuit_types_rs::{Circuit, EvaluatorInputsType, GarblerInputsType};
 
 // re-export
-pub use garble::{EncodedGarblerInputs, EvaluatorInput, GarbledCircuit};
+#[cfg(any(test, feature = "fuzz"))]
+pub use circuit_builder::random_circuit;
+pub use circuit_builder::{DisplayConfigBuilder, DisplayConfigWithBuilder};
+pub use circuit_cache::CircuitCache;
+pub use circuit_diff::{diff_circuits, CircuitDiff};
+pub use circuit_stats::{
+    constant_outputs, gate_referenced_wires, gate_type_histogram, materialized_gate_count,
+    output_cone, GATE_TYPE_CONSTANT,
+};
+pub use circuit_validation::{
+    evaluator_input_influence, reorder_inputs_first, unused_inputs, validate_circuit,
+    CircuitValidationError, CircuitWithConfig,
+};
+pub use frame_buffer::FrameBuffer;
+pub use garble::{
+    verify_outputs, DecodingInfoArtifact, DisplayPolarity, EncodedGarblerInputs, EvaluatorInput,
+    GarbledCircuit, GarblerInputScratch,
+};
 pub use new_garbling_scheme::evaluate::EvalCache;
-pub use serialize_deserialize::{deserialize_for_evaluator, serialize_for_evaluator};
+#[cfg(feature = "profile")]
+pub use new_garbling_scheme::evaluate::EvalProfile;
+#[cfg(feature = "timing")]
+pub use new_garbling_scheme::garble::GarbleTimings;
+pub use new_garbling_scheme::garble::{GarbleScratch, GarbleState};
+pub use new_garbling_scheme::{
+    gate_truth_table, is_gate_type_free, Blake3Backend, EvalSchedule, GarblingMode, GateKindIsFree,
+    GateTruthTable, RandomOracleBackend, Xxh3Backend, KAPPA_BYTES, RO_OUTPUT_BYTES,
+};
+pub use plain_eval::{eval_plain, functionally_equals};
+#[cfg(feature = "std")]
+pub use serialize_deserialize::eval_streaming;
+pub use serialize_deserialize::{
+    deserialize_circuit, deserialize_for_evaluator, peek_garbled_metadata, serialize_circuit,
+    serialize_for_evaluator, serialize_for_streaming, GarbledMeta,
+};
+pub use watermark::{
+    resample as resample_watermark, WatermarkBoundingBox, WatermarkCoverage, WatermarkOptions,
+    WatermarkStyle,
+};
+
+/// Check an invariant that can be broken by untrusted inputs(eg a corrupted `GarbledCircuit`).
+/// - with the `strict_errors` feature: `return Err($err.into())` if `$cond` is false
+/// - else: `assert!($cond, ...)` as before
+///
+/// NOTE: purely internal invariants SHOULD use `debug_assert!` instead.
+macro_rules! strict_assert {
+    ($cond:expr, $err:expr, $($msg:tt)+) => {
+        #[cfg(feature = "strict_errors")]
+        if !$cond {
+            return Err($err.into());
+        }
+        #[cfg(not(feature = "strict_errors"))]
+        assert!($cond, $($msg)+);
+    };
+}
 
+mod circuit_builder;
+mod circuit_cache;
+mod circuit_diff;
+mod circuit_stats;
+mod circuit_validation;
+mod frame_buffer;
 mod garble;
 mod new_garbling_scheme;
+mod plain_eval;
 mod segments;
 mod serialize_deserialize;
 mod watermark;
@@ -50,11 +110,19 @@ pub enum InterstellarError {
     /// SevenSegments garbler_input SHOULD be of length % 7
     GarblerInputs7SegmentsNotMod7,
     /// SevenSegments garbler_input SHOULD match digits parameter
-    GarblerInputs7SegmentsWrongLength,
+    /// `got`/`expected` are numbers of digits; cf `GarbledCircuit::num_digits`
+    GarblerInputs7SegmentsWrongLength {
+        got: usize,
+        expected: usize,
+    },
     /// error during `new_watermark`
     WatermarkError {
         msg: String,
     },
+    /// `parse_skcd_mmap`: the file could not be opened or mapped
+    SkcdFileError {
+        msg: String,
+    },
     SerializerDeserializerInternalError {
         err: postcard::Error,
     },
@@ -63,6 +131,112 @@ pub enum InterstellarError {
         inputs_len: usize,
         expected_len: usize,
     },
+    /// `encode_garbler_inputs_partial`: the range `start..start+len` is NOT within the garbler inputs
+    GarblerInputsPartialOutOfRange {
+        start: usize,
+        len: usize,
+        nb_inputs: usize,
+    },
+    /// `EncodedGarblerInputs::merge`: the two partial encodings overlap, or leave a gap
+    GarblerInputsMergeNotContiguous {
+        first_end: usize,
+        second_start: usize,
+    },
+    /// `encode_inputs`/`eval_all_plaintext`: wrong number of garbler inputs; cf `num_inputs`
+    GarblerInputsWrongLength {
+        inputs_len: usize,
+        expected_len: usize,
+    },
+    /// `eval_all_plaintext`, `unpack_evaluator_inputs`...: wrong number of evaluator inputs; cf `num_evaluator_inputs`
+    EvaluatorInputsWrongLength {
+        inputs_len: usize,
+        expected_len: usize,
+    },
+    /// `eval_chain`: the first circuit's outputs can NOT be fed as the next circuit's evaluator inputs
+    ChainOutputsInputsMismatch {
+        nb_outputs: usize,
+        nb_evaluator_inputs: usize,
+    },
+    /// `eval_plain`: wrong number of inputs(garbler + evaluator)
+    PlainInputsWrongLength {
+        inputs_len: usize,
+        expected_len: usize,
+    },
+    /// `eval_plain`: `wire_id` is used before being computed
+    PlainEvalMissingWire {
+        wire_id: usize,
+    },
+    /// `verify_against_plain`: the garbled eval differs from `eval_plain` for the sample `sample_idx`
+    VerifyAgainstPlainMismatch {
+        sample_idx: usize,
+    },
+    /// `verify_against_plain`: the sample `sample_idx` does not have one value per input
+    /// (garbler + evaluator)
+    VerifyAgainstPlainWrongLength {
+        sample_idx: usize,
+        inputs_len: usize,
+        expected_len: usize,
+    },
+    /// `verify_against_plain`: the `GarbledCircuit` does not hold the `Circuit` it was garbled from
+    VerifyAgainstPlainMissingCircuit,
+    /// `eval_frame`: `frame_idx` is NOT a valid frame of the `FrameBuffer`
+    FrameOutOfRange {
+        frame_idx: usize,
+        nb_frames: usize,
+    },
+    /// `deserialize_for_evaluator`: the buffer contains `extra_bytes` after the `GarbledCircuit`
+    /// ie it is corrupted(or something was appended to it)
+    TrailingData {
+        extra_bytes: usize,
+    },
+    /// `deserialize_for_evaluator`: the buffer does NOT start with the header written by
+    /// `serialize_for_evaluator`(or with an unsupported version); eg a blob from an older release
+    BlobHeaderInvalid,
+    /// `deserialize_for_evaluator`: the buffer was garbled by a build with another KAPPA;
+    /// both are the number of words of a label. cf `serialize_for_evaluator`
+    KappaMismatch {
+        blob: usize,
+        build: usize,
+    },
+    /// `GarbledCircuit::eval_packed_out`: `packed` MUST be exactly `ceil(nb_outputs / 8)` bytes
+    OutputsPackedWrongLength {
+        bytes_len: usize,
+        nb_outputs: usize,
+    },
+    /// The garbler input at `index` is `value` instead of 0 or 1
+    NonBinaryInput {
+        index: usize,
+        value: u8,
+    },
+    /// `GarbledCircuit::decode_region`: the rectangle `(x, y, w, h)` does NOT fit in the display
+    RegionOutOfRange {
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+        width: u32,
+        height: u32,
+    },
+    /// `functionally_equals`: `nb_inputs` is too large to enumerate every input combination
+    InputSpaceTooLarge {
+        nb_inputs: usize,
+        max_inputs: usize,
+    },
+    /// `GarbledCircuit::self_check`: `field` has length `len` instead of `expected_len`
+    GarbledCircuitInconsistent {
+        field: &'static str,
+        len: usize,
+        expected_len: usize,
+    },
+    /// `open_input`: `wire_idx` is NOT an input wire
+    InputWireOutOfRange {
+        wire_idx: usize,
+        nb_inputs: usize,
+    },
+    /// `parse_skcd`: the circuit is parseable but NOT consistent; cf `validate_circuit`
+    InvalidCircuit {
+        err: CircuitValidationError,
+    },
 }
 
 #[derive(Debug)]
@@ -71,6 +245,12 @@ pub enum InterstellarEvaluatorError {
     DecodingErrorMissingOutputLabel {
         idx: usize,
     },
+    /// Error at `decoding_internal`: `d` does not have one element per output
+    /// eg the circuit was garbled with `garble_circuit_no_decoding`
+    DecodingErrorMissingDecodingInfo {
+        len: usize,
+        expected_len: usize,
+    },
     /// Error at `evaluate_internal`
     EvaluateErrorMissingLabel {
         idx: usize,
@@ -79,6 +259,31 @@ pub enum InterstellarEvaluatorError {
     EvaluateErrorMissingDelta {
         idx: usize,
     },
+    /// Error at `evaluate_internal`: the number of encoded inputs(garbler + evaluator)
+    /// does not match the circuit; eg `EncodedGarblerInputs` from a partial encoding
+    /// Only returned with the `strict_errors` feature, else it panics
+    EvaluateErrorWrongInputsLength {
+        inputs_len: usize,
+        expected_len: usize,
+    },
+    /// Error at `encode_evaluator_inputs`: the evaluator inputs MUST be encoded right after
+    /// the `nb_garbler_inputs` garbler inputs, ie `start..end` within `nb_garbler_inputs..nb_inputs`
+    EncodingErrorWrongEvaluatorInputsRange {
+        start: usize,
+        end: usize,
+        nb_garbler_inputs: usize,
+        nb_inputs: usize,
+    },
+    /// Error at `evaluate_internal`: a Gate references a wire outside of the circuit
+    /// ie the `GarbledCircuit` is corrupted
+    EvaluateErrorWireOutOfRange {
+        idx: usize,
+    },
+    /// Error at `EvalSchedule::DepthFirst`: the Gate `idx` is(directly or not) one of its own inputs
+    /// ie the `GarbledCircuit` is corrupted
+    EvaluateErrorCycle {
+        idx: usize,
+    },
     BaseError {
         err: InterstellarError,
     },
@@ -109,13 +314,180 @@ fn garble_skcd_aux(
     skcd_buf: &[u8],
     rng_seed: Option<u64>,
 ) -> Result<GarbledCircuit, InterstellarError> {
+    let circuit = parse_skcd(skcd_buf)?;
+
+    garble_circuit(circuit, rng_seed)
+}
+
+/// Parse a .skcd into a `Circuit`, WITHOUT garbling it.
+/// Together with `garble_circuit` this allows a server to parse a circuit once,
+/// and then garble it as many times as needed.
+///
+/// NOTE: a .skcd is already a postcard-serialized `Circuit`, so the buffer returned
+/// by `serialize_circuit` can also be given here.
+///
+/// # Errors
+/// - `SkcdParserError` if `skcd_buf` does not contain a properly serialized `Circuit`
+/// - `InvalidCircuit` if the inputs of the circuit do not match its config
+///
+pub fn parse_skcd(skcd_buf: &[u8]) -> Result<Circuit, InterstellarError> {
     let circuit = circuit_types_rs::deserialize_from_buffer(skcd_buf)
         .map_err(|_e| InterstellarError::SkcdParserError)?;
 
-    let garbled = new_garbling_scheme::garble::garble(circuit, rng_seed)
+    // the .skcd usually comes from IPFS ie it is untrusted
+    circuit_validation::check_circuit_input_config(&circuit)
+        .map_err(|err| InterstellarError::InvalidCircuit { err })?;
+    circuit_validation::check_circuit_output_config(&circuit)
+        .map_err(|err| InterstellarError::InvalidCircuit { err })?;
+
+    Ok(circuit)
+}
+
+/// Same as `parse_skcd` but `skcd_buf` MAY be gzip-compressed(eg circuits stored on IPFS);
+/// it is detected using the gzip magic bytes.
+///
+/// # Errors
+/// - `SkcdParserError` if the buffer can not be decompressed
+/// - cf `parse_skcd`
+///
+#[cfg(feature = "gzip")]
+pub fn parse_skcd_maybe_compressed(skcd_buf: &[u8]) -> Result<Circuit, InterstellarError> {
+    use std::io::Read;
+
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    if !skcd_buf.starts_with(&GZIP_MAGIC) {
+        return parse_skcd(skcd_buf);
+    }
+
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(skcd_buf)
+        .read_to_end(&mut decompressed)
+        .map_err(|_e| InterstellarError::SkcdParserError)?;
+
+    parse_skcd(&decompressed)
+}
+
+/// Same as `parse_skcd` but reading the .skcd directly from a memory-mapped file,
+/// ie without first copying the whole file into a `Vec<u8>`.
+///
+/// # Errors
+/// - `SkcdFileError` if the file can not be opened or mapped
+/// - cf `parse_skcd`
+///
+#[cfg(feature = "mmap")]
+pub fn parse_skcd_mmap(path: &std::path::Path) -> Result<Circuit, InterstellarError> {
+    let file = std::fs::File::open(path).map_err(|err| InterstellarError::SkcdFileError {
+        msg: err.to_string(),
+    })?;
+    // SAFETY: the mapping is only read during `parse_skcd` and dropped right after;
+    // the .skcd files are NOT supposed to be modified while the server is running.
+    let mmap =
+        unsafe { memmap2::Mmap::map(&file) }.map_err(|err| InterstellarError::SkcdFileError {
+            msg: err.to_string(),
+        })?;
+
+    parse_skcd(&mmap)
+}
+
+/// Garble an already parsed `Circuit`; cf `parse_skcd`
+///
+/// # Arguments
+///
+/// * `rng_seed` - cf `garble_skcd_with_seed`
+///
+/// # Errors
+/// - something went wrong during `garble`
+///
+pub fn garble_circuit(
+    circuit: Circuit,
+    rng_seed: Option<u64>,
+) -> Result<GarbledCircuit, InterstellarError> {
+    garble_circuit_reuse(circuit, rng_seed, &mut GarbleScratch::new())
+}
+
+/// Same as `garble_circuit`, but WITHOUT generating the decoding information.
+/// Meant for circuits only evaluated to output labels, eg feeding another circuit;
+/// cf `GarbledCircuit::eval_to_labels`.
+///
+/// NOTE: `GarbledCircuit::eval` etc on such a circuit return `DecodingErrorMissingDecodingInfo`.
+///
+/// # Errors
+/// - something went wrong during `garble`
+///
+pub fn garble_circuit_no_decoding(
+    circuit: Circuit,
+    rng_seed: Option<u64>,
+) -> Result<GarbledCircuit, InterstellarError> {
+    let garbled = new_garbling_scheme::garble::garble_no_decoding(&circuit, rng_seed)
         .map_err(|_e| InterstellarError::GarblerError)?;
 
-    Ok(GarbledCircuit::new(garbled))
+    Ok(GarbledCircuit::new_with_plain_circuit(garbled, circuit))
+}
+
+/// Same as `garble_circuit`, but reusing the buffers in `scratch` between calls.
+/// Meant for servers garbling a lot of circuits.
+///
+/// # Errors
+/// - something went wrong during `garble`
+///
+pub fn garble_circuit_reuse(
+    circuit: Circuit,
+    rng_seed: Option<u64>,
+    scratch: &mut GarbleScratch,
+) -> Result<GarbledCircuit, InterstellarError> {
+    let garbled = new_garbling_scheme::garble::garble_reuse(&circuit, rng_seed, scratch)
+        .map_err(|_e| InterstellarError::GarblerError)?;
+
+    Ok(GarbledCircuit::new_with_plain_circuit(garbled, circuit))
+}
+
+/// Return value of `garble_circuit_resumable`
+#[allow(clippy::large_enum_variant)]
+pub enum GarbleProgress {
+    /// Not finished: call again with `from_gate = next_gate`
+    More {
+        next_gate: usize,
+    },
+    Done(GarbledCircuit),
+}
+
+/// Same as `garble_circuit`, but split in several calls, eg when garbling a huge circuit in one go
+/// would exceed the compute budget of a single enclave call.
+/// Each call garbles at most `budget` gates starting at `from_gate`; everything needed to resume
+/// is kept in `state`. The last call returns the `GarbledCircuit`.
+/// A `budget` of 0 is treated as 1: each call garbles at least one gate.
+/// With the same `rng_seed`, the result is the same as `garble_circuit` whatever the `budget`.
+///
+/// # Arguments
+///
+/// * `circuit` - MUST be the same for all the calls
+/// * `rng_seed` - cf `garble_circuit`; only used when `from_gate` is 0
+/// * `from_gate` - 0 to start(or restart), else the `next_gate` returned by the previous call
+///
+/// # Errors
+/// - `GarblerError` if `from_gate` is not where the previous call stopped,
+///   or something went wrong during `garble`
+///
+pub fn garble_circuit_resumable(
+    circuit: &Circuit,
+    rng_seed: Option<u64>,
+    from_gate: usize,
+    budget: usize,
+    state: &mut GarbleState,
+) -> Result<GarbleProgress, InterstellarError> {
+    let step =
+        new_garbling_scheme::garble::garble_resumable(circuit, rng_seed, from_gate, budget, state)
+            .map_err(|_e| InterstellarError::GarblerError)?;
+
+    Ok(match step {
+        new_garbling_scheme::garble::GarbleStep::More { next_gate } => {
+            GarbleProgress::More { next_gate }
+        }
+        new_garbling_scheme::garble::GarbleStep::Done(garbled) => {
+            GarbleProgress::Done(GarbledCircuit::new(garbled))
+        }
+    })
 }
 
 /// Variant of `garble_skcd` used for tests
@@ -135,6 +507,105 @@ pub fn garble_skcd_with_seed(
     garble_skcd_aux(skcd_buf, Some(rng_seed))
 }
 
+/// Deterministic variant of `garble_skcd`: the seed is the BLAKE3 hash of `skcd_buf`
+/// (all 32 bytes, used as the `ChaChaRng` seed) so identical .skcd always garble identically,
+/// ie the CID of the circuit implies its garbling, without the caller having to manage seeds.
+///
+/// SECURITY: this trades forward secrecy for reproducibility: anyone with `skcd_buf` can
+/// re-derive ALL the labels. Do NOT use it when the labels MUST stay secret across sessions.
+///
+/// # Errors
+/// cf `garble_skcd`
+///
+pub fn garble_skcd_deterministic(skcd_buf: &[u8]) -> Result<GarbledCircuit, InterstellarError> {
+    let circuit = parse_skcd(skcd_buf)?;
+
+    let garbled =
+        new_garbling_scheme::garble::garble_from_seed(&circuit, *blake3::hash(skcd_buf).as_bytes())
+            .map_err(|_e| InterstellarError::GarblerError)?;
+
+    Ok(GarbledCircuit::new(garbled))
+}
+
+/// Variant of `garble_skcd` where the Free-XOR global "delta" `r` is given by the caller.
+/// This is meant for protocols garbling several circuits sharing the same delta
+/// eg for cross-circuit wire reuse.
+///
+/// SECURITY: reusing the same `r` across circuits means that learning both labels
+/// of ANY input wire in ANY of those circuits reveals `r`, and therefore both labels of
+/// every input wire of ALL the circuits sharing it.
+/// Only do this when the protocol requires it, and NEVER reuse a delta across unrelated sessions.
+///
+/// The input wires, and the output wires of the XOR/XNOR gates(which are then free: no `Delta`,
+/// no RO call at eval time) respect the Free-XOR invariant `LW0 ⊕ LW1 = r`.
+/// The other binary gates are still garbled using `Delta`, and a XOR/XNOR gate fed by one of
+/// them is as well.
+///
+/// # Arguments
+///
+/// * `r` - the Free-XOR delta, as little-endian bytes; MUST NOT be all zeros
+/// * `rng` - cf `garble_circuit_with_rng_trait`
+///
+/// # Errors
+/// - `GarblerError` if `r` is all zeros, or something went wrong during `garble`
+///
+pub fn garble_circuit_with_delta(
+    circuit: &Circuit,
+    r: [u8; KAPPA_BYTES],
+    rng: &mut dyn RngCore,
+) -> Result<GarbledCircuit, InterstellarError> {
+    let garbled = new_garbling_scheme::garble::garble_with_delta(circuit, &r, rng)
+        .map_err(|_e| InterstellarError::GarblerError)?;
+
+    Ok(GarbledCircuit::new(garbled))
+}
+
+/// Variant of `garble_circuit` where EVERY random block is drawn from `rng`,
+/// eg a hardware RNG(RDRAND etc) when running in an enclave.
+///
+/// SECURITY: the default(`rng_seed` = None) is a `ChaChaRng` seeded once from the OS, ie a
+/// userspace CSPRNG: fast, and as secure as its seed. A hardware RNG avoids trusting that
+/// seeding, but is slower(2 draws per input wire, and a few per output) and only as good as the
+/// hardware. In any case `rng` MUST be cryptographically secure: the labels are only as secret as it.
+///
+/// # Errors
+/// - something went wrong during `garble`
+///
+pub fn garble_circuit_with_rng_trait(
+    circuit: &Circuit,
+    rng: &mut dyn RngCore,
+) -> Result<GarbledCircuit, InterstellarError> {
+    let garbled = new_garbling_scheme::garble::garble_with_rng(circuit, rng)
+        .map_err(|_e| InterstellarError::GarblerError)?;
+
+    Ok(GarbledCircuit::new(garbled))
+}
+
+/// Variant of `garble_circuit` where the input labels are given by the caller instead of being
+/// sampled, eg to check against known-answer vectors. The decoding information is still random.
+///
+/// SECURITY: the labels MUST be random for a real garbling; this is meant for testing.
+///
+/// # Arguments
+///
+/// * `labels` - `(value0, value1)` as little-endian bytes, one per input wire(in order)
+/// * `rng_seed` - cf `garble_skcd_with_seed`; ONLY used for the decoding information
+///
+/// # Errors
+/// - `GarblerError` if `labels` does not match the inputs of the circuit, if `value0 == value1`
+///   for an input, or something went wrong during `garble`
+///
+pub fn garble_circuit_with_labels(
+    circuit: &Circuit,
+    labels: &[([u8; KAPPA_BYTES], [u8; KAPPA_BYTES])],
+    rng_seed: Option<u64>,
+) -> Result<GarbledCircuit, InterstellarError> {
+    let garbled = new_garbling_scheme::garble::garble_with_labels(circuit, labels, rng_seed)
+        .map_err(|_e| InterstellarError::GarblerError)?;
+
+    Ok(GarbledCircuit::new(garbled))
+}
+
 /// Prepare the `garbler_inputs`; it contains both:
 /// - the watermark(ie the message)
 /// - the 7 segments digits
@@ -153,6 +624,63 @@ pub fn garbled_display_circuit_prepare_garbler_inputs(
     digits: &[u8],
     watermark_text: &str,
 ) -> Result<EncodedGarblerInputs, InterstellarError> {
+    garbled_display_circuit_prepare_garbler_inputs_with_options(
+        garb,
+        digits,
+        watermark_text,
+        &WatermarkOptions::default(),
+    )
+}
+
+/// Like `garbled_display_circuit_prepare_garbler_inputs` but with custom rendering
+/// of the watermark(eg `WatermarkStyle::Outline`)
+///
+/// # Errors
+///
+/// cf `garbled_display_circuit_prepare_garbler_inputs`
+pub fn garbled_display_circuit_prepare_garbler_inputs_with_options(
+    garb: &GarbledCircuit,
+    digits: &[u8],
+    watermark_text: &str,
+    watermark_options: &WatermarkOptions,
+) -> Result<EncodedGarblerInputs, InterstellarError> {
+    garb.prepare_garbler_inputs_reuse_with_options(
+        digits,
+        watermark_text,
+        watermark_options,
+        &mut GarblerInputScratch::new(),
+    )
+}
+
+/// The "plaintext" part of `garbled_display_circuit_prepare_garbler_inputs`
+/// ie everything except the final `encode_inputs`
+#[cfg(test)]
+fn prepare_display_garbler_inputs_bits(
+    garb: &GarbledCircuit,
+    digits: &[u8],
+    watermark_text: &str,
+    watermark_options: &WatermarkOptions,
+) -> Result<Vec<u8>, InterstellarError> {
+    let mut garbler_inputs = Vec::new();
+    prepare_display_garbler_inputs_bits_into(
+        garb,
+        digits,
+        watermark_text,
+        watermark_options,
+        &mut garbler_inputs,
+    )?;
+
+    Ok(garbler_inputs)
+}
+
+/// Same as `prepare_display_garbler_inputs_bits` but reusing `garbler_inputs`(it is cleared first)
+pub(crate) fn prepare_display_garbler_inputs_bits_into(
+    garb: &GarbledCircuit,
+    digits: &[u8],
+    watermark_text: &str,
+    watermark_options: &WatermarkOptions,
+    garbler_inputs: &mut Vec<u8>,
+) -> Result<(), InterstellarError> {
     // Those are splitted into:
     // - "buf" gate (cf Verilog "rndswitch.v"; and correspondingly lib_garble/src/packmsg/packmsg_utils.cpp PrepareInputLabels);
     //    it MUST always be 0 else the 7 segments will not work as expected = 1 bit
@@ -162,7 +690,8 @@ pub fn garbled_display_circuit_prepare_garbler_inputs(
     // prepare using the correct garbler_inputs total length(in BITS)
     // ie simply sum the length of each GarblerInput
     let display_config = garb.get_display_config()?;
-    let mut garbler_inputs = Vec::with_capacity(
+    garbler_inputs.clear();
+    garbler_inputs.reserve(
         display_config
             .garbler_inputs
             .iter()
@@ -182,28 +711,61 @@ pub fn garbled_display_circuit_prepare_garbler_inputs(
                     return Err(InterstellarError::GarblerInputs7SegmentsNotMod7);
                 }
                 if garbler_input.length as usize != digits.len() * 7 {
-                    return Err(InterstellarError::GarblerInputs7SegmentsWrongLength);
+                    return Err(InterstellarError::GarblerInputs7SegmentsWrongLength {
+                        got: digits.len(),
+                        expected: garbler_input.length as usize / 7,
+                    });
                 }
 
-                let mut segments_inputs = segments::digits_to_segments_bits(digits)
+                segments::digits_to_segments_bits_into(digits, garbler_inputs)
                     .map_err(|e| InterstellarError::NotAValid7Segment { digit: e.number })?;
-                garbler_inputs.append(&mut segments_inputs);
             }
             GarblerInputsType::Watermark => {
-                let mut watermark_inputs = watermark::new_watermark(
+                watermark::new_watermark_into(
                     display_config.width,
                     display_config.height,
                     watermark_text,
+                    watermark_options,
+                    garbler_inputs,
                 )
                 .map_err(|err| InterstellarError::WatermarkError {
                     msg: err.to_string(),
                 })?;
-                garbler_inputs.append(&mut watermark_inputs);
             }
         }
     }
 
-    Ok(garb.encode_inputs(&garbler_inputs))
+    Ok(())
+}
+
+/// Compute how many pixels a given watermark text would light on a `width` x `height` display.
+/// This is typically used to reject messages that would be illegible BEFORE garbling.
+///
+/// # Errors
+///
+/// - `WatermarkError` if the watermark could not be rendered
+pub fn watermark_coverage(
+    width: u32,
+    height: u32,
+    watermark_text: &str,
+) -> Result<WatermarkCoverage, InterstellarError> {
+    watermark::watermark_coverage(width, height, watermark_text).map_err(|err| {
+        InterstellarError::WatermarkError {
+            msg: err.to_string(),
+        }
+    })
+}
+
+/// Maximum number of chars per line of watermark text that can fit on a `width` display,
+/// computed from the font metrics; eg to pass as `WatermarkOptions::max_chars`.
+///
+/// # Errors
+///
+/// - `WatermarkError` if the font could not be loaded
+pub fn watermark_max_chars(width: u32) -> Result<usize, InterstellarError> {
+    watermark::max_chars_for_width(width).map_err(|err| InterstellarError::WatermarkError {
+        msg: err.to_string(),
+    })
 }
 
 /// Like `garbled_display_circuit_prepare_garbler_inputs` but for the client-side(ie Evaluator)
@@ -237,6 +799,20 @@ pub fn prepare_evaluator_inputs(
     Ok(evaluator_inputs)
 }
 
+/// Pack evaluator inputs(one `u8` per bit) into 8 bits per byte, LSB first.
+/// eg to send the per-frame random inputs over the network; cf `GarbledCircuit::unpack_evaluator_inputs`
+#[must_use]
+pub fn pack_evaluator_inputs(evaluator_inputs: &[EvaluatorInput]) -> Vec<u8> {
+    evaluator_inputs
+        .chunks(8)
+        .map(|bits| {
+            bits.iter()
+                .enumerate()
+                .fold(0u8, |byte, (idx, bit)| byte | (u8::from(*bit != 0) << idx))
+        })
+        .collect()
+}
+
 #[doc(hidden)]
 #[cfg(feature = "std")]
 pub mod tests_utils;
@@ -245,6 +821,9 @@ pub mod tests_utils;
 mod tests {
 
     use super::*;
+    use circuit_types_rs::{KindBinary, KindUnary};
+    use rand::SeedableRng;
+    use sha2::{Digest, Sha256};
 
     // all_inputs/all_expected_outputs: standard full-adder 2 bits truth table(and expected results)
     // input  i_bit1;
@@ -274,13 +853,82 @@ mod tests {
         [1, 1],
     ];
 
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_parse_skcd_maybe_compressed_full_adder() {
+        use std::io::Write;
+
+        let skcd_buf: &[u8] = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(skcd_buf).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let circuit = parse_skcd(skcd_buf).unwrap();
+        for buf in [&compressed[..], skcd_buf] {
+            let parsed = parse_skcd_maybe_compressed(buf).unwrap();
+            assert!(diff_circuits(&circuit, &parsed).is_empty());
+        }
+
+        // truncated gzip stream
+        assert_eq!(
+            parse_skcd_maybe_compressed(&compressed[..compressed.len() / 2]).err(),
+            Some(InterstellarError::SkcdParserError)
+        );
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_parse_skcd_mmap_full_adder() {
+        let skcd_buf: &[u8] = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        let path = std::env::temp_dir().join(format!(
+            "lib-garble-rs-test-parse-skcd-mmap-{}.skcd",
+            std::process::id()
+        ));
+        std::fs::write(&path, skcd_buf).unwrap();
+
+        let parsed = parse_skcd_mmap(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(diff_circuits(&parse_skcd(skcd_buf).unwrap(), &parsed.unwrap()).is_empty());
+        assert!(matches!(
+            parse_skcd_mmap(&path),
+            Err(InterstellarError::SkcdFileError { .. })
+        ));
+    }
+
+    /// ZERO/ONE gates are `GateType::Constant`(NOT rewritten as eg XNOR(A,A) anymore);
+    /// check them through the whole public path, including the (de)serialization
+    #[test]
+    fn test_garble_evaluate_constant_circuits() {
+        for value in [false, true] {
+            let garb = garble_circuit(Circuit::new_test_circuit_constant(value), Some(42)).unwrap();
+            let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+            let buf = serialize_for_evaluator(garb, encoded_garbler_inputs).unwrap();
+            let (garb, encoded_garbler_inputs) = deserialize_for_evaluator(&buf).unwrap();
+
+            let mut outputs = vec![];
+            let mut eval_cache = EvalCache::new();
+            for input in [0, 1] {
+                garb.eval(
+                    &encoded_garbler_inputs,
+                    &vec![input; garb.num_evaluator_inputs()],
+                    &mut outputs,
+                    &mut eval_cache,
+                )
+                .unwrap();
+
+                assert_eq!(outputs, vec![u8::from(value)], "input = {input}");
+            }
+        }
+    }
+
     #[test]
     fn test_garble_evaluate_full_adder_2bits() {
         let garb = garble_skcd(include_bytes!(
             "../examples/data/result_abc_full_adder.postcard.bin"
         ))
         .unwrap();
-        let encoded_garbler_inputs = garb.encode_inputs(&[]);
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
 
         let mut outputs = vec![0u8; FULL_ADDER_2BITS_ALL_EXPECTED_OUTPUTS[0].len()];
         let mut eval_cache = EvalCache::new();
@@ -304,5 +952,1621 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "debug_eval")]
+    #[test]
+    fn test_eval_partial_full_adder_2bits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut eval_cache = EvalCache::new();
+        let mut outputs = vec![];
+
+        for (inputs, expected_outputs) in FULL_ADDER_2BITS_ALL_INPUTS
+            .iter()
+            .zip(FULL_ADDER_2BITS_ALL_EXPECTED_OUTPUTS)
+        {
+            // the adder has no garbler input; so "all None" is the same as a normal eval
+            for garbler_inputs in [&[][..], &[None, None][..]] {
+                garb.eval_partial(garbler_inputs, inputs, &mut outputs, &mut eval_cache)
+                    .unwrap();
+                assert_eq!(outputs, expected_outputs);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_against_plain_full_adder_2bits() {
+        let skcd_buf = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        let garb = garble_skcd(skcd_buf).unwrap();
+        let samples: Vec<&[u8]> = FULL_ADDER_2BITS_ALL_INPUTS
+            .iter()
+            .map(|inputs| &inputs[..])
+            .collect();
+
+        garb.verify_against_plain(&samples).unwrap();
+
+        assert!(matches!(
+            garb.verify_against_plain(&[samples[0], &[0, 1]]),
+            Err(InterstellarEvaluatorError::BaseError {
+                err: InterstellarError::VerifyAgainstPlainWrongLength {
+                    sample_idx: 1,
+                    inputs_len: 2,
+                    ..
+                }
+            })
+        ));
+        // the `Circuit` is only kept when garbled from an owned one, and NOT serialized
+        let regarbled = GarbledCircuit::regarble(&parse_skcd(skcd_buf).unwrap(), None).unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let deserialized = deserialize_for_evaluator(
+            &serialize_for_evaluator(garb, encoded_garbler_inputs).unwrap(),
+        )
+        .unwrap();
+        for garb in [regarbled, deserialized.0] {
+            assert!(matches!(
+                garb.verify_against_plain(&samples),
+                Err(InterstellarEvaluatorError::BaseError {
+                    err: InterstellarError::VerifyAgainstPlainMissingCircuit
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_eval_stream_full_adder_2bits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let frames: Vec<Vec<EvaluatorInput>> = FULL_ADDER_2BITS_ALL_INPUTS[1..4]
+            .iter()
+            .map(|inputs| inputs.to_vec())
+            .collect();
+
+        let mut eval_cache = EvalCache::new();
+        let streamed_outputs: Vec<Vec<u8>> = garb
+            .eval_stream(
+                &encoded_garbler_inputs,
+                frames.clone().into_iter(),
+                &mut eval_cache,
+            )
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(streamed_outputs.len(), 3);
+        let mut outputs = vec![];
+        for (frame, streamed_output) in frames.iter().zip(streamed_outputs.iter()) {
+            garb.eval(
+                &encoded_garbler_inputs,
+                frame,
+                &mut outputs,
+                &mut eval_cache,
+            )
+            .unwrap();
+            assert_eq!(&outputs, streamed_output);
+        }
+    }
+
+    #[test]
+    fn test_eval_frame_full_adder_2bits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let mut frame_buffer = FrameBuffer::new(garb.num_evaluator_inputs());
+        for inputs in &FULL_ADDER_2BITS_ALL_INPUTS {
+            let frame: Vec<bool> = inputs.iter().map(|bit| *bit != 0).collect();
+            frame_buffer.push_frame(&frame).unwrap();
+        }
+
+        let mut eval_cache = EvalCache::new();
+        let mut outputs_packed = vec![];
+        let mut outputs_unpacked = vec![];
+        for (frame_idx, inputs) in FULL_ADDER_2BITS_ALL_INPUTS.iter().enumerate() {
+            garb.eval_frame(
+                &encoded_garbler_inputs,
+                &frame_buffer,
+                frame_idx,
+                &mut outputs_packed,
+                &mut eval_cache,
+            )
+            .unwrap();
+            garb.eval(
+                &encoded_garbler_inputs,
+                inputs,
+                &mut outputs_unpacked,
+                &mut eval_cache,
+            )
+            .unwrap();
+
+            assert_eq!(outputs_packed, outputs_unpacked);
+        }
+
+        assert!(matches!(
+            garb.eval_frame(
+                &encoded_garbler_inputs,
+                &frame_buffer,
+                frame_buffer.len(),
+                &mut outputs_packed,
+                &mut eval_cache,
+            ),
+            Err(InterstellarEvaluatorError::BaseError {
+                err: InterstellarError::FrameOutOfRange { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_prepare_garbler_inputs_reuse_same_as_allocating() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let mut scratch = GarblerInputScratch::new();
+
+        // twice: the second call MUST NOT see anything left from the first one
+        for (digits, text) in [([4, 2], "first message"), ([1, 7], "second")] {
+            assert_eq!(
+                garb.prepare_garbler_inputs_reuse(&digits, text, &mut scratch)
+                    .unwrap(),
+                garbled_display_circuit_prepare_garbler_inputs(&garb, &digits, text).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_partial_garbler_inputs_full_adder_2bits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        let merged = EncodedGarblerInputs::merge(
+            garb.encode_garbler_inputs_partial(&[], 0).unwrap(),
+            garb.encode_garbler_inputs_partial(&[], 0).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(merged, garb.encode_inputs(&[]).unwrap());
+    }
+
+    #[cfg(feature = "profile")]
+    #[test]
+    fn test_eval_profile_full_adder() {
+        let circuit = parse_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let nb_binary_gates = circuit
+            .get_gates()
+            .iter()
+            .filter(|gate| matches!(gate.get_type(), circuit_types_rs::GateType::Binary { .. }))
+            .count();
+        let nb_outputs = circuit.get_nb_outputs();
+        let garb = garble_circuit(circuit, None).unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let mut eval_cache = EvalCache::new();
+        let mut outputs = vec![];
+
+        garb.eval(
+            &encoded_garbler_inputs,
+            &FULL_ADDER_2BITS_ALL_INPUTS[0],
+            &mut outputs,
+            &mut eval_cache,
+        )
+        .unwrap();
+
+        assert_eq!(
+            *eval_cache.profile(),
+            EvalProfile {
+                ro_g_calls: nb_binary_gates,
+                ro_prime_calls: nb_outputs,
+            }
+        );
+    }
+
+    /// Eval with only a part of the garbler inputs; ie what a malicious/buggy Garbler could send
+    fn eval_with_partial_garbler_inputs() -> Result<(), InterstellarEvaluatorError> {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let garbler_inputs = vec![0; garb.num_inputs()];
+        let partial_encoded_garbler_inputs = garb
+            .encode_garbler_inputs_partial(&garbler_inputs[..garbler_inputs.len() / 2], 0)
+            .unwrap();
+
+        let mut outputs = vec![];
+        garb.eval(
+            &partial_encoded_garbler_inputs,
+            &vec![0; garb.num_evaluator_inputs()],
+            &mut outputs,
+            &mut EvalCache::new(),
+        )
+    }
+
+    #[cfg(feature = "strict_errors")]
+    #[test]
+    fn test_eval_partial_garbler_inputs_returns_error() {
+        assert!(matches!(
+            eval_with_partial_garbler_inputs(),
+            Err(InterstellarEvaluatorError::EvaluateErrorWrongInputsLength { .. })
+        ));
+    }
+
+    #[cfg(not(feature = "strict_errors"))]
+    #[test]
+    #[should_panic(expected = "inputs len MUST match")]
+    fn test_eval_partial_garbler_inputs_panics() {
+        let _res = eval_with_partial_garbler_inputs();
+    }
+
+    #[test]
+    fn test_merge_partial_garbler_inputs_display_message_120x52_2digits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let garbler_inputs = prepare_display_garbler_inputs_bits(
+            &garb,
+            &[4, 2],
+            "test message",
+            &WatermarkOptions::default(),
+        )
+        .unwrap();
+
+        // split: the last `width * height` bits(ie the watermark) vs everything before
+        let display_config = garb.get_display_config().unwrap();
+        let watermark_len = (display_config.width * display_config.height) as usize;
+        let split_idx = garbler_inputs.len() - watermark_len;
+        let part_segments = garb
+            .encode_garbler_inputs_partial(&garbler_inputs[..split_idx], 0)
+            .unwrap();
+        let part_watermark = garb
+            .encode_garbler_inputs_partial(&garbler_inputs[split_idx..], split_idx)
+            .unwrap();
+
+        // NOTE: the order of the parameters does not matter
+        let merged =
+            EncodedGarblerInputs::merge(part_watermark.clone(), part_segments.clone()).unwrap();
+        assert_eq!(merged, garb.encode_inputs(&garbler_inputs).unwrap());
+
+        // and the same part twice can not be merged
+        assert_eq!(
+            EncodedGarblerInputs::merge(part_segments.clone(), part_segments),
+            Err(InterstellarError::GarblerInputsMergeNotContiguous {
+                first_end: split_idx,
+                second_start: 0
+            })
+        );
+        assert_eq!(
+            garb.encode_garbler_inputs_partial(&garbler_inputs[split_idx..], split_idx + 1),
+            Err(InterstellarError::GarblerInputsPartialOutOfRange {
+                start: split_idx + 1,
+                len: watermark_len,
+                nb_inputs: garbler_inputs.len()
+            })
+        );
+    }
+
+    #[test]
+    fn test_verify_outputs_with_decoding_info_artifact() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let mut eval_cache = EvalCache::new();
+        let artifact: DecodingInfoArtifact = postcard::from_bytes(
+            &postcard::to_allocvec(&garb.export_decoding_info().unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(artifact.circuit_fingerprint(), &garb.fingerprint().unwrap());
+
+        for (inputs, expected_outputs) in FULL_ADDER_2BITS_ALL_INPUTS
+            .iter()
+            .zip(FULL_ADDER_2BITS_ALL_EXPECTED_OUTPUTS)
+        {
+            let output_labels = garb
+                .eval_to_labels(&encoded_garbler_inputs, inputs, &mut eval_cache)
+                .unwrap();
+
+            assert!(verify_outputs(&artifact, &output_labels, &expected_outputs));
+            let mut flipped = expected_outputs;
+            flipped[1] ^= 1;
+            assert!(!verify_outputs(&artifact, &output_labels, &flipped));
+        }
+    }
+
+    #[test]
+    fn test_display_config_builder_garble_and_prepare_inputs() {
+        let config = circuit_types_rs::DisplayConfig::builder()
+            .width(2)
+            .height(2)
+            .add_watermark_input(4)
+            .add_segments_input(7)
+            .add_buf_input()
+            .add_rnd_evaluator_input(4)
+            .build()
+            .unwrap();
+        assert_eq!((config.width, config.height), (2, 2));
+        // reordered like a .skcd: buf, segments, watermark
+        assert!(matches!(
+            config
+                .garbler_inputs
+                .iter()
+                .map(|garbler_input| (&garbler_input.r#type, garbler_input.length))
+                .collect::<Vec<_>>()[..],
+            [
+                (GarblerInputsType::Buf, 1),
+                (GarblerInputsType::SevenSegments, 7),
+                (GarblerInputsType::Watermark, 4)
+            ]
+        ));
+
+        // each pixel = watermark XOR rnd; ie inputs 8..12 XOR inputs 12..16
+        let circuit = circuit_builder::build_circuit(
+            (0..16).map(|id| circuit_types_rs::WireRef { id }).collect(),
+            (0..4)
+                .map(|idx| {
+                    (
+                        circuit_types_rs::GateType::Binary {
+                            gate_type: KindBinary::XOR,
+                            input_a: circuit_types_rs::WireRef { id: 8 + idx },
+                            input_b: circuit_types_rs::WireRef { id: 12 + idx },
+                        },
+                        circuit_types_rs::WireRef { id: 16 + idx },
+                    )
+                })
+                .collect(),
+            (16..20)
+                .map(|id| circuit_types_rs::WireRef { id })
+                .collect(),
+            Some(config),
+        )
+        .unwrap();
+        let garb = garble_circuit(
+            parse_skcd(&serialize_circuit(&circuit).unwrap()).unwrap(),
+            Some(42),
+        )
+        .unwrap();
+        assert_eq!((garb.num_inputs(), garb.num_evaluator_inputs()), (12, 4));
+
+        let garbler_inputs =
+            prepare_display_garbler_inputs_bits(&garb, &[3], "", &WatermarkOptions::default())
+                .unwrap();
+        assert_eq!(garbler_inputs.len(), 12);
+        let evaluator_inputs = [1, 0, 1, 1];
+        let mut outputs = vec![];
+        garb.eval_all_plaintext(
+            &garbler_inputs,
+            &evaluator_inputs,
+            &mut outputs,
+            &mut EvalCache::new(),
+        )
+        .unwrap();
+        let plain_inputs: Vec<u8> = garbler_inputs
+            .iter()
+            .chain(&evaluator_inputs)
+            .copied()
+            .collect();
+        assert_eq!(outputs, eval_plain(&circuit, &plain_inputs).unwrap());
+
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[3], "").unwrap();
+        garb.eval(
+            &encoded_garbler_inputs,
+            &prepare_evaluator_inputs(&garb).unwrap(),
+            &mut outputs,
+            &mut EvalCache::new(),
+        )
+        .unwrap();
+        assert_eq!(outputs.len(), 4);
+    }
+
+    #[test]
+    fn test_with_config_swapped_garbler_inputs() {
+        let circuit = parse_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let mut config = circuit.get_config().unwrap().clone();
+        config.garbler_inputs.reverse();
+        let mut config_wrong_len = config.clone();
+        config_wrong_len.garbler_inputs[0].length += 1;
+        let mut config_zero_width = config.clone();
+        config_zero_width.width = 0;
+
+        assert!(matches!(
+            circuit.clone().with_config(config_wrong_len),
+            Err(InterstellarError::InvalidCircuit {
+                err: CircuitValidationError::InputConfigMismatch { .. }
+            })
+        ));
+        assert!(matches!(
+            circuit.clone().with_config(config_zero_width),
+            Err(InterstellarError::InvalidCircuit {
+                err: CircuitValidationError::OutputCountMismatch { expected: 0, .. }
+            })
+        ));
+        let circuit = circuit.with_config(config.clone()).unwrap();
+        assert_eq!(circuit.get_config(), Some(&config));
+
+        // re-garble: the garbler inputs are prepared following the new config
+        let garb = garble_circuit(circuit.clone(), Some(42)).unwrap();
+        assert_eq!(garb.get_display_config().unwrap(), &config);
+        let garbler_inputs = prepare_display_garbler_inputs_bits(
+            &garb,
+            &[4, 2],
+            "test message",
+            &WatermarkOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(garbler_inputs.len(), garb.num_inputs());
+        let mut evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+        for (idx, input) in evaluator_inputs.iter_mut().enumerate() {
+            *input = u8::from(idx % 3 == 0);
+        }
+
+        let mut outputs = vec![];
+        garb.eval_all_plaintext(
+            &garbler_inputs,
+            &evaluator_inputs,
+            &mut outputs,
+            &mut EvalCache::new(),
+        )
+        .unwrap();
+
+        let plain_inputs: Vec<u8> = garbler_inputs
+            .iter()
+            .chain(&evaluator_inputs)
+            .copied()
+            .collect();
+        assert_eq!(outputs, eval_plain(&circuit, &plain_inputs).unwrap());
+        assert_eq!(outputs.len(), 120 * 52);
+    }
+
+    #[test]
+    fn test_encode_evaluator_inputs_overlapping_range() {
+        use crate::new_garbling_scheme::wire_value::WireValue;
+
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "").unwrap();
+        let evaluator_inputs: Vec<WireValue> = prepare_evaluator_inputs(&garb)
+            .unwrap()
+            .into_iter()
+            .map(WireValue::from)
+            .collect();
+        let num_inputs = garb.num_inputs();
+
+        for (start, end) in [
+            // overlaps the last garbler input
+            (num_inputs - 1, num_inputs - 1 + evaluator_inputs.len()),
+            // leaves a gap
+            (num_inputs + 1, num_inputs + 1 + evaluator_inputs.len()),
+            // past the end
+            (num_inputs, num_inputs + evaluator_inputs.len() + 1),
+        ] {
+            let mut encoded_info = encoded_garbler_inputs.encoded.clone();
+
+            assert!(matches!(
+                new_garbling_scheme::evaluate::encode_evaluator_inputs(
+                    &garb.garbled,
+                    &evaluator_inputs,
+                    &mut encoded_info,
+                    start,
+                    end,
+                ),
+                Err(InterstellarEvaluatorError::EncodingErrorWrongEvaluatorInputsRange { .. })
+            ));
+            // NOTHING was encoded
+            assert_eq!(encoded_info, encoded_garbler_inputs.encoded);
+        }
+    }
+
+    #[test]
+    fn test_eval_watermark_only() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+
+        let outputs_blank = garb.eval_watermark_only("").unwrap();
+        let outputs = garb.eval_watermark_only("test").unwrap();
+
+        // everything else being equal, the ONLY pixels which change are the watermark ones
+        let watermark = watermark::new_watermark(120, 52, "test").unwrap();
+        assert!(watermark.contains(&1));
+        let diff: Vec<u8> = outputs
+            .iter()
+            .zip(&outputs_blank)
+            .map(|(pixel, pixel_blank)| pixel ^ pixel_blank)
+            .collect();
+        assert_eq!(diff, watermark);
+    }
+
+    #[test]
+    fn test_decode_region_matches_full_decode() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "test message").unwrap();
+        let evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+        let mut eval_cache = EvalCache::new();
+        let mut outputs = vec![];
+        garb.eval(
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            &mut outputs,
+            &mut eval_cache,
+        )
+        .unwrap();
+        let output_labels = garb
+            .eval_to_labels(&encoded_garbler_inputs, &evaluator_inputs, &mut eval_cache)
+            .unwrap();
+
+        let region = garb
+            .decode_region(&output_labels, 30, 20, 10, 10, DisplayPolarity::ActiveHigh)
+            .unwrap();
+
+        let expected: Vec<u8> = (20..30)
+            .flat_map(|y| (30..40).map(move |x| (x, y)))
+            .map(|(x, y)| outputs[y * 120 + x])
+            .collect();
+        assert_eq!(region, expected);
+        assert!(matches!(
+            garb.decode_region(&output_labels, 115, 0, 10, 10, DisplayPolarity::ActiveHigh),
+            Err(InterstellarEvaluatorError::BaseError {
+                err: InterstellarError::RegionOutOfRange { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_encode_inputs_non_binary() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let mut garbler_inputs = prepare_display_garbler_inputs_bits(
+            &garb,
+            &[4, 2],
+            "test message",
+            &WatermarkOptions::default(),
+        )
+        .unwrap();
+        garbler_inputs[7] = 2;
+
+        assert_eq!(
+            garb.encode_inputs(&garbler_inputs),
+            Err(InterstellarError::NonBinaryInput { index: 7, value: 2 })
+        );
+        assert_eq!(
+            garb.encode_garbler_inputs_partial(&garbler_inputs[5..], 5),
+            Err(InterstellarError::NonBinaryInput { index: 7, value: 2 })
+        );
+    }
+
+    #[test]
+    fn test_encode_inputs_wrong_length() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let garbler_inputs = prepare_display_garbler_inputs_bits(
+            &garb,
+            &[4, 2],
+            "test message",
+            &WatermarkOptions::default(),
+        )
+        .unwrap();
+        let mut too_long = garbler_inputs.clone();
+        too_long.push(0);
+
+        assert_eq!(
+            garb.encode_inputs(&garbler_inputs[1..]),
+            Err(InterstellarError::GarblerInputsWrongLength {
+                inputs_len: garb.num_inputs() - 1,
+                expected_len: garb.num_inputs(),
+            })
+        );
+        assert_eq!(
+            garb.encode_inputs(&too_long),
+            Err(InterstellarError::GarblerInputsWrongLength {
+                inputs_len: garb.num_inputs() + 1,
+                expected_len: garb.num_inputs(),
+            })
+        );
+    }
+
+    /// Same as `test_encode_inputs_wrong_length` but on the path used by `prepare_garbler_inputs_reuse`
+    #[test]
+    fn test_encode_inputs_reuse_short_buffer() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let mut scratch = GarblerInputScratch::new();
+        let expected = garb
+            .prepare_garbler_inputs_reuse(&[4, 2], "test message", &mut scratch)
+            .unwrap();
+
+        let garbler_inputs = prepare_display_garbler_inputs_bits(
+            &garb,
+            &[4, 2],
+            "test message",
+            &WatermarkOptions::default(),
+        )
+        .unwrap();
+        let mut wire_values = vec![];
+        assert_eq!(
+            garb.encode_garbler_inputs_into(&garbler_inputs[..7], &mut wire_values),
+            Err(InterstellarError::GarblerInputsWrongLength {
+                inputs_len: 7,
+                expected_len: garb.num_inputs(),
+            })
+        );
+
+        // the buffer is still usable after the error
+        assert_eq!(
+            garb.encode_garbler_inputs_into(&garbler_inputs, &mut wire_values),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn test_eval_all_plaintext_display_message_120x52_2digits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let garbler_inputs = prepare_display_garbler_inputs_bits(
+            &garb,
+            &[4, 2],
+            "test message",
+            &WatermarkOptions::default(),
+        )
+        .unwrap();
+        let mut evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+        for (idx, input) in evaluator_inputs.iter_mut().enumerate() {
+            *input = u8::from(idx % 3 == 0);
+        }
+        let mut eval_cache = EvalCache::new();
+
+        let mut outputs_two_steps = vec![];
+        garb.eval(
+            &garb.encode_inputs(&garbler_inputs).unwrap(),
+            &evaluator_inputs,
+            &mut outputs_two_steps,
+            &mut eval_cache,
+        )
+        .unwrap();
+
+        let mut outputs = vec![];
+        garb.eval_all_plaintext(
+            &garbler_inputs,
+            &evaluator_inputs,
+            &mut outputs,
+            &mut eval_cache,
+        )
+        .unwrap();
+
+        assert_eq!(outputs, outputs_two_steps);
+
+        assert!(matches!(
+            garb.eval_all_plaintext(
+                &garbler_inputs[1..],
+                &evaluator_inputs,
+                &mut outputs,
+                &mut eval_cache,
+            ),
+            Err(InterstellarEvaluatorError::BaseError {
+                err: InterstellarError::GarblerInputsWrongLength { .. }
+            })
+        ));
+        assert!(matches!(
+            garb.eval_all_plaintext(
+                &garbler_inputs,
+                &evaluator_inputs[1..],
+                &mut outputs,
+                &mut eval_cache,
+            ),
+            Err(InterstellarEvaluatorError::BaseError {
+                err: InterstellarError::EvaluatorInputsWrongLength { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_eval_chain_buf_then_inv() {
+        let garb_a = garble_circuit(Circuit::new_test_circuit_unary(KindUnary::BUF), None).unwrap();
+        let garb_b = garble_circuit(Circuit::new_test_circuit_unary(KindUnary::INV), None).unwrap();
+        let encoded_garbler_inputs_a = garb_a.encode_inputs(&[]).unwrap();
+        let encoded_garbler_inputs_b = garb_b.encode_inputs(&[]).unwrap();
+        let mut eval_cache = EvalCache::new();
+
+        for input in [0u8, 1] {
+            let outputs = garb_a
+                .eval_chain(
+                    &garb_b,
+                    &encoded_garbler_inputs_a,
+                    &[input],
+                    &encoded_garbler_inputs_b,
+                    &mut eval_cache,
+                )
+                .unwrap();
+
+            assert_eq!(outputs, vec![1 - input]);
+        }
+    }
+
+    #[test]
+    fn test_eval_chain_mismatch() {
+        let garb_a = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let garb_b = garble_circuit(Circuit::new_test_circuit_unary(KindUnary::INV), None).unwrap();
+
+        assert!(matches!(
+            garb_a.eval_chain(
+                &garb_b,
+                &garb_a.encode_inputs(&[]).unwrap(),
+                &[0, 0, 0],
+                &garb_b.encode_inputs(&[]).unwrap(),
+                &mut EvalCache::new(),
+            ),
+            Err(InterstellarEvaluatorError::BaseError {
+                err: InterstellarError::ChainOutputsInputsMismatch {
+                    nb_outputs: 2,
+                    nb_evaluator_inputs: 1,
+                }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_garble_circuit_no_decoding_full_adder_2bits() {
+        let skcd_buf = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        // same seed: the labels are the same, only `d` differs
+        let garb = garble_circuit(parse_skcd(skcd_buf).unwrap(), Some(42)).unwrap();
+        let garb_no_decoding =
+            garble_circuit_no_decoding(parse_skcd(skcd_buf).unwrap(), Some(42)).unwrap();
+        garb_no_decoding.self_check().unwrap();
+
+        let mut eval_cache = EvalCache::new();
+        for inputs in FULL_ADDER_2BITS_ALL_INPUTS {
+            let labels = garb_no_decoding
+                .eval_to_labels(
+                    &garb_no_decoding.encode_inputs(&[]).unwrap(),
+                    &inputs,
+                    &mut eval_cache,
+                )
+                .unwrap();
+
+            assert_eq!(labels.len(), 2);
+            assert_eq!(
+                labels,
+                garb.eval_to_labels(&garb.encode_inputs(&[]).unwrap(), &inputs, &mut eval_cache)
+                    .unwrap()
+            );
+        }
+
+        assert!(matches!(
+            garb_no_decoding.eval(
+                &garb_no_decoding.encode_inputs(&[]).unwrap(),
+                &[0, 0, 0],
+                &mut vec![],
+                &mut eval_cache
+            ),
+            Err(
+                InterstellarEvaluatorError::DecodingErrorMissingDecodingInfo {
+                    len: 0,
+                    expected_len: 2
+                }
+            )
+        ));
+    }
+
+    #[test]
+    fn test_evaluator_input_groups_display_message_120x52_2digits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+
+        let groups = garb.evaluator_input_groups().unwrap();
+
+        assert_eq!(
+            groups.len(),
+            garb.get_display_config().unwrap().evaluator_inputs.len()
+        );
+        assert_eq!(groups.first().unwrap().1.start, 0);
+        assert_eq!(groups.last().unwrap().1.end, garb.num_evaluator_inputs());
+        assert!(garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap()
+        .evaluator_input_groups()
+        .is_err());
+    }
+
+    #[test]
+    fn test_contiguous_ranges_two_rnd_groups() {
+        let groups = garble::contiguous_ranges(
+            [(EvaluatorInputsType::Rnd, 9), (EvaluatorInputsType::Rnd, 7)].into_iter(),
+        );
+
+        let ranges: Vec<_> = groups.into_iter().map(|(_type, range)| range).collect();
+        assert_eq!(ranges, vec![0..9, 9..16]);
+        assert_eq!(ranges.iter().map(ExactSizeIterator::len).sum::<usize>(), 16);
+    }
+
+    /// adder -> AND(o_sum, o_carry) ie a & b & c, evaluated without ever decoding the adder outputs
+    #[test]
+    fn test_eval_with_input_labels_composition() {
+        let adder = garble_skcd_with_seed(
+            include_bytes!("../examples/data/result_abc_full_adder.postcard.bin"),
+            42,
+        )
+        .unwrap();
+        let encoded_garbler_inputs = adder.encode_inputs(&[]).unwrap();
+        let mut eval_cache = EvalCache::new();
+        let adder_output_labels: Vec<_> = FULL_ADDER_2BITS_ALL_INPUTS
+            .iter()
+            .map(|inputs| {
+                adder
+                    .eval_to_labels(&encoded_garbler_inputs, inputs, &mut eval_cache)
+                    .unwrap()
+            })
+            .collect();
+        // garbler-side: the label pairs of the adder outputs, as seen when evaluating all the inputs
+        let labels: Vec<_> = (0..adder.num_outputs())
+            .map(|output_idx| {
+                let label_for = |bit: u8| {
+                    FULL_ADDER_2BITS_ALL_EXPECTED_OUTPUTS
+                        .iter()
+                        .position(|expected| expected[output_idx] == bit)
+                        .map(|idx| adder_output_labels[idx][output_idx])
+                        .unwrap()
+                };
+                (label_for(0), label_for(1))
+            })
+            .collect();
+        let and = garble_circuit_with_labels(
+            &Circuit::new_test_circuit(KindBinary::AND),
+            &labels,
+            Some(42),
+        )
+        .unwrap();
+
+        for (inputs, output_labels) in FULL_ADDER_2BITS_ALL_INPUTS.iter().zip(&adder_output_labels)
+        {
+            let outputs = and
+                .eval_with_input_labels(output_labels, &mut eval_cache)
+                .unwrap();
+
+            assert_eq!(outputs, vec![inputs[0] & inputs[1] & inputs[2]]);
+        }
+        assert!(matches!(
+            and.eval_with_input_labels(&adder_output_labels[0][..1], &mut eval_cache),
+            Err(InterstellarEvaluatorError::EvaluateErrorWrongInputsLength {
+                inputs_len: 1,
+                expected_len: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_interface_signature() {
+        // same interface(2 inputs, 1 output), different gates
+        let xor = garble_circuit(Circuit::new_test_circuit(KindBinary::XOR), None).unwrap();
+        let and = garble_circuit(Circuit::new_test_circuit(KindBinary::AND), None).unwrap();
+        assert_eq!(xor.interface_signature(), and.interface_signature());
+        let inv = garble_circuit(Circuit::new_test_circuit_unary(KindUnary::INV), None).unwrap();
+        assert_ne!(xor.interface_signature(), inv.interface_signature());
+
+        let display = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let mut config = display.get_display_config().unwrap().clone();
+        let nb_inputs = display.num_inputs() + display.num_evaluator_inputs();
+        assert_eq!(
+            garble::interface_signature(nb_inputs, display.num_outputs(), Some(&config)),
+            display.interface_signature()
+        );
+        config.garbler_inputs[0].length += 1;
+        assert_ne!(
+            garble::interface_signature(nb_inputs, display.num_outputs(), Some(&config)),
+            display.interface_signature()
+        );
+    }
+
+    #[test]
+    fn test_eval_from_bits_display_message_120x52_2digits() {
+        let garb = garble_skcd_with_seed(
+            include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin"),
+            42,
+        )
+        .unwrap();
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "test").unwrap();
+        let n = garb.num_evaluator_inputs();
+        let mut eval_cache = EvalCache::new();
+
+        let evaluator_inputs: Vec<EvaluatorInput> = (0..n).map(|i| u8::from(i % 2 == 0)).collect();
+        let mut outputs = vec![];
+        garb.eval(
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            &mut outputs,
+            &mut eval_cache,
+        )
+        .unwrap();
+
+        let mut outputs_from_bits = vec![];
+        // extra bits are ignored
+        garb.eval_from_bits(
+            &encoded_garbler_inputs,
+            (0..n + 8).map(|i| i % 2 == 0),
+            &mut outputs_from_bits,
+            &mut eval_cache,
+        )
+        .unwrap();
+        assert_eq!(outputs_from_bits, outputs);
+
+        assert!(matches!(
+            garb.eval_from_bits(
+                &encoded_garbler_inputs,
+                (0..n - 1).map(|i| i % 2 == 0),
+                &mut outputs_from_bits,
+                &mut eval_cache,
+            ),
+            Err(InterstellarEvaluatorError::BaseError {
+                err: InterstellarError::EvaluatorInputsWrongLength { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_eval_packed_out_display_message_120x52_2digits() {
+        let garb = garble_skcd_with_seed(
+            include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin"),
+            42,
+        )
+        .unwrap();
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "test").unwrap();
+        let evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+        let mut eval_cache = EvalCache::new();
+
+        let mut outputs = vec![];
+        garb.eval(
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            &mut outputs,
+            &mut eval_cache,
+        )
+        .unwrap();
+        let expected: Vec<u8> = outputs
+            .chunks(8)
+            .map(|bits| {
+                bits.iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (idx, bit)| byte | (bit << (7 - idx)))
+            })
+            .collect();
+
+        let mut packed = vec![0xff; garb.num_outputs().div_ceil(8)];
+        garb.eval_packed_out(
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            &mut packed,
+            DisplayPolarity::ActiveHigh,
+            &mut eval_cache,
+        )
+        .unwrap();
+        assert_eq!(packed, expected);
+
+        assert!(matches!(
+            garb.eval_packed_out(
+                &encoded_garbler_inputs,
+                &evaluator_inputs,
+                &mut packed[1..],
+                DisplayPolarity::ActiveHigh,
+                &mut eval_cache,
+            ),
+            Err(InterstellarEvaluatorError::BaseError {
+                err: InterstellarError::OutputsPackedWrongLength {
+                    nb_outputs: 6240,
+                    ..
+                }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_output_wire_ids_full_adder_2bits() {
+        let skcd_buf = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        let circuit = parse_skcd(skcd_buf).unwrap();
+        let expected: Vec<usize> = circuit.get_outputs().iter().map(|wire| wire.id).collect();
+
+        let garb = garble_circuit(circuit, None).unwrap();
+
+        assert_eq!(garb.output_wire_ids(), expected);
+        assert_eq!(garb.output_wire_ids().len(), garb.num_outputs());
+    }
+
+    #[test]
+    fn test_verify_commitment_full_adder_2bits() {
+        let skcd_buf = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        let garb = garble_skcd_with_seed(skcd_buf, 42).unwrap();
+        let fingerprint = garb.fingerprint().unwrap();
+
+        assert!(garb.verify_commitment(&fingerprint));
+        // re-garbling with the same seed gives the same circuit
+        assert!(garble_skcd_with_seed(skcd_buf, 42)
+            .unwrap()
+            .verify_commitment(&fingerprint));
+
+        let mut tampered = fingerprint;
+        tampered[0] ^= 1;
+        assert!(!garb.verify_commitment(&tampered));
+        assert!(!garble_skcd_with_seed(skcd_buf, 43)
+            .unwrap()
+            .verify_commitment(&fingerprint));
+    }
+
+    #[test]
+    fn test_garble_skcd_deterministic_full_adder_2bits() {
+        let skcd_buf = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+
+        let fingerprint = garble_skcd_deterministic(skcd_buf)
+            .unwrap()
+            .fingerprint()
+            .unwrap();
+
+        assert_eq!(
+            garble_skcd_deterministic(skcd_buf)
+                .unwrap()
+                .fingerprint()
+                .unwrap(),
+            fingerprint
+        );
+        assert_ne!(
+            garble_skcd(skcd_buf).unwrap().fingerprint().unwrap(),
+            fingerprint
+        );
+    }
+
+    #[test]
+    fn test_commit_open_inputs_full_adder_2bits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        let commitments = garb.commit_inputs();
+        assert_eq!(commitments.len(), 3);
+
+        for (wire_idx, commitment) in commitments.iter().enumerate() {
+            let (value0, value1) = garb.open_input(wire_idx).unwrap();
+            assert_ne!(value0, value1);
+
+            let mut hasher = Sha256::new();
+            hasher.update(value0);
+            hasher.update(value1);
+            let rehashed: [u8; 32] = hasher.finalize().into();
+            assert_eq!(&rehashed, commitment);
+        }
+
+        assert_eq!(
+            garb.open_input(3),
+            Err(InterstellarError::InputWireOutOfRange {
+                wire_idx: 3,
+                nb_inputs: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_debug_summary_json_full_adder() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let circuit = parse_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        let mut summary = String::new();
+        garb.debug_summary_json(&mut summary).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&summary).unwrap();
+
+        assert_eq!(json["nb_gates"], circuit.get_gates().len());
+        assert_eq!(json["nb_inputs"], 3);
+        assert_eq!(json["nb_outputs"], 2);
+        assert_eq!(
+            json["nb_free_gates"].as_u64().unwrap()
+                + json["nb_materialized_gates"].as_u64().unwrap(),
+            json["nb_gates"].as_u64().unwrap()
+        );
+        assert!(json["display"].is_null());
+    }
+
+    #[test]
+    fn test_eval_chunked_display_message_120x52_2digits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "test message").unwrap();
+        let evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+        let mut eval_cache = EvalCache::new();
+
+        let mut outputs = vec![];
+        garb.eval(
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            &mut outputs,
+            &mut eval_cache,
+        )
+        .unwrap();
+
+        let mut outputs_chunked = vec![];
+        garb.eval_chunked(
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            7,
+            &mut eval_cache,
+            |chunk_start, outputs_chunk| {
+                assert_eq!(chunk_start, outputs_chunked.len());
+                assert!(outputs_chunk.len() <= 7);
+                outputs_chunked.extend_from_slice(outputs_chunk);
+            },
+        )
+        .unwrap();
+
+        assert_eq!(outputs_chunked, outputs);
+
+        // out of range chunks are clamped: one output at a time, or all of them at once
+        for chunk in [0, usize::MAX] {
+            let mut outputs_chunked = vec![];
+            garb.eval_chunked(
+                &encoded_garbler_inputs,
+                &evaluator_inputs,
+                chunk,
+                &mut eval_cache,
+                |_chunk_start, outputs_chunk| outputs_chunked.extend_from_slice(outputs_chunk),
+            )
+            .unwrap();
+
+            assert_eq!(outputs_chunked, outputs);
+        }
+    }
+
+    #[test]
+    fn test_display_polarity_inverts_rendered_pixels() {
+        let garb = garble_skcd_with_seed(
+            include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin"),
+            42,
+        )
+        .unwrap();
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "test").unwrap();
+        let evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+        let mut eval_cache = EvalCache::new();
+
+        // 120x52 outputs: no padding bits in the last byte
+        let mut packed_high = vec![0; garb.num_outputs().div_ceil(8)];
+        let mut packed_low = packed_high.clone();
+        for (packed, polarity) in [
+            (&mut packed_high, DisplayPolarity::ActiveHigh),
+            (&mut packed_low, DisplayPolarity::ActiveLow),
+        ] {
+            garb.eval_packed_out(
+                &encoded_garbler_inputs,
+                &evaluator_inputs,
+                packed,
+                polarity,
+                &mut eval_cache,
+            )
+            .unwrap();
+        }
+        assert!(packed_high.iter().any(|byte| *byte != 0));
+        assert_eq!(
+            packed_low,
+            packed_high.iter().map(|byte| !byte).collect::<Vec<u8>>()
+        );
+
+        let output_labels = garb
+            .eval_to_labels(&encoded_garbler_inputs, &evaluator_inputs, &mut eval_cache)
+            .unwrap();
+        let region_high = garb
+            .decode_region(&output_labels, 0, 0, 120, 52, DisplayPolarity::ActiveHigh)
+            .unwrap();
+        let region_low = garb
+            .decode_region(&output_labels, 0, 0, 120, 52, DisplayPolarity::ActiveLow)
+            .unwrap();
+        assert_eq!(
+            region_low,
+            region_high
+                .iter()
+                .map(|pixel| 1 - pixel)
+                .collect::<Vec<u8>>()
+        );
+    }
+
+    #[test]
+    fn test_garble_circuit_resumable_same_as_single_shot() {
+        let skcd_buf: &[u8] =
+            include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin");
+        let circuit = parse_skcd(skcd_buf).unwrap();
+        let expected = garble_circuit(parse_skcd(skcd_buf).unwrap(), Some(42)).unwrap();
+        let mut state = GarbleState::new();
+
+        for budget in [1000, circuit.get_gates().len(), usize::MAX] {
+            let mut from_gate = 0;
+            let mut nb_calls = 0;
+            let garbled = loop {
+                nb_calls += 1;
+                match garble_circuit_resumable(&circuit, Some(42), from_gate, budget, &mut state)
+                    .unwrap()
+                {
+                    GarbleProgress::More { next_gate } => from_gate = next_gate,
+                    GarbleProgress::Done(garbled) => break garbled,
+                }
+            };
+
+            assert_eq!(garbled, expected);
+            assert_eq!(nb_calls, circuit.get_gates().len().div_ceil(budget));
+        }
+    }
+
+    #[test]
+    fn test_garble_circuit_resumable_budget_zero_still_progresses() {
+        let skcd_buf: &[u8] = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        let circuit = parse_skcd(skcd_buf).unwrap();
+        let expected = garble_circuit(parse_skcd(skcd_buf).unwrap(), Some(42)).unwrap();
+        let mut state = GarbleState::new();
+
+        let mut from_gate = 0;
+        let mut nb_calls = 0;
+        let garbled = loop {
+            nb_calls += 1;
+            assert!(nb_calls <= circuit.get_gates().len());
+            match garble_circuit_resumable(&circuit, Some(42), from_gate, 0, &mut state).unwrap() {
+                GarbleProgress::More { next_gate } => from_gate = next_gate,
+                GarbleProgress::Done(garbled) => break garbled,
+            }
+        };
+
+        assert_eq!(garbled, expected);
+        assert_eq!(nb_calls, circuit.get_gates().len());
+    }
+
+    #[test]
+    fn test_garble_circuit_resumable_wrong_from_gate() {
+        let circuit = parse_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut state = GarbleState::new();
+
+        // nothing to resume
+        assert!(garble_circuit_resumable(&circuit, Some(42), 1, 1, &mut state).is_err());
+        assert!(matches!(
+            garble_circuit_resumable(&circuit, Some(42), 0, 1, &mut state).unwrap(),
+            GarbleProgress::More { next_gate: 1 }
+        ));
+        assert_eq!(
+            garble_circuit_resumable(&circuit, Some(42), 2, 1, &mut state).err(),
+            Some(InterstellarError::GarblerError)
+        );
+        // the state is untouched by a wrong call
+        assert!(matches!(
+            garble_circuit_resumable(&circuit, Some(42), 1, 1, &mut state).unwrap(),
+            GarbleProgress::More { next_gate: 2 }
+        ));
+    }
+
+    #[test]
+    fn test_eval_schedule_same_outputs() {
+        let garb = garble_skcd_with_seed(
+            include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin"),
+            42,
+        )
+        .unwrap();
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "test").unwrap();
+        let evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+        let mut eval_cache = EvalCache::new();
+        let mut expected_outputs = vec![];
+        garb.eval(
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            &mut expected_outputs,
+            &mut eval_cache,
+        )
+        .unwrap();
+
+        // NOTE: the same `EvalCache` is reused across schedules, and back to `Stored`
+        for schedule in [
+            EvalSchedule::Layered,
+            EvalSchedule::DepthFirst,
+            EvalSchedule::Stored,
+        ] {
+            eval_cache.set_schedule(schedule);
+            let mut outputs = vec![];
+            garb.eval(
+                &encoded_garbler_inputs,
+                &evaluator_inputs,
+                &mut outputs,
+                &mut eval_cache,
+            )
+            .unwrap();
+
+            assert_eq!(outputs, expected_outputs, "{schedule:?}");
+        }
+    }
+
+    #[test]
+    fn test_garble_circuit_reuse_same_as_fresh() {
+        let adder: &[u8] = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        let display: &[u8] =
+            include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin");
+        let mut scratch = GarbleScratch::new();
+
+        // NOTE: alternate between circuits of different sizes to check the scratch is properly reset
+        for skcd_buf in [display, adder, display, adder] {
+            let reused =
+                garble_circuit_reuse(parse_skcd(skcd_buf).unwrap(), Some(42), &mut scratch)
+                    .unwrap();
+            let fresh = garble_circuit(parse_skcd(skcd_buf).unwrap(), Some(42)).unwrap();
+            assert_eq!(reused, fresh);
+        }
+    }
+
+    #[test]
+    fn test_output_pixel_map_display_message_120x52_2digits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let display_config = garb.get_display_config().unwrap();
+        let (width, height) = (display_config.width, display_config.height);
+
+        let pixel_map = garb.output_pixel_map().unwrap();
+
+        assert_eq!(pixel_map.len(), (width * height) as usize);
+        assert_eq!(pixel_map.len(), garb.num_outputs());
+        assert_eq!(pixel_map[0], (0, 0));
+        assert_eq!(pixel_map[1], (1, 0));
+        assert_eq!(pixel_map[width as usize], (0, 1));
+        assert_eq!(pixel_map[pixel_map.len() - 1], (width - 1, height - 1));
+    }
+
+    #[test]
+    fn test_check_display_config_dimensions_zero() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let display_config = garb.get_display_config().unwrap();
+        assert_eq!(
+            garble::check_display_config_dimensions(display_config),
+            Ok(())
+        );
+
+        let mut zero_width = display_config.clone();
+        zero_width.width = 0;
+        let mut zero_height = display_config.clone();
+        zero_height.height = 0;
+
+        for display_config in [zero_width, zero_height] {
+            assert_eq!(
+                garble::check_display_config_dimensions(&display_config),
+                Err(InterstellarError::NotAValidDisplayCircuit)
+            );
+        }
+    }
+
+    #[test]
+    fn test_prepare_garbler_inputs_auto_digits_display_message_120x52_2digits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        assert_eq!(garb.num_digits(), Ok(2));
+
+        assert!(garb
+            .prepare_garbler_inputs_auto_digits(&[4, 2], "test message")
+            .is_ok());
+        assert_eq!(
+            garb.prepare_garbler_inputs_auto_digits(&[1, 2, 3], "test message")
+                .err(),
+            Some(InterstellarError::GarblerInputs7SegmentsWrongLength {
+                got: 3,
+                expected: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_output_pixel_map_not_a_display_circuit() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            garb.output_pixel_map(),
+            Err(InterstellarError::NotAValidDisplayCircuit)
+        );
+    }
+
+    #[test]
+    fn test_circuit_depth_full_adder() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        let depth = garb.circuit_depth();
+        // 2 bits full adder: at least XOR(XOR(a,b),c); and a handful of gates at most
+        assert!((2..=10).contains(&depth), "depth = {depth}");
+    }
+
+    #[test]
+    fn test_circuit_depth_single_input_inv() {
+        let garb = garble_circuit(Circuit::new_test_circuit_unary(KindUnary::INV), None).unwrap();
+
+        assert_eq!(garb.circuit_depth(), 1);
+    }
+
+    #[test]
+    fn test_regarble_full_adder_2bits() {
+        let circuit = parse_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        let garb1 = GarbledCircuit::regarble(&circuit, Some(1)).unwrap();
+        let garb2 = GarbledCircuit::regarble(&circuit, Some(2)).unwrap();
+        assert_ne!(garb1, garb2);
+
+        let mut outputs = vec![];
+        let mut eval_cache = EvalCache::new();
+        for garb in [&garb1, &garb2] {
+            for (inputs, expected_outputs) in FULL_ADDER_2BITS_ALL_INPUTS
+                .iter()
+                .zip(FULL_ADDER_2BITS_ALL_EXPECTED_OUTPUTS)
+            {
+                garb.eval_all_plaintext(&[], inputs, &mut outputs, &mut eval_cache)
+                    .unwrap();
+                assert_eq!(outputs, expected_outputs);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pack_unpack_evaluator_inputs_round_trip() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let evaluator_inputs = garb.random_evaluator_inputs(&mut rng).unwrap();
+
+        let packed = pack_evaluator_inputs(&evaluator_inputs);
+        assert_eq!(packed.len(), evaluator_inputs.len().div_ceil(8));
+
+        assert_eq!(
+            garb.unpack_evaluator_inputs(&packed).unwrap(),
+            evaluator_inputs
+        );
+
+        // NOT a multiple of 8
+        let bits = [1, 0, 1, 1, 0, 0, 0, 0, 1, 1];
+        assert_eq!(pack_evaluator_inputs(&bits), vec![0b0000_1101, 0b0000_0011]);
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let packed = pack_evaluator_inputs(&bits[..3]);
+        assert_eq!(packed, vec![0b0000_0101]);
+        assert_eq!(garb.unpack_evaluator_inputs(&packed).unwrap(), &bits[..3]);
+    }
+
+    #[test]
+    fn test_unpack_evaluator_inputs_wrong_length() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            garb.unpack_evaluator_inputs(&[0, 0]),
+            Err(InterstellarError::EvaluatorInputsWrongLength {
+                inputs_len: 16,
+                expected_len: 3
+            })
+        );
+        assert_eq!(
+            garb.unpack_evaluator_inputs(&[]),
+            Err(InterstellarError::EvaluatorInputsWrongLength {
+                inputs_len: 0,
+                expected_len: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_random_evaluator_inputs_display_message_120x52_2digits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+
+        let evaluator_inputs1 = garb.random_evaluator_inputs(&mut rng).unwrap();
+        let evaluator_inputs2 = garb.random_evaluator_inputs(&mut rng).unwrap();
+
+        assert_eq!(evaluator_inputs1.len(), garb.num_evaluator_inputs());
+        assert_eq!(
+            evaluator_inputs1.len(),
+            prepare_evaluator_inputs(&garb).unwrap().len()
+        );
+        assert!(evaluator_inputs1.iter().all(|&bit| bit <= 1));
+        assert_ne!(evaluator_inputs1, evaluator_inputs2);
+    }
+
+    /// Single input circuits: 1 input -> unary gate -> 1 output
+    /// Check the whole "public" path: sizes, `encode_inputs` and `eval`
+    fn aux_test_single_input_circuit(gate_type: KindUnary, expected_outputs: [u8; 2]) {
+        let garb = garble_circuit(Circuit::new_test_circuit_unary(gate_type), None).unwrap();
+
+        assert_eq!(garb.num_inputs(), 0);
+        assert_eq!(garb.num_evaluator_inputs(), 1);
+        assert_eq!(garb.num_outputs(), 1);
+
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let mut outputs = vec![];
+        let mut eval_cache = EvalCache::new();
+        for (input, expected_output) in [0u8, 1].into_iter().zip(expected_outputs) {
+            garb.eval(
+                &encoded_garbler_inputs,
+                &[input],
+                &mut outputs,
+                &mut eval_cache,
+            )
+            .unwrap();
+
+            assert_eq!(outputs, vec![expected_output]);
+        }
+    }
+
+    #[test]
+    fn test_single_input_inv() {
+        aux_test_single_input_circuit(KindUnary::INV, [1, 0]);
+    }
+
+    #[test]
+    fn test_single_input_buf() {
+        aux_test_single_input_circuit(KindUnary::BUF, [0, 1]);
+    }
+
     // NOTE: more tests with "display circuits" are in tests/ folder
 }
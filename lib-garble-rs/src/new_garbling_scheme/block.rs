@@ -20,11 +20,11 @@ type MyBitArrayP = [BitsInternal; KAPPA_NB_ELEMENTS * KAPPA_FACTOR];
 /// The number of Bytes needed to store `MyBitArrayL`/`BlockL`
 /// Typically this would be 8 b/c we are using `u64` internally for `bitvec`
 /// eg KAPPA = 128 and sizeof(u64) = 8 => `KAPPA_BYTES` = 128 / 8 => 16
-// pub(super) const KAPPA_BYTES: usize = size_of::<MyBitArrayL>();
+pub const KAPPA_BYTES: usize = size_of::<MyBitArrayL>();
 /// That is the number of "internal element"(eg `BitsInternal` = u64) needed
 /// to represent a `MyBitArrayL`
 /// eg KAPPA = 128 bits  //  `BitsInternal` = u64 = 64 bits => 128 / 64 => 2 elements
-pub(super) const KAPPA_NB_ELEMENTS: usize = KAPPA / BitsInternal::BITS as usize;
+pub(crate) const KAPPA_NB_ELEMENTS: usize = KAPPA / BitsInternal::BITS as usize;
 
 /// The "external" Block,
 /// "a random string of length l" (l <=> KAPPA)
@@ -38,6 +38,13 @@ pub(super) struct BlockL {
     bits_words: MyBitArrayL,
 }
 
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for BlockL {
+    fn zeroize(&mut self) {
+        self.bits_words.zeroize();
+    }
+}
+
 /// The "internal" Block,
 /// "a random string of length l'" (l' <=> 8 * l <=> 8 * KAPPA)
 #[derive(PartialEq, Debug, Clone)]
@@ -55,29 +62,40 @@ impl BlockL {
         }
     }
 
-    pub(super) fn as_bytes(&self) -> &[u8] {
-        // let slice: &[BitsInternal] = self.bits.as_raw_slice();
-        // let ptr = slice.as_ptr() as *const u8;
-        // let len = slice.len() * std::mem::size_of::<BitsInternal>();
-        // unsafe { std::slice::from_raw_parts(ptr, len) }
-        //
+    /// Canonical little-endian byte representation, whatever the endianness of the machine.
+    /// Use this for anything hashed or sent to another machine(eg RO inputs, commitments).
+    pub(super) fn to_le_bytes(&self) -> [u8; KAPPA_BYTES] {
+        let mut bytes = [0u8; KAPPA_BYTES];
+        for (chunk, word) in bytes
+            .chunks_mut(size_of::<BitsInternal>())
+            .zip(self.bits_words.iter())
+        {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+
+        bytes
+    }
 
-        // [
-        //     self.bits_words[0].to_be_bytes(),
-        //     self.bits_words[1].to_be_bytes(),
-        // ]
-        // .concat()
-        // .as_slice()
-        // let bits = self.bits_words.view_bits::<Lsb0>();
-        // let bytes = bits.as_raw_slice();
-        // bytes
-
-        let ptr = self.bits_words.as_ptr().cast::<u8>();
-        let len = self.bits_words.len() * size_of::<u64>();
-        unsafe { alloc::slice::from_raw_parts(ptr, len) }
+    /// Build a `BlockL` from its little-endian byte representation
+    /// ie the inverse of `to_le_bytes`.
+    pub(super) fn new_from_le_bytes(bytes: &[u8; KAPPA_BYTES]) -> Self {
+        // NOTE: no `Vec` here: this is called for each Gate during eval(cf `random_oracle_g_truncated`)
+        let mut bits_words: MyBitArrayL = [0; KAPPA_NB_ELEMENTS];
+        for (word, chunk) in bits_words
+            .iter_mut()
+            .zip(bytes.chunks_exact(size_of::<BitsInternal>()))
+        {
+            *word = BitsInternal::from_le_bytes(unsafe { chunk.try_into().unwrap_unchecked() });
+        }
+
+        Self { bits_words }
+    }
+
+    /// Return true if all the bits are 0
+    pub(super) fn is_zero(&self) -> bool {
+        self.bits_words.iter().all(|word| *word == 0)
     }
 
-    #[allow(dead_code)]
     pub(super) fn xor(&self, other: &BlockL) -> BlockL {
         let bits_words: Vec<BitsInternal> = self
             .bits_words
@@ -356,4 +374,16 @@ mod tests {
 
         assert_ne!(result1, result2);
     }
+
+    #[test]
+    fn test_block_l_to_le_bytes_is_endianness_independent() {
+        let block = BlockL::new_with([0x0807_0605_0403_0201, 0x100f_0e0d_0c0b_0a09]);
+
+        let bytes = block.to_le_bytes();
+        assert_eq!(
+            bytes,
+            [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]
+        );
+        assert_eq!(BlockL::new_from_le_bytes(&bytes), block);
+    }
 }
@@ -6,6 +6,8 @@
 use circuit_types_rs::{Circuit, DisplayConfig, Gate, GateType, Metadata, WireRef};
 use serde::{Deserialize, Serialize};
 
+use super::wire_storage::WireStorage;
+
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
@@ -21,6 +23,20 @@ pub(crate) struct CircuitForEval {
     metadata: Metadata,
 }
 
+/// The first fields of `CircuitForEval`, in the same order.
+/// As postcard does not frame nested structs, this can be deserialized directly from the start
+/// of a serialized `GarbledCircuit`(or `EvaluableGarbledCircuit`) WITHOUT decoding the rest
+/// (ie `F`, `d`, `e`...); cf `peek_garbled_metadata`
+/// NOTE: `gates` MUST still be decoded b/c postcard can not skip a field.
+#[derive(Deserialize, Debug)]
+pub(crate) struct CircuitForEvalHeader {
+    pub(crate) inputs: Vec<WireRef>,
+    pub(crate) gates: Vec<GateForEval>,
+    pub(crate) nb_wires: usize,
+    pub(crate) nb_outputs: usize,
+    pub(crate) display_config: Option<DisplayConfig>,
+}
+
 /// Basically `impl Circuit`, but without `get_outputs` and `get_wires`
 impl CircuitForEval {
     pub(crate) fn get_inputs(&self) -> &[WireRef] {
@@ -50,6 +66,88 @@ impl CircuitForEval {
     pub(crate) fn get_gates(&self) -> &Vec<GateForEval> {
         &self.gates
     }
+
+    /// The wire ids of the outputs in decode order; ie `d[j]` decodes the wire `output_wire_ids()[j]`
+    /// NOTE: the outputs are NOT stored as such(cf `Circuit::get_outputs`); they are
+    /// rebuilt from the metadata, the same way `evaluate_internal` fills `Y`.
+    pub(crate) fn output_wire_ids(&self) -> Vec<usize> {
+        let mut output_wire_ids = Vec::new();
+        output_wire_ids.resize(self.nb_outputs, 0);
+        for gate in &self.gates {
+            let id = gate.get_id();
+            if self.metadata.gate_idx_is_output(id) {
+                if let Some(output_wire_id) =
+                    output_wire_ids.get_mut(self.metadata.convert_gate_id_to_outputs_index(id))
+                {
+                    *output_wire_id = id;
+                }
+            }
+        }
+
+        output_wire_ids
+    }
+
+    /// [Supporting Free-XOR] turn the `Binary` gates for which `is_free(gate_idx)` into `FreeXor`
+    /// NOTE: `gate_idx` is the position of the Gate, cf `F`
+    /// cf `garble::garble_finalize`
+    pub(crate) fn mark_free_xor_gates(&mut self, is_free: impl Fn(usize) -> bool) {
+        for (gate_idx, gate) in self.gates.iter_mut().enumerate() {
+            if let GateTypeForEval::Binary { input_a, input_b } = &gate.internal {
+                if is_free(gate_idx) {
+                    gate.internal = GateTypeForEval::FreeXor {
+                        input_a: input_a.clone(),
+                        input_b: input_b.clone(),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Longest path(in number of gates) from any input to any output.
+    /// cf `gates_depth`
+    pub(crate) fn depth(&self) -> usize {
+        gates_depth(&self.gates, self.nb_wires)
+    }
+}
+
+/// NOTE: the deepest wire is necessarily an output(or a dangling wire) so we
+/// do not need the list of outputs.
+fn gates_depth(gates: &[GateForEval], nb_wires: usize) -> usize {
+    gates_depths(gates, nb_wires).into_iter().max().unwrap_or(0)
+}
+
+/// Single pass over the gates(which are topologically sorted) accumulating the
+/// depth of each wire: inputs are at depth 0, and a gate's output is one more
+/// than the deepest of its inputs.
+/// NOTE: the circuit comes from the Garbler: a gate id outside of `nb_wires` is not stored(`evaluate_internal`
+/// rejects it anyway), and the storage is sized once from the max valid id; sparse if needed cf `WireStorage`.
+/// Return: the depth of each Gate, in the same order as `gates`
+pub(super) fn gates_depths(gates: &[GateForEval], nb_wires: usize) -> Vec<usize> {
+    let max_id = gates
+        .iter()
+        .map(GateForEval::get_id)
+        .filter(|id| *id < nb_wires)
+        .max();
+    let mut wires_depth: WireStorage<usize> = WireStorage::new();
+    wires_depth.reset(max_id.map_or(0, |id| id + 1), gates.len());
+    let mut gates_depth = Vec::with_capacity(gates.len());
+
+    for gate in gates {
+        let depth_of = |wire: &WireRef| wires_depth.get(wire.id).copied().unwrap_or(0);
+        let depth = 1 + match gate.get_type() {
+            GateTypeForEval::Binary { input_a, input_b }
+            | GateTypeForEval::FreeXor { input_a, input_b } => {
+                depth_of(input_a).max(depth_of(input_b))
+            }
+            GateTypeForEval::Unary { input_a } => depth_of(input_a),
+            GateTypeForEval::Constant { value: _ } => 0,
+        };
+
+        wires_depth.set(gate.get_id(), depth);
+        gates_depth.push(depth);
+    }
+
+    gates_depth
 }
 
 /// Same principle as `CircuitBase` but for `Gate`
@@ -79,6 +177,11 @@ pub enum GateTypeForEval {
     /// That is because contrary to Unary gates, the paper does not explain how to
     /// generalize "Garbling other gate functionalities" to 0 input gate.
     Constant { value: bool },
+    /// [Supporting Free-XOR] a XOR/XNOR gate garbled for free ie WITHOUT `∇g` in `F`; cf `garble::free_xor_labels`
+    /// ONLY set by `garble_with_delta`(cf `EvalMetadata::is_free_xor`): the other circuits keep `Binary`
+    /// so they DO NOT leak which gates are XOR.
+    /// NOTE: last variant, so the serialization of the others is unchanged.
+    FreeXor { input_a: WireRef, input_b: WireRef },
 }
 
 impl GateForEval {
@@ -98,13 +201,28 @@ impl GateForEval {
 
 impl From<Circuit> for CircuitForEval {
     fn from(circuit: Circuit) -> Self {
+        (&circuit).into()
+    }
+}
+
+/// The size of the wire id space ie what `WireStorage` is reset with.
+/// `get_nb_wires` counts the wires; for a sparse circuit(eg a Gate with id 1_000_000)
+/// that is less than the max id + 1.
+pub(crate) fn nb_wire_ids(circuit: &Circuit) -> usize {
+    circuit
+        .get_nb_wires()
+        .max(circuit.get_metadata().get_max_gate_id() + 1)
+}
+
+impl From<&Circuit> for CircuitForEval {
+    fn from(circuit: &Circuit) -> Self {
         Self {
             gates: circuit
                 .get_gates()
                 .iter()
                 .map(core::convert::Into::into)
                 .collect(),
-            nb_wires: circuit.get_nb_wires(),
+            nb_wires: nb_wire_ids(circuit),
             inputs: circuit.get_inputs().to_vec(),
             nb_outputs: circuit.get_nb_outputs(),
             display_config: circuit.get_config().map(core::clone::Clone::clone),
@@ -137,3 +255,57 @@ impl From<&Gate> for GateForEval {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn unary(input: usize, output: usize) -> GateForEval {
+        GateForEval {
+            internal: GateTypeForEval::Unary {
+                input_a: WireRef { id: input },
+            },
+            output: WireRef { id: output },
+        }
+    }
+
+    #[test]
+    fn test_gates_depth_inv_chain() {
+        // input = wire 0; then INV(0) -> 1, INV(1) -> 2, etc
+        let nb_gates = 10;
+        let gates: Vec<GateForEval> = (0..nb_gates).map(|i| unary(i, i + 1)).collect();
+
+        assert_eq!(gates_depth(&gates, nb_gates + 1), nb_gates);
+    }
+
+    #[test]
+    fn test_gates_depth_binary_takes_deepest_input() {
+        let gates = [
+            unary(0, 2),
+            unary(2, 3),
+            GateForEval {
+                internal: GateTypeForEval::Binary {
+                    input_a: WireRef { id: 1 },
+                    input_b: WireRef { id: 3 },
+                },
+                output: WireRef { id: 4 },
+            },
+        ];
+
+        assert_eq!(gates_depth(&gates, 5), 3);
+    }
+
+    /// A gate id far outside of `nb_wires`(ie a corrupted circuit) MUST NOT be allocated
+    #[test]
+    fn test_gates_depths_id_out_of_range() {
+        let gates = [unary(0, 1), unary(1, usize::MAX), unary(usize::MAX, 2)];
+
+        assert_eq!(gates_depths(&gates, 3), vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn test_gates_depth_empty() {
+        assert_eq!(gates_depth(&[], 3), 0);
+    }
+}
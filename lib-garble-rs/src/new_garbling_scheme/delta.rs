@@ -2,11 +2,12 @@ use alloc::vec;
 
 use serde::{Deserialize, Serialize};
 
-use circuit_types_rs::{KindBinary, KindUnary};
+use circuit_types_rs::{Gate, GateType, KindBinary, KindUnary, WireRef};
 
 use super::{
     block::BlockL,
     block::BlockP,
+    block::KAPPA_BYTES,
     constant::{KAPPA, KAPPA_FACTOR},
     wire_labels_set::WireLabelsSet,
     wire_labels_set_bitslice::{WireLabelsSetBitSlice, WireLabelsSetBitsSliceInternal},
@@ -19,6 +20,16 @@ pub(super) struct Delta {
 }
 
 impl Delta {
+    /// Upper bound of the serialized(postcard) size of a `Delta`.
+    /// The words of the block are varint-encoded so a random `Delta` is usually a bit smaller;
+    /// all bits set is the worst case.
+    pub(super) fn max_serialized_size() -> usize {
+        let delta = Self {
+            block: BlockL::new_from_le_bytes(&[0xff; KAPPA_BYTES]),
+        };
+        postcard::to_allocvec(&delta).map_or(0, |bytes| bytes.len())
+    }
+
     /// Build a new `Delta` from the desired `GateType`
     ///
     /// In <https://eprint.iacr.org/2021/739.pdf>
@@ -45,9 +56,11 @@ impl Delta {
     ///     - NOTE: if a group has more than on Sxy/Sx column with a given value (eg S01 and S00) you can pick whichever
     ///       you want; what matters is to be determistic b/w garbling and evaluating (ie use the same one!)
     ///
+    /// `gate_id`: ONLY used for error reporting
     pub(super) fn new(
         compressed_set: &WireLabelsSet,
         gate_type_binary: &KindBinary,
+        gate_id: usize,
     ) -> Result<(BlockP, BlockP, Self), GarblerError> {
         // "5: initialize ∇g ← 0ℓ′ and let j = 1"
         // "Next, the random oracle outputs (Xg00, Xg01, Xg10, Xg11) are used to derive a
@@ -89,51 +102,32 @@ impl Delta {
             // "16: ABORT the computation"
             return Err(GarblerError::BadHammingWeight {
                 hw: count_bits_ones,
+                gate_id,
+                gate_type: gate_type_binary.clone(),
             });
         }
 
         // Following are after line 19: of "Algorithm 5 Gate"
         //
-        // To know how to "project" cf docstring
+        // To know how to "project" cf docstring and `ProjectionSources::new`
         //
         // NOTE: both `CompressedSet`(randomly generated) and `Delta` are `BlockP`
         // NOTE: `Delta` is technically a `BlockL` padded to a `BlockP`(?)
         // TODO? but we want a `BlockL`
         // TODO same issue with `l1`
-        #[allow(clippy::match_same_arms)]
-        let (l0_full, l1_full) = match gate_type_binary {
-            KindBinary::XOR => (
-                BlockP::new_projection(compressed_set.get_x00(), &delta_g_block),
-                BlockP::new_projection(compressed_set.get_x01(), &delta_g_block),
-            ),
-            KindBinary::XNOR => (
-                BlockP::new_projection(compressed_set.get_x01(), &delta_g_block),
-                BlockP::new_projection(compressed_set.get_x00(), &delta_g_block),
-            ),
-            KindBinary::AND => (
-                BlockP::new_projection(compressed_set.get_x00(), &delta_g_block),
-                BlockP::new_projection(compressed_set.get_x11(), &delta_g_block),
-            ),
-            KindBinary::NAND => (
-                BlockP::new_projection(compressed_set.get_x11(), &delta_g_block),
-                BlockP::new_projection(compressed_set.get_x00(), &delta_g_block),
-            ),
-            KindBinary::OR => (
-                BlockP::new_projection(compressed_set.get_x00(), &delta_g_block),
-                BlockP::new_projection(compressed_set.get_x01(), &delta_g_block),
-            ),
-            KindBinary::NOR => (
-                BlockP::new_projection(compressed_set.get_x01(), &delta_g_block),
-                BlockP::new_projection(compressed_set.get_x00(), &delta_g_block),
-            ),
-        };
+        let (l0_full, l1_full) =
+            ProjectionSources::new(gate_type_binary).project(compressed_set, &delta_g_block);
 
         let delta = Self {
             block: delta_g_block.into(),
         };
 
         // cf `Wire::new` assert for why this is bad
-        assert!(l0_full != l1_full, "`L0` and `L1` MUST be different!");
+        strict_assert!(
+            l0_full != l1_full,
+            GarblerError::DeltaSameLabels,
+            "`L0` and `L1` MUST be different!"
+        );
         Ok((l0_full, l1_full, delta))
     }
 
@@ -142,6 +136,57 @@ impl Delta {
     }
 }
 
+/// Which column of the `WireLabelsSet` is projected(with ∇) to get L0/L1
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum ProjectionSource {
+    X00,
+    X01,
+    X11,
+}
+
+impl ProjectionSource {
+    fn get(self, compressed_set: &WireLabelsSet) -> &BlockP {
+        match self {
+            ProjectionSource::X00 => compressed_set.get_x00(),
+            ProjectionSource::X01 => compressed_set.get_x01(),
+            ProjectionSource::X11 => compressed_set.get_x11(),
+        }
+    }
+}
+
+/// The (L0, L1) sources for a given binary Gate; that is the "group the COLUMNS Sxy" step
+/// of `Delta::new`.
+/// When adding a new `KindBinary`, only this table has to be updated.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct ProjectionSources {
+    l0: ProjectionSource,
+    l1: ProjectionSource,
+}
+
+impl ProjectionSources {
+    #[allow(clippy::match_same_arms)]
+    fn new(gate_type_binary: &KindBinary) -> Self {
+        let (l0, l1) = match gate_type_binary {
+            KindBinary::XOR => (ProjectionSource::X00, ProjectionSource::X01),
+            KindBinary::XNOR => (ProjectionSource::X01, ProjectionSource::X00),
+            KindBinary::AND => (ProjectionSource::X00, ProjectionSource::X11),
+            KindBinary::NAND => (ProjectionSource::X11, ProjectionSource::X00),
+            KindBinary::OR => (ProjectionSource::X00, ProjectionSource::X01),
+            KindBinary::NOR => (ProjectionSource::X01, ProjectionSource::X00),
+        };
+
+        Self { l0, l1 }
+    }
+
+    /// Return (L0, L1)
+    fn project(self, compressed_set: &WireLabelsSet, delta_g_block: &BlockP) -> (BlockP, BlockP) {
+        (
+            BlockP::new_projection(self.l0.get(compressed_set), delta_g_block),
+            BlockP::new_projection(self.l1.get(compressed_set), delta_g_block),
+        )
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Below: was trying to make the Delta dynamic instead of hardcoding the
 // few types of Gates we need...
@@ -474,15 +519,123 @@ struct TruthTable {
     truth_table: WireLabelsSetBitSlice,
 }
 
+/// The canonical truth table of a Gate; cf `gate_truth_table`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GateTruthTable {
+    /// Outputs for the inputs (a,b) = 00, 01, 10, 11
+    Binary([bool; 4]),
+    /// Outputs for the input a = 0, 1
+    Unary([bool; 2]),
+}
+
+/// Return the truth table of any supported Gate.
+/// This is the same table that is used when garbling(cf `Delta::new`).
+///
+/// NOTE: a `Constant` gate is returned as a `Unary` one, with its value repeated.
+#[must_use]
+pub fn gate_truth_table(gate_type: &GateType) -> GateTruthTable {
+    match gate_type {
+        GateType::Binary { gate_type, .. } => TruthTable::new_from_binary_gate(gate_type).to_bits(),
+        GateType::Unary { gate_type, .. } => TruthTable::new_from_unary_gate(gate_type).to_bits(),
+        GateType::Constant { value } => GateTruthTable::Unary([*value, *value]),
+    }
+}
+
+/// How a circuit is garbled; it decides which gates are "free"(cf `GateKindIsFree`)
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum GarblingMode {
+    /// `garble_circuit` and the others: only the unary and constant gates are free
+    #[default]
+    Standard,
+    /// `garble_circuit_with_delta`: XOR/XNOR are free too, as long as both their inputs
+    /// are on the Free-XOR delta; cf `FreeXorWires`
+    FreeXor,
+}
+
+/// `is_free` for the gate kinds of circuit-types-rs(which can not get inherent methods here)
+pub trait GateKindIsFree {
+    /// Whether a Gate of this kind gets NO `Delta` in `F`(and costs no RO call at eval time)
+    /// when garbled with `mode`
+    fn is_free(&self, mode: GarblingMode) -> bool;
+}
+
+/// INV/BUF: free, cf "free-NOT" in `garble_internal`
+impl GateKindIsFree for KindUnary {
+    fn is_free(&self, _mode: GarblingMode) -> bool {
+        match self {
+            KindUnary::INV | KindUnary::BUF => true,
+        }
+    }
+}
+
+/// XOR/XNOR: free ONLY with `GarblingMode::FreeXor`, and if both inputs are on the Free-XOR delta;
+/// the others are always materialized
+impl GateKindIsFree for KindBinary {
+    fn is_free(&self, mode: GarblingMode) -> bool {
+        match self {
+            KindBinary::XOR | KindBinary::XNOR => mode == GarblingMode::FreeXor,
+            KindBinary::AND | KindBinary::NAND | KindBinary::OR | KindBinary::NOR => false,
+        }
+    }
+}
+
+/// Whether a Gate of this type is "free" in this implementation ie it gets NO `Delta` in `F`;
+/// cf `GateKindIsFree`. The constant gates are free: they are evaluated in the clear.
+/// NOTE: with `GarblingMode::FreeXor` a XOR/XNOR gate is ONLY free when both its inputs are on the
+/// Free-XOR delta, which depends on the rest of the circuit; cf `materialized_gate_count`
+#[must_use]
+pub fn is_gate_type_free(gate_type: &GateType, mode: GarblingMode) -> bool {
+    match gate_type {
+        GateType::Binary { gate_type, .. } => gate_type.is_free(mode),
+        GateType::Unary { gate_type, .. } => gate_type.is_free(mode),
+        GateType::Constant { .. } => true,
+    }
+}
+
+/// [Supporting Free-XOR] The wires whose labels respect `LW0 ⊕ LW1 = r`: the inputs, then the
+/// outputs of the free gates(INV/BUF/XOR/XNOR) reading only such wires.
+/// NOT the outputs of the materialized gates(projections, cf `Delta::new`) nor of the constants.
+/// Shared by the garbler(cf `garble_gates`) and `materialized_gate_count` so that they agree.
+pub(crate) struct FreeXorWires(hashbrown::HashSet<usize>);
+
+impl FreeXorWires {
+    pub(crate) fn new(inputs: &[WireRef]) -> Self {
+        Self(inputs.iter().map(|input| input.id).collect())
+    }
+
+    /// Must be called for every Gate, in topological order.
+    /// Return true if `gate` is a XOR/XNOR garbled for free with `GarblingMode::FreeXor`
+    pub(crate) fn insert_gate(&mut self, gate: &Gate) -> bool {
+        let (is_on_delta, is_free_xor) = match gate.get_type() {
+            GateType::Binary {
+                gate_type,
+                input_a,
+                input_b,
+            } => {
+                let is_free_xor = gate_type.is_free(GarblingMode::FreeXor)
+                    && self.0.contains(&input_a.id)
+                    && self.0.contains(&input_b.id);
+                (is_free_xor, is_free_xor)
+            }
+            GateType::Unary { input_a, .. } => (self.0.contains(&input_a.id), false),
+            GateType::Constant { .. } => (false, false),
+        };
+        if is_on_delta {
+            self.0.insert(gate.get_output().id);
+        }
+
+        is_free_xor
+    }
+}
+
 impl TruthTable {
-    #[allow(dead_code)]
     pub(self) fn new_from_unary_gate(gate_type_unary: &KindUnary) -> Self {
         match gate_type_unary {
             KindUnary::INV => TruthTable {
-                truth_table: WireLabelsSetBitSlice::new_unary_gate_from_bool(false, true),
+                truth_table: WireLabelsSetBitSlice::new_unary_gate_from_bool(true, false),
             },
             KindUnary::BUF => TruthTable {
-                truth_table: WireLabelsSetBitSlice::new_unary_gate_from_bool(true, false),
+                truth_table: WireLabelsSetBitSlice::new_unary_gate_from_bool(false, true),
             },
         }
     }
@@ -523,6 +676,17 @@ impl TruthTable {
         }
     }
 
+    pub(self) fn to_bits(&self) -> GateTruthTable {
+        match &self.truth_table.internal {
+            WireLabelsSetBitsSliceInternal::BinaryGate { x00, x01, x10, x11 } => {
+                GateTruthTable::Binary([x00.value, x01.value, x10.value, x11.value])
+            }
+            WireLabelsSetBitsSliceInternal::UnaryGate { x0, x1 } => {
+                GateTruthTable::Unary([x0.value, x1.value])
+            }
+        }
+    }
+
     pub(self) fn get_complement(&self) -> WireLabelsSetBitSlice {
         match &self.truth_table.internal {
             WireLabelsSetBitsSliceInternal::BinaryGate { x00, x01, x10, x11 } => {
@@ -540,7 +704,10 @@ impl TruthTable {
 #[cfg(test)]
 mod tests {
     use rand::rngs::ThreadRng;
-    use rand::Rng;
+    use rand::{Rng, RngCore, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
 
     /// Minimal Reprodocible Example for Delta for a NAND Gate
     /// Helpful to visualize of the algorithm works if we hardcoded all the truth tables etc
@@ -645,6 +812,168 @@ mod tests {
     //     /// 0 0 1 1
     //     /// ...
     //     /// 1 1 1 1
+    fn new_binary_gate_type(gate_type: KindBinary) -> GateType {
+        GateType::Binary {
+            gate_type,
+            input_a: WireRef { id: 0 },
+            input_b: WireRef { id: 1 },
+        }
+    }
+
+    fn eval_binary(gate_type_binary: &KindBinary, a: bool, b: bool) -> bool {
+        match gate_type_binary {
+            KindBinary::XOR => a ^ b,
+            KindBinary::XNOR => !(a ^ b),
+            KindBinary::AND => a & b,
+            KindBinary::NAND => !(a & b),
+            KindBinary::OR => a | b,
+            KindBinary::NOR => !(a | b),
+        }
+    }
+
+    /// Brute force (∇, L0, L1) from the gate's truth table, without `TruthTable`/`ProjectionSources`:
+    /// - ∇ is set on the bits whose slice (x00,x01,x10,x11) is constant, the truth table or
+    ///   its complement; up to HW(∇) = `KAPPA` like `Delta::new`
+    /// - then EVERY Xab projected with ∇ MUST be the L of the output of the gate for (a, b)
+    fn reference_projections(
+        compressed_set: &WireLabelsSet,
+        gate_type_binary: &KindBinary,
+    ) -> (BlockP, BlockP, BlockP) {
+        let columns = [
+            compressed_set.get_x00(),
+            compressed_set.get_x01(),
+            compressed_set.get_x10(),
+            compressed_set.get_x11(),
+        ];
+        let outputs = [(false, false), (false, true), (true, false), (true, true)]
+            .map(|(a, b)| eval_binary(gate_type_binary, a, b));
+
+        let mut delta = BlockP::new_zero();
+        let mut hamming_weight = 0;
+        for j in 0..KAPPA * KAPPA_FACTOR {
+            let slice = columns.map(|x| x.get_bit(j).unwrap().value);
+            if slice.iter().all(|bit| *bit == slice[0])
+                || slice == outputs
+                || slice == outputs.map(|output| !output)
+            {
+                delta.set_bit(j);
+                hamming_weight += 1;
+            }
+            if hamming_weight == KAPPA {
+                break;
+            }
+        }
+
+        let mut l: [Option<BlockP>; 2] = [None, None];
+        for (x, output) in columns.iter().zip(outputs) {
+            let projection = BlockP::new_projection(x, &delta);
+            match &l[usize::from(output)] {
+                Some(expected) => assert_eq!(&projection, expected, "{gate_type_binary:?}"),
+                None => l[usize::from(output)] = Some(projection),
+            }
+        }
+        let [l0, l1] = l;
+
+        (delta, l0.unwrap(), l1.unwrap())
+    }
+
+    fn new_fixed_block(rng: &mut ChaChaRng) -> BlockP {
+        let mut bytes = [0u8; KAPPA_BYTES * KAPPA_FACTOR];
+        rng.fill_bytes(&mut bytes);
+        BlockP::new_with_raw_bytes(bytes)
+    }
+
+    #[test]
+    fn test_projection_sources_match_reference() {
+        for seed in 0..8 {
+            let mut rng = ChaChaRng::seed_from_u64(seed);
+            let compressed_set = WireLabelsSet::new_binary(
+                new_fixed_block(&mut rng),
+                new_fixed_block(&mut rng),
+                new_fixed_block(&mut rng),
+                new_fixed_block(&mut rng),
+            );
+
+            for gate_type in [
+                KindBinary::XOR,
+                KindBinary::XNOR,
+                KindBinary::AND,
+                KindBinary::NAND,
+                KindBinary::OR,
+                KindBinary::NOR,
+            ] {
+                let (delta_g_block, l0, l1) = reference_projections(&compressed_set, &gate_type);
+
+                assert_eq!(
+                    ProjectionSources::new(&gate_type).project(&compressed_set, &delta_g_block),
+                    (l0.clone(), l1.clone()),
+                    "{gate_type:?} seed {seed}"
+                );
+                // and the full path
+                let (l0_full, l1_full, _delta) =
+                    Delta::new(&compressed_set, &gate_type, 0).unwrap();
+                assert_eq!((l0_full, l1_full), (l0, l1), "{gate_type:?} seed {seed}");
+            }
+        }
+    }
+
+    /// Same effect as a (too) low `KAPPA_FACTOR`: NO slice is in the "delta set" of an AND
+    /// so HW(∇) can never reach `KAPPA`
+    #[test]
+    fn test_delta_new_bad_hamming_weight_reports_gate() {
+        let mut x10 = BlockP::new_zero();
+        x10.set_bit(0);
+        let mut x11 = BlockP::new_zero();
+        x11.set_bit(1);
+        let compressed_set = WireLabelsSet::new_binary(
+            BlockP::new_zero(),
+            BlockP::new_with_raw_bytes([0xff; KAPPA_BYTES * KAPPA_FACTOR]),
+            x10,
+            x11,
+        );
+
+        let res = Delta::new(&compressed_set, &KindBinary::AND, 4213);
+
+        assert!(
+            matches!(
+                res,
+                Err(GarblerError::BadHammingWeight {
+                    hw: 0,
+                    gate_id: 4213,
+                    gate_type: KindBinary::AND,
+                })
+            ),
+            "{res:?}"
+        );
+    }
+
+    #[test]
+    fn test_gate_truth_table_binary() {
+        assert_eq!(
+            gate_truth_table(&new_binary_gate_type(KindBinary::XOR)),
+            GateTruthTable::Binary([false, true, true, false])
+        );
+        assert_eq!(
+            gate_truth_table(&new_binary_gate_type(KindBinary::AND)),
+            GateTruthTable::Binary([false, false, false, true])
+        );
+    }
+
+    #[test]
+    fn test_gate_truth_table_unary_and_constant() {
+        assert_eq!(
+            gate_truth_table(&GateType::Unary {
+                gate_type: KindUnary::INV,
+                input_a: WireRef { id: 0 },
+            }),
+            GateTruthTable::Unary([true, false])
+        );
+        assert_eq!(
+            gate_truth_table(&GateType::Constant { value: true }),
+            GateTruthTable::Unary([true, true])
+        );
+    }
+
     //     #[test]
     //     fn test_delta_table_Xab() {
     //         // NOTE: for this we only care about the first 4 cols; so the GateType does not matter
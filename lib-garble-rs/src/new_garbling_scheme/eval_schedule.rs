@@ -0,0 +1,269 @@
+//! Order in which `evaluate_internal` walks the gates; cf `EvalCache::set_schedule`
+//!
+//! The stored order is the one produced by the synthesis, which is topological but not
+//! necessarily cache-friendly: a wire label can stay alive long after its last consumer.
+//! The other schedules reorder the iteration(the stored circuit is NOT modified) and free each
+//! label right after its last use, which bounds the number of live labels.
+
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+
+use crate::InterstellarEvaluatorError;
+
+use super::circuit_for_eval::{gates_depths, CircuitForEval, GateForEval, GateTypeForEval};
+use super::wire_storage::WireStorage;
+
+/// cf module doc
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum EvalSchedule {
+    /// The gates as stored in the circuit; labels are kept until the end of the eval
+    #[default]
+    Stored,
+    /// BFS-by-layer: all the gates of depth 1, then depth 2, etc
+    Layered,
+    /// DFS from each output(in decode order): the gates feeding an output are evaluated
+    /// right before it, so their labels can be dropped early
+    DepthFirst,
+}
+
+/// The inputs of a Gate; the same wire is listed twice for eg XOR(A,A)
+fn gate_inputs(gate: &GateForEval) -> impl Iterator<Item = usize> + '_ {
+    let (input_a, input_b) = match gate.get_type() {
+        GateTypeForEval::Binary { input_a, input_b }
+        | GateTypeForEval::FreeXor { input_a, input_b } => (Some(input_a.id), Some(input_b.id)),
+        GateTypeForEval::Unary { input_a } => (Some(input_a.id), None),
+        GateTypeForEval::Constant { value: _ } => (None, None),
+    };
+    input_a.into_iter().chain(input_b)
+}
+
+/// Remaining number of consumers of each wire; when it reaches 0 the label can be dropped
+pub(super) struct FanOut(WireStorage<usize>);
+
+impl FanOut {
+    /// `gate` was just evaluated: release its inputs
+    pub(super) fn release_inputs<T>(
+        &mut self,
+        gate: &GateForEval,
+        wire_labels: &mut WireStorage<T>,
+    ) {
+        for wire_id in gate_inputs(gate) {
+            let remaining = self.0.get(wire_id).copied().unwrap_or(0).saturating_sub(1);
+            self.0.set(wire_id, remaining);
+            if remaining == 0 {
+                wire_labels.remove(wire_id);
+            }
+        }
+    }
+
+    /// A Gate whose output is never consumed(ie an output, already copied into `Y`, or a dangling wire)
+    pub(super) fn drop_if_unused<T>(&self, wire_id: usize, wire_labels: &mut WireStorage<T>) {
+        if self.0.get(wire_id).is_none() {
+            wire_labels.remove(wire_id);
+        }
+    }
+}
+
+/// Reusable buffers; part of `EvalCache`
+pub(super) struct Scheduler {
+    schedule: EvalSchedule,
+    /// indices in `get_gates()`
+    order: Vec<usize>,
+    fan_out: FanOut,
+}
+
+impl Scheduler {
+    pub(super) fn new() -> Self {
+        Self {
+            schedule: EvalSchedule::default(),
+            order: Vec::new(),
+            fan_out: FanOut(WireStorage::new()),
+        }
+    }
+
+    pub(super) fn set_schedule(&mut self, schedule: EvalSchedule) {
+        self.schedule = schedule;
+    }
+
+    /// Compute the order of the gates of `circuit` for the current `EvalSchedule`
+    /// Return: the order, and the `FanOut` to free the labels early(None for `EvalSchedule::Stored`)
+    ///
+    /// # Errors
+    /// - `EvaluateErrorCycle` if the gates are NOT a DAG(only detected by `EvalSchedule::DepthFirst`);
+    ///   the circuit comes from the Garbler so DO NOT loop forever on it
+    pub(super) fn prepare(
+        &mut self,
+        circuit: &CircuitForEval,
+    ) -> Result<(&[usize], Option<&mut FanOut>), InterstellarEvaluatorError> {
+        let gates = circuit.get_gates();
+        self.order.clear();
+        match self.schedule {
+            EvalSchedule::Stored => {
+                self.order.extend(0..gates.len());
+                return Ok((&self.order, None));
+            }
+            EvalSchedule::Layered => {
+                let depths = gates_depths(gates, circuit.get_nb_wires());
+                self.order.extend(0..gates.len());
+                // NOTE: stable sort; within a layer the stored order is kept
+                self.order.sort_by_key(|gate_idx| depths[*gate_idx]);
+            }
+            EvalSchedule::DepthFirst => {
+                depth_first_order(circuit, &mut self.order)?;
+            }
+        }
+
+        let fan_out = &mut self.fan_out.0;
+        fan_out.reset(
+            circuit.get_nb_wires(),
+            circuit.get_nb_inputs() + gates.len(),
+        );
+        for wire_id in gates.iter().flat_map(gate_inputs) {
+            let count = fan_out.get(wire_id).copied().unwrap_or(0);
+            fan_out.set(wire_id, count + 1);
+        }
+
+        Ok((&self.order, Some(&mut self.fan_out)))
+    }
+}
+
+/// `depth_first_order`: state of each Gate
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    New,
+    /// expanded ie its inputs are being visited; reaching it again means a cycle
+    OnStack,
+    Done,
+}
+
+/// Post-order DFS from each output(in decode order), then the gates NOT feeding any output
+/// in stored order; which is still topological b/c their own inputs come before them.
+///
+/// # Errors
+/// - `EvaluateErrorCycle` if a Gate is reached again while its inputs are still being visited
+fn depth_first_order(
+    circuit: &CircuitForEval,
+    order: &mut Vec<usize>,
+) -> Result<(), InterstellarEvaluatorError> {
+    let gates = circuit.get_gates();
+    let gate_idx_by_wire: HashMap<usize, usize> = gates
+        .iter()
+        .enumerate()
+        .map(|(gate_idx, gate)| (gate.get_id(), gate_idx))
+        .collect();
+    let mut visits = Vec::new();
+    visits.resize(gates.len(), Visit::New);
+
+    // (gate_idx, are its inputs already pushed)
+    let mut stack: Vec<(usize, bool)> = Vec::new();
+    let roots = circuit
+        .output_wire_ids()
+        .into_iter()
+        .filter_map(|wire_id| gate_idx_by_wire.get(&wire_id).copied())
+        .chain(0..gates.len());
+    for root in roots {
+        stack.push((root, false));
+        while let Some((gate_idx, is_expanded)) = stack.pop() {
+            match (visits[gate_idx], is_expanded) {
+                (Visit::Done, _) => {}
+                (Visit::OnStack, true) => {
+                    visits[gate_idx] = Visit::Done;
+                    order.push(gate_idx);
+                }
+                (Visit::OnStack, false) => {
+                    return Err(InterstellarEvaluatorError::EvaluateErrorCycle {
+                        idx: gates[gate_idx].get_id(),
+                    });
+                }
+                (Visit::New, _) => {
+                    visits[gate_idx] = Visit::OnStack;
+                    stack.push((gate_idx, true));
+                    stack.extend(
+                        gate_inputs(&gates[gate_idx])
+                            .filter_map(|wire_id| gate_idx_by_wire.get(&wire_id).copied())
+                            .filter(|input_gate_idx| visits[*input_gate_idx] != Visit::Done)
+                            .map(|input_gate_idx| (input_gate_idx, false)),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// every Gate exactly once, and after the gates producing its inputs
+    fn assert_topological(circuit: &CircuitForEval, order: &[usize]) {
+        let gates = circuit.get_gates();
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..gates.len()).collect::<Vec<_>>());
+
+        let mut is_done: hashbrown::HashSet<usize> =
+            circuit.get_inputs().iter().map(|input| input.id).collect();
+        for gate_idx in order {
+            let gate = &gates[*gate_idx];
+            assert!(gate_inputs(gate).all(|wire_id| is_done.contains(&wire_id)));
+            is_done.insert(gate.get_id());
+        }
+    }
+
+    #[test]
+    fn test_schedules_are_topological() {
+        let circuit = crate::parse_skcd(include_bytes!(
+            "../../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let circuit = CircuitForEval::from(&circuit);
+        let mut scheduler = Scheduler::new();
+
+        for schedule in [
+            EvalSchedule::Stored,
+            EvalSchedule::Layered,
+            EvalSchedule::DepthFirst,
+        ] {
+            scheduler.set_schedule(schedule);
+            let (order, fan_out) = scheduler.prepare(&circuit).unwrap();
+
+            assert_topological(&circuit, order);
+            assert_eq!(fan_out.is_none(), schedule == EvalSchedule::Stored);
+        }
+    }
+
+    /// A Gate reading its own output, or two gates reading each other: MUST NOT loop forever
+    #[test]
+    fn test_depth_first_cycle_returns_error() {
+        use circuit_types_rs::{GateType, KindUnary, WireRef};
+
+        let inv = |input, output| {
+            (
+                GateType::Unary {
+                    gate_type: KindUnary::INV,
+                    input_a: WireRef { id: input },
+                },
+                WireRef { id: output },
+            )
+        };
+        let mut scheduler = Scheduler::new();
+        scheduler.set_schedule(EvalSchedule::DepthFirst);
+
+        for gates in [alloc::vec![inv(1, 1)], alloc::vec![inv(2, 1), inv(1, 2)]] {
+            let circuit = crate::circuit_builder::build_circuit(
+                alloc::vec![WireRef { id: 0 }],
+                gates,
+                alloc::vec![WireRef { id: 1 }],
+                None,
+            )
+            .unwrap();
+
+            assert!(matches!(
+                scheduler.prepare(&CircuitForEval::from(&circuit)),
+                Err(InterstellarEvaluatorError::EvaluateErrorCycle { idx: 1 })
+            ));
+        }
+    }
+}
@@ -7,10 +7,12 @@ use circuit_types_rs::WireRef;
 use crate::{new_garbling_scheme::wire::WireLabel, InterstellarEvaluatorError};
 
 use super::{
-    block::BlockL,
+    block::{BlockL, KAPPA_BYTES},
     circuit_for_eval::{CircuitForEval, GateTypeForEval},
+    eval_schedule::{EvalSchedule, Scheduler},
     garble::{DecodedInfo, GarbledCircuitFinal, InputEncodingSet, F},
     random_oracle::RandomOracle,
+    wire_storage::WireStorage,
     wire_value::WireValue,
 };
 
@@ -35,6 +37,27 @@ impl EncodedInfo {
     pub(crate) fn len(&self) -> usize {
         self.x.len()
     }
+
+    pub(super) fn labels(&self) -> &[WireLabel] {
+        &self.x
+    }
+
+    /// One label per input, as little-endian bytes; eg the output labels of another circuit
+    /// cf `GarbledCircuit::eval_with_input_labels`
+    pub(crate) fn from_le_bytes(labels: &[[u8; KAPPA_BYTES]]) -> Self {
+        Self {
+            x: labels
+                .iter()
+                .map(|label| WireLabel::new(&BlockL::new_from_le_bytes(label)))
+                .collect(),
+        }
+    }
+
+    /// Append `other` at the end of `self`
+    /// cf `EncodedGarblerInputs::merge`
+    pub(crate) fn extend(&mut self, other: EncodedInfo) {
+        self.x.extend(other.x);
+    }
 }
 
 /// Encoding
@@ -69,22 +92,31 @@ fn encoding_internal<'a>(
     encoded_info: &mut EncodedInfo,
     inputs_start_index: usize,
     inputs_end_index: usize,
-) {
+) -> Result<(), InterstellarEvaluatorError> {
     // CHECK: we SHOULD have one "user input" for each Circuit's input(ie == `circuit.n`)
-    assert_eq!(
-        inputs_end_index - inputs_start_index,
-        inputs.len(),
+    strict_assert!(
+        inputs_end_index.checked_sub(inputs_start_index) == Some(inputs.len()),
+        InterstellarEvaluatorError::EvaluateErrorWrongInputsLength {
+            inputs_len: inputs.len(),
+            expected_len: inputs_end_index.saturating_sub(inputs_start_index),
+        },
         "encoding: `x` inputs len MUST match the Circuit's inputs len!"
     );
+    // NOTE: `circuit` and `e` come from the Garbler; DO NOT panic if they are inconsistent
+    let input_wires = circuit
+        .get_inputs()
+        .get(inputs_start_index..inputs_end_index)
+        .ok_or(InterstellarEvaluatorError::EvaluateErrorWireOutOfRange {
+            idx: inputs_end_index,
+        })?;
 
     // NOTE: contrary to the papers, we added the concept of "Garbler inputs" vs "Evaluator inputs"
     // which means the loop is in a different order.
     // ie we loop of the "wire value"(given by the user/evaluator/garbler) instead of the `circuit.inputs`
-    for (input_wire, input_value) in circuit.get_inputs()[inputs_start_index..inputs_end_index]
-        .iter()
-        .zip(inputs)
-    {
-        let encoded_wire = &e.e[input_wire.id];
+    for (input_wire, input_value) in input_wires.iter().zip(inputs) {
+        let encoded_wire = e.e.get(input_wire.id).ok_or(
+            InterstellarEvaluatorError::EvaluateErrorWireOutOfRange { idx: input_wire.id },
+        )?;
         let block = if input_value.value {
             encoded_wire.value1()
         } else {
@@ -101,6 +133,8 @@ fn encoding_internal<'a>(
     //     e.e.len(),
     //     "EncodedInfo: wrong length!"
     // );
+
+    Ok(())
 }
 
 /// Noted `Y` in the paper
@@ -116,6 +150,40 @@ impl OutputLabels {
     }
 }
 
+/// Number of RO calls done during eval; cf `EvalCache::profile`
+///
+/// NOTE: without the `profile` feature this is empty, and counting is a noop
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct EvalProfile {
+    /// `random_oracle_g_truncated` calls in `evaluate_internal`; ie one per binary Gate
+    #[cfg(feature = "profile")]
+    pub ro_g_calls: usize,
+    /// `random_oracle_prime` calls when decoding; ie one per output
+    #[cfg(feature = "profile")]
+    pub ro_prime_calls: usize,
+}
+
+impl EvalProfile {
+    #[allow(clippy::unused_self)]
+    #[inline]
+    fn count_ro_g(&mut self) {
+        #[cfg(feature = "profile")]
+        {
+            self.ro_g_calls += 1;
+        }
+    }
+
+    #[allow(clippy::unused_self)]
+    #[cfg_attr(not(feature = "profile"), allow(unused_variables))]
+    #[inline]
+    fn count_ro_prime(&mut self, nb_calls: usize) {
+        #[cfg(feature = "profile")]
+        {
+            self.ro_prime_calls += nb_calls;
+        }
+    }
+}
+
 /// This is what is needed to evaluate in-place as much as possible
 /// ie a bunch of "temp vec" and various "buffers"
 pub struct EvalCache {
@@ -124,7 +192,11 @@ pub struct EvalCache {
     /// This is used to avoid alloc in `decoding_internal` during eval
     outputs_bufs: Vec<BytesMut>,
     ro_buf: BytesMut,
-    wire_labels: Vec<Option<WireLabel>>,
+    wire_labels: WireStorage<WireLabel>,
+    /// cf `EvalCache::set_schedule`
+    scheduler: Scheduler,
+    /// cf `EvalCache::profile`
+    profile: EvalProfile,
 }
 
 impl EvalCache {
@@ -133,10 +205,25 @@ impl EvalCache {
         Self {
             output_labels: OutputLabels::new(),
             outputs_bufs: Vec::new(),
-            ro_buf: BytesMut::new(),
-            wire_labels: Vec::new(),
+            ro_buf: BytesMut::with_capacity(RandomOracle::max_buf_len()),
+            wire_labels: WireStorage::new(),
+            scheduler: Scheduler::new(),
+            profile: EvalProfile::default(),
         }
     }
+
+    /// Change the order in which the gates are evaluated, for all the following evals;
+    /// the outputs are the same whatever the `EvalSchedule`. cf `EvalSchedule`
+    pub fn set_schedule(&mut self, schedule: EvalSchedule) {
+        self.scheduler.set_schedule(schedule);
+    }
+
+    /// The RO calls counters, accumulated over all the evals done with this `EvalCache`
+    #[cfg(feature = "profile")]
+    #[must_use]
+    pub fn profile(&self) -> &EvalProfile {
+        &self.profile
+    }
 }
 
 impl Default for EvalCache {
@@ -161,15 +248,22 @@ impl Default for EvalCache {
 fn evaluate_internal(
     circuit: &CircuitForEval,
     f: &F,
+    is_free_xor: bool,
     encoded_info: &EncodedInfo,
     output_labels: &mut OutputLabels,
     ro_buf: &mut BytesMut,
-    wire_labels: &mut Vec<Option<WireLabel>>,
+    wire_labels: &mut WireStorage<WireLabel>,
+    scheduler: &mut Scheduler,
+    profile: &mut EvalProfile,
 ) -> Result<(), InterstellarEvaluatorError> {
     // CHECK: we SHOULD have one "user input" for each Circuit's input(ie == `circuit.n`)
-    assert_eq!(
-        encoded_info.x.len(),
-        circuit.get_nb_inputs(),
+    // NOTE: `encoded_info` comes from `EncodedGarblerInputs` which is received from the Garbler
+    strict_assert!(
+        encoded_info.x.len() == circuit.get_nb_inputs(),
+        InterstellarEvaluatorError::EvaluateErrorWrongInputsLength {
+            inputs_len: encoded_info.x.len(),
+            expected_len: circuit.get_nb_inputs(),
+        },
         "encoding: `encoded_info` inputs len MUST match the Circuit's inputs len!"
     );
 
@@ -181,9 +275,15 @@ fn evaluate_internal(
     // As we are looping on the gates in order, this will be built step by step
     // ie the first gates are inputs, and this will already contain them.
     // Then we built all the other gates in subsequent iterations of the loop.
-    wire_labels.resize_with(circuit.get_nb_wires(), Default::default);
+    // NOTE: sparse circuits(ie huge gate ids) are stored in a HashMap instead; cf `WireStorage`
+    wire_labels.reset(
+        circuit.get_nb_wires(),
+        encoded_info.x.len() + circuit.get_gates().len(),
+    );
     for (idx, wire_label) in encoded_info.x.iter().enumerate() {
-        wire_labels[idx] = Some(wire_label.clone());
+        if !wire_labels.set(idx, wire_label.clone()) {
+            return Err(InterstellarEvaluatorError::EvaluateErrorWireOutOfRange { idx });
+        }
     }
 
     // [constant gate special case]
@@ -192,29 +292,31 @@ fn evaluate_internal(
     let constant_block1 = BlockL::new_with([u64::MAX, u64::MAX]);
 
     let circuit_metadata = circuit.get_metadata();
+    let gates = circuit.get_gates();
+    let (order, mut fan_out) = scheduler.prepare(circuit)?;
 
     // "for each gate g ∈ [q] in a topological order do"
-    for gate in circuit.get_gates() {
+    // NOTE: not necessarily the stored order; cf `EvalSchedule`
+    for &gate_idx in order {
+        let gate = &gates[gate_idx];
         let wire_ref = WireRef { id: gate.get_id() };
 
         let l_g: BlockL = match gate.get_type() {
             // STANDARD CASE: cf `garble_internal`
             GateTypeForEval::Binary { input_a, input_b } => {
                 // "LA, LB ← active labels associated with the input wires of gate g"
-                let l_a = wire_labels[input_a.id].as_ref().ok_or_else(|| {
+                let l_a = wire_labels.get(input_a.id).ok_or_else(|| {
                     InterstellarEvaluatorError::EvaluateErrorMissingLabel { idx: input_a.id }
                 })?;
-                let l_b = wire_labels[input_b.id].as_ref().ok_or_else(|| {
+                let l_b = wire_labels.get(input_b.id).ok_or_else(|| {
                     InterstellarEvaluatorError::EvaluateErrorMissingLabel { idx: input_b.id }
                 })?;
 
                 // "extract ∇g ← F [g]"
-                let delta_g_blockl = f.f[wire_ref.id]
-                    .as_ref()
-                    .ok_or_else(|| InterstellarEvaluatorError::EvaluateErrorMissingDelta {
-                        idx: wire_ref.id,
-                    })?
-                    .get_block();
+                // NOTE: a missing ∇g is NOT a free gate(cf `FreeXor` below), but a corrupted `F`
+                let delta_g = f.f.get(gate_idx).and_then(Option::as_ref).ok_or_else(|| {
+                    InterstellarEvaluatorError::EvaluateErrorMissingDelta { idx: wire_ref.id }
+                })?;
 
                 // "compute Lg ← RO(g, LA, LB ) ◦ ∇g"
                 let r = RandomOracle::random_oracle_g_truncated(
@@ -223,13 +325,28 @@ fn evaluate_internal(
                     gate.get_id(),
                     ro_buf,
                 );
-                let l_g: BlockL = BlockL::new_projection(&r, delta_g_blockl);
-
-                l_g
+                profile.count_ro_g();
+                BlockL::new_projection(&r, delta_g.get_block())
+            }
+            // [Supporting Free-XOR] no ∇g: "Lg ← LA ⊕ LB"; cf `garble::free_xor_labels`
+            // ONLY for a circuit garbled with `garble_with_delta`
+            GateTypeForEval::FreeXor { input_a, input_b } => {
+                if !is_free_xor {
+                    return Err(InterstellarEvaluatorError::EvaluateErrorMissingDelta {
+                        idx: wire_ref.id,
+                    });
+                }
+                let l_a = wire_labels.get(input_a.id).ok_or_else(|| {
+                    InterstellarEvaluatorError::EvaluateErrorMissingLabel { idx: input_a.id }
+                })?;
+                let l_b = wire_labels.get(input_b.id).ok_or_else(|| {
+                    InterstellarEvaluatorError::EvaluateErrorMissingLabel { idx: input_b.id }
+                })?;
+                l_a.get_block().xor(l_b.get_block())
             }
             // SPECIAL CASE: cf `garble_internal`
             GateTypeForEval::Unary { input_a } => {
-                let l_a = wire_labels[input_a.id].as_ref().ok_or_else(|| {
+                let l_a = wire_labels.get(input_a.id).ok_or_else(|| {
                     InterstellarEvaluatorError::EvaluateErrorMissingLabel { idx: input_a.id }
                 })?;
                 l_a.get_block().clone()
@@ -245,14 +362,28 @@ fn evaluate_internal(
             },
         };
 
-        wire_labels[wire_ref.id] = Some(WireLabel::new(&l_g));
+        if !wire_labels.set(wire_ref.id, WireLabel::new(&l_g)) {
+            return Err(InterstellarEvaluatorError::EvaluateErrorWireOutOfRange {
+                idx: wire_ref.id,
+            });
+        }
 
         // "if g is a circuit output wire then"
         // TODO move the previous lines under the if; or better: iter only on output gates? (filter? or circuit.outputs?)
         if circuit_metadata.gate_idx_is_output(wire_ref.id) {
             // "Y [g] ← Lg"
-            output_labels.y[circuit_metadata.convert_gate_id_to_outputs_index(wire_ref.id)] =
-                Some(l_g);
+            // NOTE: the metadata comes from the Garbler; DO NOT panic if it is inconsistent
+            *output_labels
+                .y
+                .get_mut(circuit_metadata.convert_gate_id_to_outputs_index(wire_ref.id))
+                .ok_or(InterstellarEvaluatorError::EvaluateErrorWireOutOfRange {
+                    idx: wire_ref.id,
+                })? = Some(l_g);
+        }
+
+        if let Some(fan_out) = &mut fan_out {
+            fan_out.release_inputs(gate, wire_labels);
+            fan_out.drop_if_unused(wire_ref.id, wire_labels);
         }
     }
 
@@ -273,7 +404,13 @@ fn decoding_internal(
     outputs_bufs: &mut Vec<BytesMut>,
     output_labels: &OutputLabels,
     decoded_info: &DecodedInfo,
+    profile: &mut EvalProfile,
 ) -> Result<Vec<WireValue>, InterstellarEvaluatorError> {
+    check_decoded_info_len(output_labels, decoded_info)?;
+
+    // one `random_oracle_prime` per output; counted here b/c of the `par_iter_mut`
+    profile.count_ro_prime(outputs_bufs.len());
+
     // TODO(rayon) make it work in work in no_std
     // #[cfg(not(feature = "std"))]
     // for output in circuit.outputs.iter() {
@@ -314,6 +451,63 @@ fn decoding_internal(
     outputs
 }
 
+/// `d` MUST have one element per output; it is empty for circuits garbled with `garble_no_decoding`
+fn check_decoded_info_len(
+    output_labels: &OutputLabels,
+    decoded_info: &DecodedInfo,
+) -> Result<(), InterstellarEvaluatorError> {
+    if decoded_info.d.len() == output_labels.y.len() {
+        Ok(())
+    } else {
+        Err(
+            InterstellarEvaluatorError::DecodingErrorMissingDecodingInfo {
+                len: decoded_info.d.len(),
+                expected_len: output_labels.y.len(),
+            },
+        )
+    }
+}
+
+/// Same as `decoding_internal`, but decode the outputs in chunks of `chunk` outputs
+/// using a single RO buffer, and hand each decoded chunk to `sink` along with the index of its first output.
+/// This avoids the `nb_outputs` buffers(and the full `Vec` of outputs) on memory constrained targets.
+///
+/// NOTE: `chunk` == 0 is treated as 1
+fn decode_chunked(
+    output_labels: &OutputLabels,
+    decoded_info: &DecodedInfo,
+    chunk: usize,
+    ro_buf: &mut BytesMut,
+    profile: &mut EvalProfile,
+    mut sink: impl FnMut(usize, &[WireValue]),
+) -> Result<(), InterstellarEvaluatorError> {
+    check_decoded_info_len(output_labels, decoded_info)?;
+
+    // NOTE: bounded by the number of outputs: `chunk` is only used as a capacity
+    let chunk = chunk.clamp(1, output_labels.y.len().max(1));
+    let mut outputs_chunk: Vec<WireValue> = Vec::with_capacity(chunk);
+
+    for chunk_start in (0..output_labels.y.len()).step_by(chunk) {
+        let chunk_end = (chunk_start + chunk).min(output_labels.y.len());
+        outputs_chunk.clear();
+
+        for idx in chunk_start..chunk_end {
+            // "y[j] ← lsb(RO′(Y [j], dj ))"
+            let yj = output_labels.y[idx].as_ref().ok_or_else(|| {
+                InterstellarEvaluatorError::DecodingErrorMissingOutputLabel { idx }
+            })?;
+            let dj = &decoded_info.d[idx];
+            let r = RandomOracle::random_oracle_prime(yj, dj, ro_buf);
+            profile.count_ro_prime(1);
+            outputs_chunk.push(WireValue { value: r });
+        }
+
+        sink(chunk_start, &outputs_chunk);
+    }
+
+    Ok(())
+}
+
 /// Full evaluate chain
 ///
 /// NOTE: this is mostly for testing purposes
@@ -337,27 +531,33 @@ pub(crate) fn evaluate_full_chain(
         &mut encoded_info,
         0,
         garbled.circuit.get_nb_inputs(),
-    );
+    )?;
 
     let mut output_labels = OutputLabels { y: Vec::new() };
     // TODO(opt) pass from param? (NOT that critical b/c only used for tests)
     let mut ro_buf = BytesMut::new();
-    let mut wire_labels = Vec::new();
+    let mut wire_labels = WireStorage::new();
+    let mut profile = EvalProfile::default();
 
     evaluate_internal(
         &garbled.circuit,
         &garbled.garbled_circuit.f,
+        garbled.eval_metadata.is_free_xor,
         &encoded_info,
         &mut output_labels,
         &mut ro_buf,
         &mut wire_labels,
+        &mut Scheduler::new(),
+        &mut profile,
     )?;
 
     // TODO(opt) pass from param? (NOT that critical b/c only used for tests)
     let mut outputs_bufs = Vec::new();
-    outputs_bufs.resize_with(garbled.eval_metadata.nb_outputs, BytesMut::new);
+    outputs_bufs.resize_with(garbled.eval_metadata.nb_outputs, || {
+        BytesMut::with_capacity(RandomOracle::max_buf_len())
+    });
 
-    decoding_internal(&mut outputs_bufs, &output_labels, &garbled.d)
+    decoding_internal(&mut outputs_bufs, &output_labels, &garbled.d, &mut profile)
 }
 
 /// "Standard" evaluate chain
@@ -376,22 +576,149 @@ pub(crate) fn evaluate_with_encoded_info(
     evaluate_internal(
         &garbled.circuit,
         &garbled.garbled_circuit.f,
+        garbled.eval_metadata.is_free_xor,
         encoded_info,
         &mut eval_cache.output_labels,
         &mut eval_cache.ro_buf,
         &mut eval_cache.wire_labels,
+        &mut eval_cache.scheduler,
+        &mut eval_cache.profile,
     )?;
 
     // The correct size MUST be set!
     // Else we end up with the wrong number of outputs
     eval_cache
         .outputs_bufs
-        .resize_with(garbled.eval_metadata.nb_outputs, BytesMut::new);
+        .resize_with(garbled.eval_metadata.nb_outputs, || {
+            BytesMut::with_capacity(RandomOracle::max_buf_len())
+        });
 
     decoding_internal(
         &mut eval_cache.outputs_bufs,
         &eval_cache.output_labels,
         &garbled.d,
+        &mut eval_cache.profile,
+    )
+}
+
+/// Same as `evaluate_with_encoded_info` but WITHOUT decoding: return the output labels `Y`
+/// as little-endian bytes, eg to feed them to another circuit.
+/// This does NOT need `d`; cf `garble_no_decoding`
+#[allow(clippy::unnecessary_lazy_evaluations)]
+pub(crate) fn evaluate_with_encoded_info_to_labels(
+    garbled: &GarbledCircuitFinal,
+    encoded_info: &EncodedInfo,
+    eval_cache: &mut EvalCache,
+) -> Result<Vec<[u8; KAPPA_BYTES]>, InterstellarEvaluatorError> {
+    evaluate_internal(
+        &garbled.circuit,
+        &garbled.garbled_circuit.f,
+        garbled.eval_metadata.is_free_xor,
+        encoded_info,
+        &mut eval_cache.output_labels,
+        &mut eval_cache.ro_buf,
+        &mut eval_cache.wire_labels,
+        &mut eval_cache.scheduler,
+        &mut eval_cache.profile,
+    )?;
+
+    eval_cache
+        .output_labels
+        .y
+        .iter()
+        .enumerate()
+        .map(|(idx, yj)| {
+            yj.as_ref()
+                .map(BlockL::to_le_bytes)
+                .ok_or_else(|| InterstellarEvaluatorError::DecodingErrorMissingOutputLabel { idx })
+        })
+        .collect()
+}
+
+/// Decode only the outputs at `outputs_indices`, from the labels of `evaluate_with_encoded_info_to_labels`;
+/// cf `GarbledCircuit::decode_region`
+pub(crate) fn decode_labels(
+    garbled: &GarbledCircuitFinal,
+    output_labels: &[[u8; KAPPA_BYTES]],
+    outputs_indices: impl Iterator<Item = usize>,
+) -> Result<Vec<WireValue>, InterstellarEvaluatorError> {
+    let mut ro_buf = BytesMut::with_capacity(RandomOracle::max_buf_len());
+
+    outputs_indices
+        .map(|idx| {
+            let yj = output_labels.get(idx).ok_or_else(|| {
+                InterstellarEvaluatorError::DecodingErrorMissingOutputLabel { idx }
+            })?;
+            let dj = garbled.d.d.get(idx).ok_or_else(|| {
+                InterstellarEvaluatorError::DecodingErrorMissingDecodingInfo {
+                    len: garbled.d.d.len(),
+                    expected_len: output_labels.len(),
+                }
+            })?;
+            // "y[j] ← lsb(RO′(Y [j], dj ))"
+            Ok(WireValue {
+                value: RandomOracle::random_oracle_prime(
+                    &BlockL::new_from_le_bytes(yj),
+                    dj,
+                    &mut ro_buf,
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Recompute "y[j] ← lsb(RO′(Y [j], dj ))" for each output and compare with `claimed_bits`;
+/// everything as little-endian bytes, cf `verify_outputs`
+pub(crate) fn verify_decoded_outputs(
+    d: &[[u8; KAPPA_BYTES]],
+    output_labels: &[[u8; KAPPA_BYTES]],
+    claimed_bits: &[u8],
+) -> bool {
+    if output_labels.len() != d.len() || claimed_bits.len() != d.len() {
+        return false;
+    }
+    let mut ro_buf = BytesMut::with_capacity(RandomOracle::max_buf_len());
+
+    d.iter()
+        .zip(output_labels)
+        .zip(claimed_bits)
+        .all(|((dj, yj), claimed_bit)| {
+            let bit = RandomOracle::random_oracle_prime(
+                &BlockL::new_from_le_bytes(yj),
+                &BlockL::new_from_le_bytes(dj),
+                &mut ro_buf,
+            );
+            u8::from(bit) == *claimed_bit
+        })
+}
+
+/// Same as `evaluate_with_encoded_info` but the outputs are decoded in chunks; cf `decode_chunked`
+pub(crate) fn evaluate_with_encoded_info_chunked(
+    garbled: &GarbledCircuitFinal,
+    encoded_info: &EncodedInfo,
+    eval_cache: &mut EvalCache,
+    chunk: usize,
+    sink: impl FnMut(usize, &[WireValue]),
+) -> Result<(), InterstellarEvaluatorError> {
+    evaluate_internal(
+        &garbled.circuit,
+        &garbled.garbled_circuit.f,
+        garbled.eval_metadata.is_free_xor,
+        encoded_info,
+        &mut eval_cache.output_labels,
+        &mut eval_cache.ro_buf,
+        &mut eval_cache.wire_labels,
+        &mut eval_cache.scheduler,
+        &mut eval_cache.profile,
+    )?;
+
+    decode_chunked(
+        &eval_cache.output_labels,
+        &garbled.d,
+        chunk,
+        &mut eval_cache.ro_buf,
+        &mut eval_cache.profile,
+        sink,
     )
 }
 
@@ -404,7 +731,7 @@ pub(crate) fn encode_garbler_inputs(
     inputs: &[WireValue],
     inputs_start_index: usize,
     inputs_end_index: usize,
-) -> EncodedInfo {
+) -> Result<EncodedInfo, InterstellarEvaluatorError> {
     let mut encoded_info = EncodedInfo {
         x: Vec::with_capacity(garbled.circuit.get_nb_inputs()),
     };
@@ -416,22 +743,47 @@ pub(crate) fn encode_garbler_inputs(
         &mut encoded_info,
         inputs_start_index,
         inputs_end_index,
-    );
+    )?;
 
-    encoded_info
+    Ok(encoded_info)
 }
 
 /// encoded inputs
 /// "client-side" == "evaluator inputs"
 ///
 /// ie convert a "vec" of bool/u8 into a "vec" of Wire Labels
+///
+/// `encoded_info` MUST already contain exactly the garbler inputs, and the range MUST be the one
+/// of the evaluator inputs; else the labels would end up misaligned with the circuit's inputs.
 pub(crate) fn encode_evaluator_inputs(
     garbled: &GarbledCircuitFinal,
     inputs: &[WireValue],
     encoded_info: &mut EncodedInfo,
     inputs_start_index: usize,
     inputs_end_index: usize,
-) {
+) -> Result<(), InterstellarEvaluatorError> {
+    // "generic circuits": there are NO garbler inputs
+    let nb_garbler_inputs = garbled
+        .circuit
+        .get_config()
+        .as_ref()
+        .map_or(0, |config| config.num_garbler_inputs() as usize);
+    let nb_inputs = garbled.circuit.get_nb_inputs();
+    if inputs_start_index != nb_garbler_inputs
+        || encoded_info.x.len() != nb_garbler_inputs
+        || inputs_start_index > inputs_end_index
+        || inputs_end_index > nb_inputs
+    {
+        return Err(
+            InterstellarEvaluatorError::EncodingErrorWrongEvaluatorInputsRange {
+                start: inputs_start_index,
+                end: inputs_end_index,
+                nb_garbler_inputs,
+                nb_inputs,
+            },
+        );
+    }
+
     encoding_internal(
         &garbled.circuit,
         &garbled.e,
@@ -439,5 +791,5 @@ pub(crate) fn encode_evaluator_inputs(
         encoded_info,
         inputs_start_index,
         inputs_end_index,
-    );
+    )
 }
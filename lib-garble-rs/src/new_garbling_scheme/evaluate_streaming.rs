@@ -0,0 +1,301 @@
+//! EXPERIMENTAL "streaming" evaluation, for memory constrained clients.
+//!
+//! Instead of deserializing the whole `GarbledCircuitFinal` and keeping one label per wire
+//! (cf `evaluate_internal`), the circuit is serialized as:
+//! - a `StreamingHeader`: what is needed before the first gate(inputs, `e`, `d`...)
+//! - then one `StreamingGate` per Gate, in topological order: the Gate, its `∇`(ie `F[g]`)
+//!   and the wires which are NOT used anymore after this Gate.
+//!
+//! The evaluator reads the records one at a time from a `std::io::Read`(ie the blob is NEVER
+//! entirely in memory), and only keeps the labels of the "live" wires.
+//! The "dead wires" are computed garbler-side using the fan-out of each wire; cf `wires_last_use`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use circuit_types_rs::{Metadata, WireRef};
+
+use crate::InterstellarError;
+
+use super::{
+    circuit_for_eval::{GateForEval, GateTypeForEval},
+    delta::Delta,
+    garble::{DecodedInfo, GarbledCircuitFinal, InputEncodingSet},
+};
+
+#[cfg(feature = "std")]
+use {
+    super::{
+        block::BlockL, evaluate::EncodedInfo, random_oracle::RandomOracle, wire_value::WireValue,
+    },
+    crate::InterstellarEvaluatorError,
+    bytes::BytesMut,
+    serde::de::DeserializeOwned,
+};
+
+/// Everything needed before processing the first Gate
+#[derive(Serialize, Deserialize)]
+struct StreamingHeader {
+    inputs: Vec<WireRef>,
+    nb_gates: usize,
+    nb_outputs: usize,
+    metadata: Metadata,
+    e: InputEncodingSet,
+    d: DecodedInfo,
+    /// cf `EvalMetadata::is_free_xor`
+    is_free_xor: bool,
+}
+
+/// One record per Gate
+#[derive(Serialize, Deserialize)]
+struct StreamingGate {
+    gate: GateForEval,
+    /// `F[g]`; None for the "free" gates ie NOT `GateTypeForEval::Binary`
+    delta: Option<Delta>,
+    /// The labels to discard once this Gate is evaluated
+    dead_wires: Vec<usize>,
+}
+
+/// For each Gate(in order): the wires for which it is the LAST consumer.
+/// A Gate whose output is never consumed(ie an output, or a dangling wire) lists its own output
+/// so that its label is dropped right away.
+fn wires_last_use(gates: &[GateForEval]) -> Vec<Vec<usize>> {
+    let mut last_use: HashMap<usize, usize> = HashMap::new();
+    for (gate_idx, gate) in gates.iter().enumerate() {
+        match gate.get_type() {
+            GateTypeForEval::Binary { input_a, input_b }
+            | GateTypeForEval::FreeXor { input_a, input_b } => {
+                last_use.insert(input_a.id, gate_idx);
+                last_use.insert(input_b.id, gate_idx);
+            }
+            GateTypeForEval::Unary { input_a } => {
+                last_use.insert(input_a.id, gate_idx);
+            }
+            GateTypeForEval::Constant { value: _ } => {}
+        }
+    }
+
+    let mut dead_wires = vec![Vec::new(); gates.len()];
+    for (gate_idx, gate) in gates.iter().enumerate() {
+        if !last_use.contains_key(&gate.get_id()) {
+            dead_wires[gate_idx].push(gate.get_id());
+        }
+    }
+    for (wire_id, gate_idx) in last_use {
+        dead_wires[gate_idx].push(wire_id);
+    }
+    for wires in &mut dead_wires {
+        wires.sort_unstable();
+    }
+
+    dead_wires
+}
+
+/// Garbler-side: serialize `garbled` in the streaming format; cf module doc
+pub(crate) fn serialize_for_streaming(
+    garbled: &GarbledCircuitFinal,
+) -> Result<Vec<u8>, InterstellarError> {
+    let gates = garbled.circuit.get_gates();
+    let header = StreamingHeader {
+        inputs: garbled.circuit.get_inputs().to_vec(),
+        nb_gates: gates.len(),
+        nb_outputs: garbled.circuit.get_nb_outputs(),
+        metadata: garbled.circuit.get_metadata().clone(),
+        e: garbled.e.clone(),
+        d: garbled.d.clone(),
+        is_free_xor: garbled.eval_metadata.is_free_xor,
+    };
+    let mut buf = postcard::to_allocvec(&header)
+        .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })?;
+
+    for (gate_idx, (gate, dead_wires)) in gates.iter().zip(wires_last_use(gates)).enumerate() {
+        let record = StreamingGate {
+            gate: gate.clone(),
+            delta: garbled.garbled_circuit.f.f.get(gate_idx).cloned().flatten(),
+            dead_wires,
+        };
+        buf = postcard::to_extend(&record, buf)
+            .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })?;
+    }
+
+    Ok(buf)
+}
+
+/// Scratch buffer of `postcard::from_io`; it is ONLY used for the borrowed fields(`&str` etc)
+/// and the records are fully owned, so this is just a safety margin.
+#[cfg(feature = "std")]
+const IO_SCRATCH_LEN: usize = 64;
+
+/// Read the next record from `reader`
+#[cfg(feature = "std")]
+fn take<T: DeserializeOwned, R: std::io::Read>(
+    reader: &mut R,
+    scratch: &mut [u8],
+) -> Result<T, InterstellarError> {
+    postcard::from_io((reader, scratch))
+        .map(|(record, _)| record)
+        .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })
+}
+
+/// Evaluator-side: encode the evaluator inputs, evaluate and decode in a single pass over `reader`
+///
+/// `reader`: the records are read one byte at a time so it SHOULD be buffered(eg `std::io::BufReader`)
+/// `encoded_garbler_inputs`: MUST be the full encoding; cf `EncodedGarblerInputs`
+#[cfg(feature = "std")]
+#[allow(clippy::unnecessary_lazy_evaluations)]
+pub(crate) fn eval_streaming<R: std::io::Read>(
+    mut reader: R,
+    encoded_garbler_inputs: &EncodedInfo,
+    evaluator_inputs: &[WireValue],
+) -> Result<Vec<WireValue>, InterstellarEvaluatorError> {
+    let mut scratch = [0u8; IO_SCRATCH_LEN];
+    let header: StreamingHeader = take(&mut reader, &mut scratch)?;
+
+    let nb_inputs = encoded_garbler_inputs.len() + evaluator_inputs.len();
+    if nb_inputs != header.inputs.len() {
+        return Err(InterstellarEvaluatorError::EvaluateErrorWrongInputsLength {
+            inputs_len: nb_inputs,
+            expected_len: header.inputs.len(),
+        });
+    }
+
+    // "live" labels only; cf `StreamingGate::dead_wires`
+    let mut wire_labels: HashMap<usize, BlockL> = HashMap::new();
+    for (input_wire, label) in header.inputs.iter().zip(encoded_garbler_inputs.labels()) {
+        wire_labels.insert(input_wire.id, label.get_block().clone());
+    }
+    // same as `encoding_internal`, for the evaluator inputs
+    for (input_idx, input_value) in (encoded_garbler_inputs.len()..).zip(evaluator_inputs) {
+        let input_wire = &header.inputs[input_idx];
+        let encoded_wire = header.e.e.get(input_idx).ok_or_else(|| {
+            InterstellarEvaluatorError::EvaluateErrorWireOutOfRange { idx: input_idx }
+        })?;
+        let block = if input_value.value {
+            encoded_wire.value1()
+        } else {
+            encoded_wire.value0()
+        };
+        wire_labels.insert(input_wire.id, block.clone());
+    }
+
+    // [constant gate special case] cf `evaluate_internal`
+    let constant_block0 = BlockL::new_with([0, 0]);
+    let constant_block1 = BlockL::new_with([u64::MAX, u64::MAX]);
+
+    let mut output_labels: Vec<Option<BlockL>> = vec![None; header.nb_outputs];
+    let mut ro_buf = BytesMut::with_capacity(RandomOracle::max_buf_len());
+
+    for _ in 0..header.nb_gates {
+        let record: StreamingGate = take(&mut reader, &mut scratch)?;
+        let gate_id = record.gate.get_id();
+        let get_label = |wire: &WireRef| {
+            wire_labels.get(&wire.id).ok_or_else(|| {
+                InterstellarEvaluatorError::EvaluateErrorMissingLabel { idx: wire.id }
+            })
+        };
+
+        let l_g = match record.gate.get_type() {
+            GateTypeForEval::Binary { input_a, input_b } => {
+                let delta = record.delta.as_ref().ok_or_else(|| {
+                    InterstellarEvaluatorError::EvaluateErrorMissingDelta { idx: gate_id }
+                })?;
+                let r = RandomOracle::random_oracle_g_truncated(
+                    get_label(input_a)?,
+                    Some(get_label(input_b)?),
+                    gate_id,
+                    &mut ro_buf,
+                );
+                BlockL::new_projection(&r, delta.get_block())
+            }
+            // [Supporting Free-XOR] cf `evaluate_internal`
+            GateTypeForEval::FreeXor { input_a, input_b } => {
+                if !header.is_free_xor {
+                    return Err(InterstellarEvaluatorError::EvaluateErrorMissingDelta {
+                        idx: gate_id,
+                    });
+                }
+                get_label(input_a)?.xor(get_label(input_b)?)
+            }
+            GateTypeForEval::Unary { input_a } => get_label(input_a)?.clone(),
+            GateTypeForEval::Constant { value } => match value {
+                false => constant_block0.clone(),
+                true => constant_block1.clone(),
+            },
+        };
+
+        if header.metadata.gate_idx_is_output(gate_id) {
+            let output_idx = header.metadata.convert_gate_id_to_outputs_index(gate_id);
+            *output_labels.get_mut(output_idx).ok_or_else(|| {
+                InterstellarEvaluatorError::EvaluateErrorWireOutOfRange { idx: gate_id }
+            })? = Some(l_g.clone());
+        }
+        wire_labels.insert(gate_id, l_g);
+        for wire_id in &record.dead_wires {
+            wire_labels.remove(wire_id);
+        }
+    }
+
+    // cf `decoding_internal`
+    if header.d.d.len() != output_labels.len() {
+        return Err(
+            InterstellarEvaluatorError::DecodingErrorMissingDecodingInfo {
+                len: header.d.d.len(),
+                expected_len: output_labels.len(),
+            },
+        );
+    }
+    output_labels
+        .iter()
+        .zip(&header.d.d)
+        .enumerate()
+        .map(|(idx, (yj, dj))| {
+            let yj = yj.as_ref().ok_or_else(|| {
+                InterstellarEvaluatorError::DecodingErrorMissingOutputLabel { idx }
+            })?;
+            Ok(WireValue {
+                value: RandomOracle::random_oracle_prime(yj, dj, &mut ro_buf),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_garbling_scheme::circuit_for_eval::CircuitForEval;
+
+    #[test]
+    fn test_wires_last_use_full_adder() {
+        let circuit = crate::parse_skcd(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let circuit = CircuitForEval::from(&circuit);
+
+        let dead_wires = wires_last_use(circuit.get_gates());
+
+        // replay the eval: every consumed wire MUST still be live, and every wire is dropped exactly once
+        let mut live: Vec<usize> = circuit.get_inputs().iter().map(|input| input.id).collect();
+        for (gate, dead) in circuit.get_gates().iter().zip(&dead_wires) {
+            match gate.get_type() {
+                GateTypeForEval::Binary { input_a, input_b }
+                | GateTypeForEval::FreeXor { input_a, input_b } => {
+                    assert!(live.contains(&input_a.id));
+                    assert!(live.contains(&input_b.id));
+                }
+                GateTypeForEval::Unary { input_a } => assert!(live.contains(&input_a.id)),
+                GateTypeForEval::Constant { value: _ } => {}
+            }
+            live.push(gate.get_id());
+            for wire_id in dead {
+                let pos = live.iter().position(|id| id == wire_id).unwrap();
+                live.swap_remove(pos);
+            }
+        }
+
+        // only the inputs never used by any gate can remain
+        assert!(live.iter().all(|id| *id < circuit.get_nb_inputs()));
+    }
+}
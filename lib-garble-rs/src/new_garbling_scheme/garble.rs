@@ -1,16 +1,27 @@
 use alloc::vec::Vec;
 use bytes::BytesMut;
 use hashbrown::{HashMap, HashSet};
-use rand::SeedableRng;
+use rand::{RngCore, SeedableRng};
 use rand_chacha::ChaChaRng;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use snafu::prelude::*;
 
-use circuit_types_rs::{Circuit, Gate, GateType, KindUnary, WireRef};
+use circuit_types_rs::{Circuit, Gate, GateType, KindBinary, KindUnary, WireRef};
+
+use crate::InterstellarError;
 
 use super::{
-    block::BlockL, circuit_for_eval::CircuitForEval, delta, random_oracle::RandomOracle,
-    wire::Wire, wire_labels_set::WireLabelsSet,
+    block::{BlockL, KAPPA_BYTES},
+    circuit_for_eval::nb_wire_ids,
+    circuit_for_eval::CircuitForEval,
+    circuit_for_eval::GateTypeForEval,
+    delta,
+    delta::FreeXorWires,
+    random_oracle::RandomOracle,
+    wire::Wire,
+    wire_labels_set::WireLabelsSet,
+    wire_storage::WireStorage,
 };
 
 #[derive(Debug, Snafu)]
@@ -22,8 +33,11 @@ pub(crate) enum GarblerError {
     EvaluateDuplicatedWire,
     /// "Algorithm 5 Gate" L15/16
     /// "15: if HW (∇g )̸ = ℓ then 16: ABORT the computation"
+    /// NOTE: `gate_id`/`gate_type` are there to localize the failing Gate in a big circuit
     BadHammingWeight {
         hw: usize,
+        gate_id: usize,
+        gate_type: KindBinary,
     },
     /// error during `garble_internal`: the wire is NOT present in the "current wires set"
     GarbleMissingWire {
@@ -42,6 +56,31 @@ pub(crate) enum GarblerError {
     BlockPBitOutOfRange {
         index: usize,
     },
+    /// `init_internal`: the inputs MUST be the first wires
+    /// cf `circuit_validation::validate_circuit`
+    InputWireNotLeading {
+        wire: WireRef,
+    },
+    /// `garble_with_delta`: the given Free-XOR "delta" is all zeros
+    /// That would mean `LW0 == LW1` for every input wire!
+    FreeXorDeltaIsZero,
+    /// `Delta::new`: the projections gave `L0 == L1`
+    /// Only returned with the `strict_errors` feature, else it panics
+    DeltaSameLabels,
+    /// `garble_with_labels`: there MUST be exactly one pair of labels per input
+    InputLabelsWrongLength {
+        len: usize,
+        expected_len: usize,
+    },
+    /// `garble_with_labels`: the given `value0` and `value1` of an input wire are the same
+    InputLabelsSame {
+        wire_idx: usize,
+    },
+    /// `garble_resumable`: `from_gate` MUST be where the previous call stopped(or 0 to restart)
+    ResumeWrongGate {
+        from_gate: usize,
+        expected_gate: usize,
+    },
 }
 
 /// In <https://eprint.iacr.org/2021/739.pdf>
@@ -65,7 +104,7 @@ pub(crate) enum GarblerError {
 /// that it behaves independently for each gate."
 ///
 fn f1_0_compress(
-    encoded_wires: &[Option<Wire>],
+    encoded_wires: &WireStorage<Wire>,
     gate: &Gate,
     input_a: &WireRef,
     input_b: &WireRef,
@@ -74,24 +113,27 @@ fn f1_0_compress(
     let tweak = gate.get_id();
 
     let wire_a: &Wire =
-        encoded_wires[input_a.id]
-            .as_ref()
+        encoded_wires
+            .get(input_a.id)
             .ok_or_else(|| GarblerError::GarbleMissingWire {
                 wire: input_a.clone(),
             })?;
     let wire_b: &Wire =
-        encoded_wires[input_b.id]
-            .as_ref()
+        encoded_wires
+            .get(input_b.id)
             .ok_or_else(|| GarblerError::GarbleMissingWire {
                 wire: input_b.clone(),
             })?;
 
-    Ok(WireLabelsSet::new_binary(
-        RandomOracle::random_oracle_g(wire_a.value0(), Some(wire_b.value0()), tweak, buf),
-        RandomOracle::random_oracle_g(wire_a.value0(), Some(wire_b.value1()), tweak, buf),
-        RandomOracle::random_oracle_g(wire_a.value1(), Some(wire_b.value0()), tweak, buf),
-        RandomOracle::random_oracle_g(wire_a.value1(), Some(wire_b.value1()), tweak, buf),
-    ))
+    // the 4 RO calls share their setup(tweak bytes, labels bytes) and `buf`
+    let [l00, l01, l10, l11] = RandomOracle::random_oracle_g_binary_all(
+        (wire_a.value0(), wire_a.value1()),
+        (wire_b.value0(), wire_b.value1()),
+        tweak,
+        buf,
+    );
+
+    Ok(WireLabelsSet::new_binary(l00, l01, l10, l11))
 }
 
 /// "input encoding set e."
@@ -106,12 +148,41 @@ fn f1_0_compress(
 /// Produced by: `garble::init_internal`
 /// Used by: `garble::garble_internal`, `evaluate::encoding_internal`
 ///
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[cfg_attr(feature = "debug_secrets", derive(Debug))]
 pub(super) struct InputEncodingSet {
     /// One per input
     pub(super) e: Vec<Wire>,
 }
 
+/// The labels are secret: `{:?}` ONLY prints how many there are.
+/// NOTE: not even a hash of them: a fast non-cryptographic hash of secret labels is a leak too.
+/// cf feature "`debug_secrets`" for the full labels
+#[cfg(not(feature = "debug_secrets"))]
+impl core::fmt::Debug for InputEncodingSet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("InputEncodingSet")
+            .field("len", &self.e.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// With feature "zeroize": the input labels are secret, so clear them on drop.
+/// This matters mostly for the SGX target, where the garbling happens in the enclave heap.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for InputEncodingSet {
+    fn zeroize(&mut self) {
+        self.e.iter_mut().for_each(zeroize::Zeroize::zeroize);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for InputEncodingSet {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
 /// Initialize the `W` which is the set of wires:
 /// TODO? Does two things:
 /// - allocate the full `W` set with the correct number of wires
@@ -137,18 +208,31 @@ pub(super) struct InputEncodingSet {
 /// 7: Return e
 ///
 /// param `r`: [Supporting Free-XOR] this is the "delta" for Free-XOR; ie a random `BlockL`
+/// param `is_free_xor_inputs`: when set, the input labels are generated with `LW1 = LW0 ⊕ r`
+///   instead of being sampled independently; cf `insert_new_wire_free_xor_labels`
 ///
-fn init_internal(circuit: &Circuit, rng: &mut ChaChaRng, r: &BlockL) -> InputEncodingSet {
+fn init_internal(
+    circuit: &Circuit,
+    rng: &mut dyn RngCore,
+    r: &BlockL,
+    is_free_xor_inputs: bool,
+) -> Result<InputEncodingSet, GarblerError> {
     let nb_inputs = circuit.get_nb_inputs();
     let mut w = Vec::with_capacity(nb_inputs);
-    for (idx, input_wire) in circuit.get_wires()[0..nb_inputs].iter().enumerate() {
-        // CHECK: the Wires MUST be iterated in topological order!
-        assert_eq!(
-            input_wire.id, idx,
-            "Wires MUST be iterated in topological order!"
-        );
+    for input_wire in circuit.get_inputs() {
+        // CHECK: the inputs MUST be the first wires!
+        // NOTE: `e` is indexed by wire id, cf `encoding_internal`
+        if input_wire.id >= nb_inputs {
+            return Err(GarblerError::InputWireNotLeading {
+                wire: input_wire.clone(),
+            });
+        }
 
-        insert_new_wire_random_labels(rng, &mut w, r);
+        if is_free_xor_inputs {
+            insert_new_wire_free_xor_labels(rng, &mut w, r);
+        } else {
+            insert_new_wire_random_labels(rng, &mut w, r);
+        }
     }
 
     // w.extend((0..circuit.q()).iter(). )
@@ -157,7 +241,7 @@ fn init_internal(circuit: &Circuit, rng: &mut ChaChaRng, r: &BlockL) -> InputEnc
 
     // w
 
-    InputEncodingSet { e: w }
+    Ok(InputEncodingSet { e: w })
 }
 
 /// Generate a new RANDOM wire
@@ -168,7 +252,7 @@ fn init_internal(circuit: &Circuit, rng: &mut ChaChaRng, r: &BlockL) -> InputEnc
 ///   5 Supporting Free-XOR; <https://eprint.iacr.org/2021/739.pdf>
 ///
 /// param: r: [Supporting Free-XOR] "delta"
-fn insert_new_wire_random_labels(rng: &mut ChaChaRng, wires: &mut Vec<Wire>, _r: &BlockL) {
+fn insert_new_wire_random_labels(rng: &mut dyn RngCore, wires: &mut Vec<Wire>, _r: &BlockL) {
     let lw0 = RandomOracle::new_random_block_l(rng);
     let lw1 = RandomOracle::new_random_block_l(rng);
 
@@ -180,8 +264,84 @@ fn insert_new_wire_random_labels(rng: &mut ChaChaRng, wires: &mut Vec<Wire>, _r:
     wires.push(Wire::new(lw0, lw1));
 }
 
+/// Generate a new wire respecting the Free-XOR invariant
+/// - l0 is random
+/// - l1 = l0 ⊕ `r`
+///
+/// "invariant that for the output wire of the XOR gate, L0 ⊕ L1 = ∆"
+/// 5 Supporting Free-XOR; <https://eprint.iacr.org/2021/739.pdf>
+///
+/// param: r: [Supporting Free-XOR] "delta"; MUST NOT be zero
+fn insert_new_wire_free_xor_labels(rng: &mut dyn RngCore, wires: &mut Vec<Wire>, r: &BlockL) {
+    let lw0 = RandomOracle::new_random_block_l(rng);
+    let lw1 = lw0.xor(r);
+
+    wires.push(Wire::new(lw0, lw1));
+}
+
 /// Garble
 ///
+/// Reusable buffers for garbling; cf `garble_reuse`
+/// A server garbling a lot of circuits SHOULD keep one around to avoid re-allocating them each time.
+///
+/// NOTE: `F` and `D` are NOT part of it: they are moved into the resulting `GarbledCircuitFinal`.
+pub struct GarbleScratch {
+    /// cf `garble_internal`; one per wire
+    encoded_wires: WireStorage<Wire>,
+    /// cf `garble_internal` and `decoding_info`
+    buf: BytesMut,
+    /// cf `GarbleScratch::timings`
+    #[cfg(feature = "timing")]
+    timings: GarbleTimings,
+}
+
+impl GarbleScratch {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            encoded_wires: WireStorage::new(),
+            buf: BytesMut::with_capacity(RandomOracle::max_buf_len()),
+            #[cfg(feature = "timing")]
+            timings: GarbleTimings::default(),
+        }
+    }
+
+    /// The duration of each stage of the LAST garbling done with this `GarbleScratch`
+    #[cfg(feature = "timing")]
+    #[must_use]
+    pub fn timings(&self) -> &GarbleTimings {
+        &self.timings
+    }
+}
+
+/// Duration of each stage of `garble`; cf `GarbleScratch::timings`
+/// eg to know whether the gates loop or the rejection sampling of `decoding_info` dominates.
+#[cfg(feature = "timing")]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct GarbleTimings {
+    /// `init_internal` ie sampling the input labels
+    pub init: std::time::Duration,
+    /// `garble_internal` ie the gates loop
+    pub gates: std::time::Duration,
+    /// `decoding_info`; zero with `garble_no_decoding`
+    pub decoding: std::time::Duration,
+}
+
+impl Default for GarbleScratch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// cf `impl Drop for InputEncodingSet`: `encoded_wires` holds the label pairs of EVERY wire
+/// of the last garbled circuit, until the next garbling resets it.
+#[cfg(feature = "zeroize")]
+impl Drop for GarbleScratch {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.encoded_wires);
+    }
+}
+
 /// In <https://eprint.iacr.org/2021/739.pdf>
 /// Algorithm 4 Circuit(e, C, ℓ, ℓ′)
 ///
@@ -193,32 +353,101 @@ fn insert_new_wire_random_labels(rng: &mut ChaChaRng, wires: &mut Vec<Wire>, _r:
 /// (2) Circuit(C, e) = (F, D);
 /// (3) DecodingInfo(D) → d
 ///
+/// param `free_xor_delta`: cf `GarbleGatesState::free_xor`
 fn garble_internal(
     circuit: &Circuit,
     e: &InputEncodingSet,
+    free_xor_delta: Option<BlockL>,
+    scratch: &mut GarbleScratch,
 ) -> Result<GarbledCircuitInternal, GarblerError> {
+    let mut state = garble_gates_init(circuit, e, free_xor_delta, &mut scratch.encoded_wires)?;
+    let outputs_set: HashSet<&WireRef> = circuit.get_outputs().iter().collect();
+
+    garble_gates(
+        circuit.get_gates(),
+        0,
+        &outputs_set,
+        &mut state,
+        &mut scratch.encoded_wires,
+        &mut scratch.buf,
+    )?;
+
+    // assert_eq!(encoded_wires, deltas);
+    Ok(GarbledCircuitInternal {
+        f: F { f: state.f },
+        d: D { d: state.deltas },
+    })
+}
+
+/// What `garble_gates` builds gate by gate ie `F` and `D`, before they are wrapped;
+/// cf `garble_internal` and `garble_resumable`
+struct GarbleGatesState {
+    f: Vec<Option<delta::Delta>>,
+    /// also noted as: ∇g
+    deltas: HashMap<WireRef, (BlockL, BlockL)>,
+    /// [Supporting Free-XOR] when set(cf `garble_with_delta`): the delta `r`, and the wires which
+    /// respect `LW0 ⊕ LW1 = r`. A XOR/XNOR gate whose two inputs are such wires gets NO `Delta` in `F`:
+    /// its labels are the XOR of the input labels; cf `free_xor_labels`
+    free_xor: Option<(BlockL, FreeXorWires)>,
+}
+
+/// Beginning of `garble_internal`: allocate `F`/`D`, and set the input wires in `encoded_wires`
+fn garble_gates_init(
+    circuit: &Circuit,
+    e: &InputEncodingSet,
+    free_xor_delta: Option<BlockL>,
+    encoded_wires: &mut WireStorage<Wire>,
+) -> Result<GarbleGatesState, GarblerError> {
     // "6: initialize F = [], D = []"
+    // NOTE: indexed by the position of the Gate, NOT by its id; cf `F`
     let mut f = Vec::new();
-    // "+ 1" b/c get_max_gate_id is a valid ID to be processed!
-    f.resize_with(
-        circuit.get_metadata().get_max_gate_id() + 1,
-        Default::default,
-    );
+    f.resize_with(circuit.get_gates().len(), Default::default);
     // also noted as: ∇g
     // TODO should this (semantically) be instead `HashMap<&WireRef, Wire>`(or `HashMap<&WireRef, &Wire>`)
-    let mut deltas = HashMap::with_capacity(circuit.get_nb_outputs());
+    let deltas = HashMap::with_capacity(circuit.get_nb_outputs());
 
     // As we are looping on the gates in order, this will be built step by step
     // ie the first gates are inputs, and this will already contain them.
     // Then we built all the other gates in subsequent iterations of the loop.
-    let mut encoded_wires: Vec<Option<Wire>> = Vec::new();
-    encoded_wires.resize_with(circuit.get_nb_wires(), Default::default);
+    // NOTE: sparse circuits(ie huge gate ids) are stored in a HashMap instead; cf `WireStorage`
+    // The labels of the previous circuit are cleared first; `reset` would only drop them.
+    #[cfg(feature = "zeroize")]
+    zeroize::Zeroize::zeroize(encoded_wires);
+    encoded_wires.reset(nb_wire_ids(circuit), e.e.len() + circuit.get_gates().len());
     for (idx, input_wire) in e.e.iter().enumerate() {
-        encoded_wires[idx] = Some(input_wire.clone());
+        if !encoded_wires.set(idx, input_wire.clone()) {
+            return Err(GarblerError::GarbleMissingWire {
+                wire: WireRef { id: idx },
+            });
+        }
     }
 
+    Ok(GarbleGatesState {
+        f,
+        deltas,
+        free_xor: free_xor_delta.map(|r| (r, FreeXorWires::new(circuit.get_inputs()))),
+    })
+}
+
+/// The gates loop of `garble_internal`; `gates` MUST be in topological order, and MAY be only
+/// a part of the circuit's gates(cf `garble_resumable`) as long as they are given in order.
+/// `first_gate_idx`: the position of `gates[0]` in the circuit's gates; cf `F`
+fn garble_gates(
+    gates: &[Gate],
+    first_gate_idx: usize,
+    outputs_set: &HashSet<&WireRef>,
+    state: &mut GarbleGatesState,
+    encoded_wires: &mut WireStorage<Wire>,
+    buf: &mut BytesMut,
+) -> Result<(), GarblerError> {
     // [constant gate special case]
     // We need a placeholder Wire for simplicity; these are NOT used during `evaluate_internal` etc
+    // NOTE: Constant gates are NOT rewritten as eg XOR(A,A) so they never add anything to `e`
+    // nor to `F`; however many of them the circuit contains, they all share those two blocks.
+    // These are public on purpose: the value of a constant leaks anyway. And they DO NOT bias the
+    // labels of a downstream Binary gate: they are never XORed into a label, only hashed by
+    // `f1_0_compress` together with the(random) other input and the gate id.
+    // cf `test_constant_feeding_binary_gate_labels_are_unbiased`
     let constant_block0 = BlockL::new_with([0, 0]);
     let constant_block1 = BlockL::new_with([u64::MAX, u64::MAX]);
 
@@ -227,10 +456,13 @@ fn garble_internal(
     // let mut all_wires_sorted = all_wires.clone();
     // all_wires_sorted.sort();
 
-    let outputs_set: HashSet<&WireRef> = circuit.get_outputs().iter().collect();
-    let mut buf = BytesMut::new();
+    for (gate_idx, gate) in (first_gate_idx..).zip(gates) {
+        // [Supporting Free-XOR] NOTE: `insert_gate` MUST see every gate, in order
+        let free_xor_delta = match &mut state.free_xor {
+            Some((r, free_xor_wires)) if free_xor_wires.insert_gate(gate) => Some(&*r),
+            _ => None,
+        };
 
-    for gate in circuit.get_gates() {
         let (l0, l1): (BlockL, BlockL) = match gate.get_type() {
             // STANDARD CASE: Binary Gates or using Delta etc
             GateType::Binary {
@@ -238,15 +470,24 @@ fn garble_internal(
                 input_a,
                 input_b,
             } => {
-                let compressed_set =
-                    f1_0_compress(&encoded_wires, gate, input_a, input_b, &mut buf)?;
-                let (l0, l1, delta) = delta::Delta::new(&compressed_set, gate_type)?;
-                f[gate.get_id()] = Some(delta);
-                (l0.into(), l1.into())
+                // [Supporting Free-XOR] "L_out = L_a ⊕ L_b"; cf `free_xor_labels`
+                if let Some(r) = free_xor_delta {
+                    free_xor_labels(encoded_wires, gate_type, input_a, input_b, r)?
+                } else {
+                    let compressed_set = f1_0_compress(encoded_wires, gate, input_a, input_b, buf)?;
+                    let (l0, l1, delta) =
+                        delta::Delta::new(&compressed_set, gate_type, gate.get_id())?;
+                    *state.f.get_mut(gate_idx).ok_or_else(|| {
+                        GarblerError::GarbleMissingWire {
+                            wire: gate.get_output().clone(),
+                        }
+                    })? = Some(delta);
+                    (l0.into(), l1.into())
+                }
             }
             // SPECIAL CASE: Unary Gates are bypassing Delta (and therefore DO NOT need a RO call during eval)
             GateType::Unary { gate_type, input_a } => {
-                let wire_a: &Wire = encoded_wires[input_a.id].as_ref().ok_or_else(|| {
+                let wire_a: &Wire = encoded_wires.get(input_a.id).ok_or_else(|| {
                     GarblerError::GarbleMissingWire {
                         wire: input_a.clone(),
                     }
@@ -270,12 +511,17 @@ fn garble_internal(
         // w is init with [0,n], and as size [0,n+q]
         // what about Gate's index? (== output)
         let new_wires = Wire::new(l0, l1);
-        encoded_wires[gate.get_id()] = Some(new_wires.clone());
+        if !encoded_wires.set(gate.get_id(), new_wires.clone()) {
+            return Err(GarblerError::GarbleMissingWire {
+                wire: gate.get_output().clone(),
+            });
+        }
 
         // "12: if g is an output gate then"
         // TODO(opt) if circuit_metadata.gate_idx_is_output(wire_ref.id) { (cf `evaluate_internal`)
         if let Some(wire_output) = outputs_set.get(gate.get_output()) {
-            deltas
+            state
+                .deltas
                 .try_insert(
                     (*wire_output).clone().clone(),
                     (new_wires.value0().clone(), new_wires.value1().clone()),
@@ -286,10 +532,38 @@ fn garble_internal(
         }
     }
 
-    // assert_eq!(encoded_wires, deltas);
-    Ok(GarbledCircuitInternal {
-        f: F { f },
-        d: D { d: deltas },
+    Ok(())
+}
+
+/// [Supporting Free-XOR] The labels of a XOR/XNOR gate garbled for free, ie WITHOUT `Delta`:
+/// - XOR: `L0 = LA0 ⊕ LB0` and `L1 = L0 ⊕ r`
+/// - XNOR: the same, with `L0`/`L1` swapped(cf free-NOT)
+///
+/// The evaluator computes `LA ⊕ LB` which is `L0` when both inputs have the same value, `L1` otherwise.
+/// This ONLY holds if both inputs respect `LW0 ⊕ LW1 = r`, which is the case for the input wires
+/// with `garble_with_delta`, and is kept by the free gates(INV/BUF/XOR/XNOR); but NOT for the
+/// output of a gate garbled with `Delta`. The caller MUST have checked it; cf `FreeXorWires`
+fn free_xor_labels(
+    encoded_wires: &WireStorage<Wire>,
+    gate_type: &KindBinary,
+    input_a: &WireRef,
+    input_b: &WireRef,
+    r: &BlockL,
+) -> Result<(BlockL, BlockL), GarblerError> {
+    let get_wire = |wire: &WireRef| {
+        encoded_wires
+            .get(wire.id)
+            .ok_or_else(|| GarblerError::GarbleMissingWire { wire: wire.clone() })
+    };
+    let wire_a = get_wire(input_a)?;
+    let wire_b = get_wire(input_b)?;
+
+    let l_same = wire_a.value0().xor(wire_b.value0());
+    let l_different = l_same.xor(r);
+
+    Ok(match gate_type {
+        KindBinary::XNOR => (l_different, l_same),
+        _ => (l_same, l_different),
     })
 }
 
@@ -298,15 +572,46 @@ fn garble_internal(
 pub(super) struct F {
     /// One per Gate, or rather per [free-XOR] non-free Gate
     /// But for ease of implementation we use Option<> and f.len() == "nb of gates"
+    /// NOTE: indexed by the position of the Gate in `get_gates()`, NOT by its id;
+    /// that way it does not depend on the ids, which can be sparse(eg a Gate with id 1_000_000).
     pub(super) f: Vec<Option<delta::Delta>>,
 }
 
 /// Noted `D` in the paper
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[cfg_attr(feature = "debug_secrets", derive(Debug))]
 struct D {
     d: HashMap<WireRef, (BlockL, BlockL)>,
 }
 
+/// cf `impl Debug for InputEncodingSet`
+#[cfg(not(feature = "debug_secrets"))]
+impl core::fmt::Debug for D {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("D")
+            .field("len", &self.d.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// cf `InputEncodingSet`
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for D {
+    fn zeroize(&mut self) {
+        for (l0, l1) in self.d.values_mut() {
+            l0.zeroize();
+            l1.zeroize();
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for D {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub(super) struct GarbledCircuitInternal {
     pub(super) f: F,
@@ -323,10 +628,152 @@ pub(crate) struct GarbledCircuitFinal {
     pub(crate) eval_metadata: EvalMetadata,
 }
 
+impl GarbledCircuitFinal {
+    #[must_use]
+    pub(crate) fn nb_input_labels(&self) -> usize {
+        self.e.e.len()
+    }
+
+    /// SHA256 of the postcard serialization of everything the evaluator receives:
+    /// the circuit, `F`, `d`, `e` and the eval metadata.
+    /// NOTE: `D`(garbler-side only) is skipped: it is a `HashMap` so its serialization order
+    /// is NOT something to commit to.
+    pub(crate) fn fingerprint(&self) -> Result<[u8; 32], postcard::Error> {
+        let buf = postcard::to_allocvec(&(
+            &self.circuit,
+            &self.garbled_circuit.f,
+            &self.d,
+            &self.e,
+            &self.eval_metadata,
+        ))?;
+
+        Ok(Sha256::digest(buf).into())
+    }
+
+    /// Check that the (deserialized) garbled circuit is consistent, ie that `eval` will NOT panic
+    /// on an out-of-bounds access: the sizes of `F`, `d`, `e` vs the embedded circuit,
+    /// and the wires referenced by the gates.
+    pub(crate) fn self_check(&self) -> Result<(), InterstellarError> {
+        let check = |field: &'static str, len: usize, expected_len: usize| {
+            if len == expected_len {
+                Ok(())
+            } else {
+                Err(InterstellarError::GarbledCircuitInconsistent {
+                    field,
+                    len,
+                    expected_len,
+                })
+            }
+        };
+
+        // "+ 1" b/c get_max_gate_id is a valid ID
+        let nb_gates_ids = self.circuit.get_metadata().get_max_gate_id() + 1;
+        check(
+            "F",
+            self.garbled_circuit.f.f.len(),
+            self.circuit.get_gates().len(),
+        )?;
+        // an empty `d` is valid; cf `garble_no_decoding`
+        if !self.d.d.is_empty() {
+            check("d", self.d.d.len(), self.eval_metadata.nb_outputs)?;
+        }
+        check(
+            "nb_outputs",
+            self.eval_metadata.nb_outputs,
+            self.circuit.get_nb_outputs(),
+        )?;
+        check("e", self.e.e.len(), self.circuit.get_nb_inputs())?;
+
+        let nb_wires = self.circuit.get_nb_wires();
+        for gate in self.circuit.get_gates() {
+            let max_wire_id = match gate.get_type() {
+                GateTypeForEval::Binary { input_a, input_b }
+                | GateTypeForEval::FreeXor { input_a, input_b } => {
+                    gate.get_id().max(input_a.id).max(input_b.id)
+                }
+                GateTypeForEval::Unary { input_a } => gate.get_id().max(input_a.id),
+                GateTypeForEval::Constant { .. } => gate.get_id(),
+            };
+            if max_wire_id >= nb_wires || gate.get_id() >= nb_gates_ids {
+                return Err(InterstellarError::GarbledCircuitInconsistent {
+                    field: "gates",
+                    len: max_wire_id,
+                    expected_len: nb_wires,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// cf `GarbledCircuit::f_byte_size`
+    #[must_use]
+    pub(crate) fn f_byte_size(&self) -> usize {
+        let nb_slots = self.garbled_circuit.f.f.len();
+
+        // postcard: the `Vec` length(varint), then one `Option` tag per slot,
+        // and a `Delta` for each materialized gate
+        varint_len(nb_slots)
+            + nb_slots
+            + self.nb_materialized_gates() * delta::Delta::max_serialized_size()
+    }
+
+    /// cf `GarbledCircuit::is_gate_free`
+    /// NOTE: from the kind of the gate(and the garbling mode), NOT from `F`; cf `evaluate_internal`
+    #[must_use]
+    pub(crate) fn is_gate_free(&self, gate_id: usize) -> Option<bool> {
+        let gate = self
+            .circuit
+            .get_gates()
+            .iter()
+            .find(|gate| gate.get_id() == gate_id)?;
+
+        Some(match gate.get_type() {
+            GateTypeForEval::Binary { .. } => false,
+            GateTypeForEval::FreeXor { .. } => self.eval_metadata.is_free_xor,
+            GateTypeForEval::Unary { .. } | GateTypeForEval::Constant { .. } => true,
+        })
+    }
+
+    /// Number of gates which have a garbled representation(ie a `Delta` in `F`);
+    /// the others(unary, constant, and XOR/XNOR with `garble_with_delta`) are "free" at eval time.
+    #[must_use]
+    pub(crate) fn nb_materialized_gates(&self) -> usize {
+        self.garbled_circuit
+            .f
+            .f
+            .iter()
+            .filter(|delta| delta.is_some())
+            .count()
+    }
+
+    /// Return the pair of labels (`value0`, `value1`) of the input wire `wire_idx`
+    /// as canonical little-endian bytes; cf `BlockL::to_le_bytes`
+    #[must_use]
+    pub(crate) fn input_labels_le_bytes(
+        &self,
+        wire_idx: usize,
+    ) -> Option<([u8; KAPPA_BYTES], [u8; KAPPA_BYTES])> {
+        self.e
+            .e
+            .get(wire_idx)
+            .map(|wire| (wire.value0().to_le_bytes(), wire.value1().to_le_bytes()))
+    }
+
+    /// The decoding information `d`(one per output) as canonical little-endian bytes
+    #[must_use]
+    pub(crate) fn decoding_info_le_bytes(&self) -> Vec<[u8; KAPPA_BYTES]> {
+        self.d.d.iter().map(BlockL::to_le_bytes).collect()
+    }
+}
+
 /// Similar to `CircuitMetadata` but only what is needed during evaluation(instead of during garbling)
 #[derive(PartialEq, Debug, Deserialize, Serialize, Clone)]
 pub(crate) struct EvalMetadata {
     pub(crate) nb_outputs: usize,
+    /// [Supporting Free-XOR] garbled with `garble_with_delta`: the `FreeXor` gates have no `∇g` in `F`
+    /// Any other gate without `∇g` is an error; cf `evaluate_internal`
+    pub(crate) is_free_xor: bool,
 }
 
 /// Grouping of all of the sequence:
@@ -339,32 +786,282 @@ pub(crate) struct EvalMetadata {
 /// * `rng_seed` - when None; will use the standard and secure `ChaChaRng::from_entropy`
 ///     when given: wil use the NOT SECURE `seed_from_u64`
 ///
+/// NOTE: ONLY a shortcut for the tests; `garble_circuit` calls `garble_reuse` directly
+/// so that it can keep the `Circuit`, cf `GarbledCircuit::verify_against_plain`
 // TODO? how to group the garble part vs eval vs decoding?
+#[cfg(test)]
 pub(crate) fn garble(
     circuit: Circuit,
     rng_seed: Option<u64>,
 ) -> Result<GarbledCircuitFinal, GarblerError> {
-    let mut rng = if let Some(rng_seed) = rng_seed {
+    garble_reuse(&circuit, rng_seed, &mut GarbleScratch::new())
+}
+
+/// Same as `garble`, but reusing the buffers in `scratch`
+/// NOTE: `circuit` is borrowed so the caller can garble the same parsed `Circuit` again
+/// eg for key rotation; cf `GarbledCircuit::regarble`
+pub(crate) fn garble_reuse(
+    circuit: &Circuit,
+    rng_seed: Option<u64>,
+    scratch: &mut GarbleScratch,
+) -> Result<GarbledCircuitFinal, GarblerError> {
+    let mut rng = new_rng(rng_seed);
+
+    // [Supporting Free-XOR] this is the "delta" for Free-XOR; ie a random BlockL
+    let r = RandomOracle::new_random_block_l(&mut rng);
+
+    garble_aux(circuit, &mut rng, &r, false, true, scratch)
+}
+
+/// Same as `garble`, but the `ChaChaRng` is seeded with the full 32 bytes `seed`
+/// instead of `seed_from_u64`; cf `garble_skcd_deterministic`
+pub(crate) fn garble_from_seed(
+    circuit: &Circuit,
+    seed: [u8; 32],
+) -> Result<GarbledCircuitFinal, GarblerError> {
+    let mut rng = ChaChaRng::from_seed(seed);
+
+    let r = RandomOracle::new_random_block_l(&mut rng);
+
+    garble_aux(
+        circuit,
+        &mut rng,
+        &r,
+        false,
+        true,
+        &mut GarbleScratch::new(),
+    )
+}
+
+/// Same as `garble`, but WITHOUT the decoding information `d`(ie it is left empty).
+/// For circuits only evaluated to output labels eg feeding another circuit(label-level composition);
+/// this skips the rejection sampling in `decoding_info`.
+/// Decoding the outputs of such a circuit returns `DecodingErrorMissingDecodingInfo`.
+pub(crate) fn garble_no_decoding(
+    circuit: &Circuit,
+    rng_seed: Option<u64>,
+) -> Result<GarbledCircuitFinal, GarblerError> {
+    let mut rng = new_rng(rng_seed);
+
+    let r = RandomOracle::new_random_block_l(&mut rng);
+
+    garble_aux(
+        circuit,
+        &mut rng,
+        &r,
+        false,
+        false,
+        &mut GarbleScratch::new(),
+    )
+}
+
+/// Variant of `garble` where the Free-XOR "delta" `r` is given by the caller
+/// instead of being sampled.
+/// This allows multiple circuits to share the same global delta.
+///
+/// The input labels are generated with `LW1 = LW0 ⊕ r`(cf `insert_new_wire_free_xor_labels`)
+/// and the XOR/XNOR gates are free(cf `free_xor_labels`) so their output wires also respect it.
+///
+/// # Arguments
+///
+/// * `r` - the Free-XOR "delta", as little-endian bytes; MUST NOT be all zeros
+/// * `rng` - used for the input labels and the decoding information
+///
+pub(crate) fn garble_with_delta(
+    circuit: &Circuit,
+    r: &[u8; KAPPA_BYTES],
+    rng: &mut dyn RngCore,
+) -> Result<GarbledCircuitFinal, GarblerError> {
+    let r = BlockL::new_from_le_bytes(r);
+    if r.is_zero() {
+        return Err(GarblerError::FreeXorDeltaIsZero);
+    }
+
+    garble_aux(circuit, rng, &r, true, true, &mut GarbleScratch::new())
+}
+
+/// Variant of `garble` where the input labels `e` are given by the caller instead of being sampled,
+/// eg to check against known-answer vectors.
+/// The decoding information `d` is still sampled using `rng_seed`.
+///
+/// # Arguments
+///
+/// * `labels` - `(value0, value1)` as little-endian bytes, one per input wire(in order)
+/// * `rng_seed` - cf `garble`
+///
+pub(crate) fn garble_with_labels(
+    circuit: &Circuit,
+    labels: &[([u8; KAPPA_BYTES], [u8; KAPPA_BYTES])],
+    rng_seed: Option<u64>,
+) -> Result<GarbledCircuitFinal, GarblerError> {
+    if labels.len() != circuit.get_nb_inputs() {
+        return Err(GarblerError::InputLabelsWrongLength {
+            len: labels.len(),
+            expected_len: circuit.get_nb_inputs(),
+        });
+    }
+    // CHECK: the inputs MUST be the first wires; cf `init_internal`
+    if let Some(input_wire) = circuit
+        .get_inputs()
+        .iter()
+        .find(|input_wire| input_wire.id >= labels.len())
+    {
+        return Err(GarblerError::InputWireNotLeading {
+            wire: input_wire.clone(),
+        });
+    }
+
+    let mut w = Vec::with_capacity(labels.len());
+    for (wire_idx, (lw0, lw1)) in labels.iter().enumerate() {
+        if lw0 == lw1 {
+            return Err(GarblerError::InputLabelsSame { wire_idx });
+        }
+        w.push(Wire::new(
+            BlockL::new_from_le_bytes(lw0),
+            BlockL::new_from_le_bytes(lw1),
+        ));
+    }
+
+    let mut rng = new_rng(rng_seed);
+
+    garble_with_encoding(
+        circuit,
+        InputEncodingSet { e: w },
+        None,
+        &mut rng,
+        true,
+        &mut GarbleScratch::new(),
+    )
+}
+
+/// Same as `garble`, but EVERY random block(the Free-XOR "delta", the input labels, and the
+/// decoding information) is drawn directly from `rng`, eg a hardware RNG in an enclave.
+pub(crate) fn garble_with_rng(
+    circuit: &Circuit,
+    rng: &mut dyn RngCore,
+) -> Result<GarbledCircuitFinal, GarblerError> {
+    let r = RandomOracle::new_random_block_l(rng);
+
+    garble_aux(circuit, rng, &r, false, true, &mut GarbleScratch::new())
+}
+
+/// * `rng_seed` - when None; will use the standard and secure `ChaChaRng::from_entropy`
+///     when given: wil use the NOT SECURE `seed_from_u64`
+fn new_rng(rng_seed: Option<u64>) -> ChaChaRng {
+    if let Some(rng_seed) = rng_seed {
         ChaChaRng::seed_from_u64(rng_seed)
     } else {
         ChaChaRng::from_entropy()
-    };
+    }
+}
 
-    // [Supporting Free-XOR] this is the "delta" for Free-XOR; ie a random BlockL
-    let r = RandomOracle::new_random_block_l(&mut rng);
+/// Common part of `garble`, `garble_with_delta` and `garble_no_decoding`
+///
+/// param `is_free_xor`: the input labels respect `LW1 = LW0 ⊕ r` and the XOR/XNOR gates are free
+fn garble_aux(
+    circuit: &Circuit,
+    rng: &mut dyn RngCore,
+    r: &BlockL,
+    is_free_xor: bool,
+    with_decoding_info: bool,
+    scratch: &mut GarbleScratch,
+) -> Result<GarbledCircuitFinal, GarblerError> {
+    #[cfg(feature = "timing")]
+    let stage_start = std::time::Instant::now();
+
+    let e = init_internal(circuit, rng, r, is_free_xor)?;
 
-    let e = init_internal(&circuit, &mut rng, &r);
+    #[cfg(feature = "timing")]
+    {
+        scratch.timings.init = stage_start.elapsed();
+    }
+
+    garble_with_encoding(
+        circuit,
+        e,
+        is_free_xor.then(|| r.clone()),
+        rng,
+        with_decoding_info,
+        scratch,
+    )
+}
 
-    let garbled_circuit = garble_internal(&circuit, &e)?;
+/// Common part of `garble_aux` and `garble_with_labels`: everything after `init_internal`
+fn garble_with_encoding(
+    circuit: &Circuit,
+    e: InputEncodingSet,
+    free_xor_delta: Option<BlockL>,
+    rng: &mut dyn RngCore,
+    with_decoding_info: bool,
+    scratch: &mut GarbleScratch,
+) -> Result<GarbledCircuitFinal, GarblerError> {
+    #[cfg(feature = "timing")]
+    let stage_start = std::time::Instant::now();
 
-    let d = decoding_info(circuit.get_outputs(), &garbled_circuit.d, &mut rng)?;
+    let garbled_circuit = garble_internal(circuit, &e, free_xor_delta, scratch)?;
+
+    #[cfg(feature = "timing")]
+    {
+        scratch.timings.gates = stage_start.elapsed();
+    }
+
+    garble_finalize(
+        circuit,
+        e,
+        garbled_circuit,
+        free_xor_delta.is_some(),
+        rng,
+        with_decoding_info,
+        scratch,
+    )
+}
+
+/// Common part of `garble_with_encoding` and `garble_resumable`: everything after the gates loop
+///
+/// param `is_free_xor`: the gates were garbled with a `free_xor_delta`; cf `EvalMetadata::is_free_xor`
+fn garble_finalize(
+    circuit: &Circuit,
+    e: InputEncodingSet,
+    garbled_circuit: GarbledCircuitInternal,
+    is_free_xor: bool,
+    rng: &mut dyn RngCore,
+    with_decoding_info: bool,
+    scratch: &mut GarbleScratch,
+) -> Result<GarbledCircuitFinal, GarblerError> {
+    #[cfg(feature = "timing")]
+    let stage_start = std::time::Instant::now();
+
+    let d = if with_decoding_info {
+        decoding_info(
+            circuit.get_outputs(),
+            &garbled_circuit.d,
+            rng,
+            &mut scratch.buf,
+        )?
+    } else {
+        DecodedInfo { d: Vec::new() }
+    };
+
+    #[cfg(feature = "timing")]
+    {
+        scratch.timings.decoding = stage_start.elapsed();
+    }
 
     let eval_metadata = EvalMetadata {
         nb_outputs: circuit.get_outputs().len(),
+        is_free_xor,
     };
 
+    let mut circuit_for_eval = CircuitForEval::from(circuit);
+    if is_free_xor {
+        // the binary gates garbled with `free_xor_labels` ie WITHOUT `∇g`
+        circuit_for_eval.mark_free_xor_gates(|gate_idx| {
+            matches!(garbled_circuit.f.f.get(gate_idx), Some(None))
+        });
+    }
+
     Ok(GarbledCircuitFinal {
-        circuit: circuit.into(),
+        circuit: circuit_for_eval,
         garbled_circuit,
         d,
         e,
@@ -372,14 +1069,170 @@ pub(crate) fn garble(
     })
 }
 
+/// Everything a garbling split in several calls needs to keep between them; cf `garble_resumable`
+/// NOTE: it contains the input labels etc ie it is as secret as the resulting `GarbledCircuitFinal`.
+pub struct GarbleState {
+    /// None before the first call, and after the last one(ie the circuit was moved out)
+    in_progress: Option<GarbleStateInProgress>,
+    scratch: GarbleScratch,
+}
+
+struct GarbleStateInProgress {
+    rng: ChaChaRng,
+    e: InputEncodingSet,
+    gates: GarbleGatesState,
+    next_gate: usize,
+}
+
+impl GarbleState {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            in_progress: None,
+            scratch: GarbleScratch::new(),
+        }
+    }
+}
+
+impl Default for GarbleState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Return value of `garble_resumable`
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum GarbleStep {
+    /// Call again with `from_gate = next_gate`
+    More {
+        next_gate: usize,
+    },
+    Done(GarbledCircuitFinal),
+}
+
+/// Same as `garble_reuse`, but split in several calls: each call garbles at most `budget` gates
+/// starting at `from_gate`, and the intermediate labels/`F`/`D` are kept in `state`.
+/// The decoding information is generated and the `GarbledCircuitFinal` assembled by the last call.
+/// With the same `rng_seed`, the result is the same as `garble` whatever the `budget`.
+///
+/// `from_gate`: 0 starts(or restarts) the garbling, and only then is `rng_seed` used;
+/// else it MUST be the `next_gate` returned by the previous call.
+/// `circuit` MUST be the same for all the calls.
+/// A `budget` of 0 is treated as 1, else the garbling would never progress.
+pub(crate) fn garble_resumable(
+    circuit: &Circuit,
+    rng_seed: Option<u64>,
+    from_gate: usize,
+    budget: usize,
+    state: &mut GarbleState,
+) -> Result<GarbleStep, GarblerError> {
+    if from_gate == 0 {
+        let mut rng = new_rng(rng_seed);
+        // cf `garble_reuse`
+        let r = RandomOracle::new_random_block_l(&mut rng);
+        let e = init_internal(circuit, &mut rng, &r, false)?;
+        let gates = garble_gates_init(circuit, &e, None, &mut state.scratch.encoded_wires)?;
+        state.in_progress = Some(GarbleStateInProgress {
+            rng,
+            e,
+            gates,
+            next_gate: 0,
+        });
+    }
+
+    let mut in_progress = match state.in_progress.take() {
+        Some(in_progress) if in_progress.next_gate == from_gate => in_progress,
+        other => {
+            let expected_gate = other
+                .as_ref()
+                .map_or(0, |in_progress| in_progress.next_gate);
+            state.in_progress = other;
+            return Err(GarblerError::ResumeWrongGate {
+                from_gate,
+                expected_gate,
+            });
+        }
+    };
+
+    let all_gates = circuit.get_gates();
+    let end_gate = from_gate.saturating_add(budget.max(1)).min(all_gates.len());
+    let outputs_set: HashSet<&WireRef> = circuit.get_outputs().iter().collect();
+    garble_gates(
+        all_gates.get(from_gate..end_gate).unwrap_or_default(),
+        from_gate,
+        &outputs_set,
+        &mut in_progress.gates,
+        &mut state.scratch.encoded_wires,
+        &mut state.scratch.buf,
+    )?;
+    if end_gate < all_gates.len() {
+        in_progress.next_gate = end_gate;
+        state.in_progress = Some(in_progress);
+        return Ok(GarbleStep::More {
+            next_gate: end_gate,
+        });
+    }
+
+    let GarbleStateInProgress {
+        mut rng, e, gates, ..
+    } = in_progress;
+    let garbled_circuit = GarbledCircuitInternal {
+        f: F { f: gates.f },
+        d: D { d: gates.deltas },
+    };
+
+    garble_finalize(
+        circuit,
+        e,
+        garbled_circuit,
+        false,
+        &mut rng,
+        true,
+        &mut state.scratch,
+    )
+    .map(GarbleStep::Done)
+}
+
+/// Number of bytes of a `usize` once varint-encoded(cf postcard): 7 bits per byte
+fn varint_len(value: usize) -> usize {
+    let nb_bits = usize::BITS - value.leading_zeros();
+    (nb_bits as usize).div_ceil(7).max(1)
+}
+
 /// Noted `d` in the paper
 ///
-#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+#[derive(PartialEq, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "debug_secrets", derive(Debug))]
 pub(super) struct DecodedInfo {
     /// One element per output
     pub(super) d: Vec<BlockL>,
 }
 
+/// cf `impl Debug for InputEncodingSet`
+#[cfg(not(feature = "debug_secrets"))]
+impl core::fmt::Debug for DecodedInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DecodedInfo")
+            .field("len", &self.d.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// cf `InputEncodingSet`
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for DecodedInfo {
+    fn zeroize(&mut self) {
+        self.d.iter_mut().for_each(zeroize::Zeroize::zeroize);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for DecodedInfo {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
 /// In <https://eprint.iacr.org/2021/739.pdf>
 /// "Algorithm 6 DecodingInfo(D, ℓ)"
 ///
@@ -391,10 +1244,10 @@ pub(super) struct DecodedInfo {
 fn decoding_info(
     circuit_outputs: &[WireRef],
     d_up: &D,
-    rng: &mut ChaChaRng,
+    rng: &mut dyn RngCore,
+    buf: &mut BytesMut,
 ) -> Result<DecodedInfo, GarblerError> {
     let mut d = Vec::with_capacity(circuit_outputs.len());
-    let mut buf = BytesMut::new();
 
     // "2: for output wire j ∈ [m] do"
     for (_idx, output_wire) in circuit_outputs.iter().enumerate() {
@@ -408,8 +1261,8 @@ fn decoding_info(
 
         let mut dj = RandomOracle::new_random_block_l(rng);
         loop {
-            let a = !RandomOracle::random_oracle_prime(lj0, &dj, &mut buf);
-            let b = RandomOracle::random_oracle_prime(lj1, &dj, &mut buf);
+            let a = !RandomOracle::random_oracle_prime(lj0, &dj, buf);
+            let b = RandomOracle::random_oracle_prime(lj1, &dj, buf);
             if a && b {
                 break;
             }
@@ -428,7 +1281,10 @@ mod tests {
     use rand::SeedableRng;
     use rand_chacha::ChaChaRng;
 
+    use circuit_types_rs::KindBinary;
+
     use super::*;
+    use crate::new_garbling_scheme::{evaluate::evaluate_full_chain, wire_value::WireValue};
 
     #[test]
     fn test_decoding_info() {
@@ -441,10 +1297,492 @@ mod tests {
 
         let d = D { d: d_up };
 
-        let d = decoding_info(&circuit_outputs, &d, &mut rng).unwrap();
+        let d = decoding_info(&circuit_outputs, &d, &mut rng, &mut BytesMut::new()).unwrap();
         let dj = &d.d[0];
         let mut buf = BytesMut::new();
         assert!(!RandomOracle::random_oracle_prime(&l0, dj, &mut buf));
         assert!(RandomOracle::random_oracle_prime(&l1, dj, &mut buf));
     }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize_clears_all_labels() {
+        use zeroize::Zeroize;
+
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut garbled = garble(circ, Some(42)).unwrap();
+        assert!(garbled.e.e.iter().all(|wire| !wire.value0().is_zero()));
+
+        garbled.e.zeroize();
+        garbled.garbled_circuit.d.zeroize();
+        garbled.d.zeroize();
+
+        assert!(garbled
+            .e
+            .e
+            .iter()
+            .all(|wire| wire.value0().is_zero() && wire.value1().is_zero()));
+        assert!(garbled
+            .garbled_circuit
+            .d
+            .d
+            .values()
+            .all(|(l0, l1)| l0.is_zero() && l1.is_zero()));
+        assert!(garbled.d.d.iter().all(BlockL::is_zero));
+    }
+
+    #[test]
+    fn test_self_check() {
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut garbled = garble(circ, Some(42)).unwrap();
+        assert_eq!(garbled.self_check(), Ok(()));
+
+        // corrupt the blob: truncated `F`
+        garbled.garbled_circuit.f.f.pop();
+        let buf = postcard::to_allocvec(&garbled).unwrap();
+        let corrupted: GarbledCircuitFinal = postcard::from_bytes(&buf).unwrap();
+
+        assert!(matches!(
+            corrupted.self_check(),
+            Err(InterstellarError::GarbledCircuitInconsistent { field: "F", .. })
+        ));
+    }
+
+    /// The placeholders `constant_block0/1` are NOT random; check that a Binary gate with one constant
+    /// input still gets distinct and uniformly distributed output labels.
+    #[test]
+    fn test_constant_feeding_binary_gate_labels_are_unbiased() {
+        const NB_TRIALS: usize = 256;
+
+        let circ = Circuit::new_test_circuit(KindBinary::AND);
+        let gate = &circ.get_gates()[0];
+        let (gate_type, input_a, input_b) = match gate.get_type() {
+            GateType::Binary {
+                gate_type,
+                input_a,
+                input_b,
+            } => (gate_type, input_a, input_b),
+            _ => unreachable!("new_test_circuit: expected a Binary gate"),
+        };
+        let constant_block0 = BlockL::new_with([0, 0]);
+        let constant_block1 = BlockL::new_with([u64::MAX, u64::MAX]);
+
+        let mut nb_ones = 0;
+        let mut buf = BytesMut::new();
+        for seed in 0..NB_TRIALS {
+            let mut rng = ChaChaRng::seed_from_u64(seed as u64);
+            let mut encoded_wires = WireStorage::new();
+            encoded_wires.reset(circ.get_nb_wires(), circ.get_nb_wires());
+            encoded_wires.set(
+                input_a.id,
+                Wire::new(constant_block0.clone(), constant_block1.clone()),
+            );
+            encoded_wires.set(
+                input_b.id,
+                Wire::new(
+                    RandomOracle::new_random_block_l(&mut rng),
+                    RandomOracle::new_random_block_l(&mut rng),
+                ),
+            );
+
+            let compressed_set =
+                f1_0_compress(&encoded_wires, gate, input_a, input_b, &mut buf).unwrap();
+            let (l0, l1, _delta) =
+                delta::Delta::new(&compressed_set, gate_type, gate.get_id()).unwrap();
+            let (l0, l1): (BlockL, BlockL) = (l0.into(), l1.into());
+
+            assert_ne!(l0, l1);
+            for label in [&l0, &l1] {
+                assert_ne!(label, &constant_block0);
+                assert_ne!(label, &constant_block1);
+                nb_ones += label
+                    .to_le_bytes()
+                    .iter()
+                    .map(|byte| byte.count_ones() as usize)
+                    .sum::<usize>();
+            }
+        }
+
+        // 2 labels per trial; expect ~50% of ones, with a lot of margin
+        let nb_bits = NB_TRIALS * 2 * KAPPA_BYTES * 8;
+        assert!(
+            (nb_bits * 45 / 100..nb_bits * 55 / 100).contains(&nb_ones),
+            "biased labels: {nb_ones} ones out of {nb_bits} bits"
+        );
+    }
+
+    #[test]
+    fn test_constant_gates_are_not_materialized() {
+        for value in [false, true] {
+            let circ = Circuit::new_test_circuit_constant(value);
+            let nb_inputs = circ.get_nb_inputs();
+            let garbled = garble(circ, None).unwrap();
+
+            // only the "real" inputs are encoded; and the constant gate needs no Delta
+            assert_eq!(garbled.nb_input_labels(), nb_inputs);
+            assert_eq!(garbled.nb_materialized_gates(), 0);
+
+            for input in [false, true] {
+                let inputs = vec![WireValue::from(input); nb_inputs];
+                let outputs = evaluate_full_chain(&garbled, &inputs).unwrap();
+                assert_eq!(outputs, vec![WireValue::from(value)]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_garble_scratch_buf_reserved_to_max_buf_len() {
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut scratch = GarbleScratch::new();
+
+        garble_reuse(&circ, Some(42), &mut scratch).unwrap();
+
+        assert!(scratch.buf.capacity() >= RandomOracle::max_buf_len());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize_garble_scratch_encoded_wires() {
+        use zeroize::Zeroize;
+
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut scratch = GarbleScratch::new();
+        garble_reuse(&circ, Some(42), &mut scratch).unwrap();
+        let nb_wires = nb_wire_ids(&circ);
+        assert!((0..nb_wires)
+            .filter_map(|id| scratch.encoded_wires.get(id))
+            .any(|wire| !wire.value0().is_zero()));
+
+        scratch.encoded_wires.zeroize();
+
+        assert!((0..nb_wires)
+            .filter_map(|id| scratch.encoded_wires.get(id))
+            .all(|wire| wire.value0().is_zero() && wire.value1().is_zero()));
+    }
+
+    #[cfg(feature = "timing")]
+    #[test]
+    fn test_garble_timings_full_adder() {
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut scratch = GarbleScratch::new();
+        assert_eq!(scratch.timings(), &GarbleTimings::default());
+
+        garble_reuse(&circ, Some(42), &mut scratch).unwrap();
+
+        let timings = scratch.timings();
+        assert!(timings.init + timings.gates + timings.decoding > std::time::Duration::ZERO);
+        // the full adder has binary gates, and outputs to decode
+        assert!(timings.gates > std::time::Duration::ZERO);
+        assert!(timings.decoding > std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn test_garble_reuse_same_circuit_different_seeds() {
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut scratch = GarbleScratch::new();
+
+        let garbled1 = garble_reuse(&circ, Some(1), &mut scratch).unwrap();
+        let garbled2 = garble_reuse(&circ, Some(2), &mut scratch).unwrap();
+
+        assert_ne!(garbled1.garbled_circuit.f, garbled2.garbled_circuit.f);
+        assert_eq!(garbled1.circuit, garbled2.circuit);
+    }
+
+    #[test]
+    fn test_is_gate_free() {
+        for (circ, expected_is_free) in [
+            (Circuit::new_test_circuit_unary(KindUnary::INV), true),
+            (Circuit::new_test_circuit_constant(true), true),
+            (Circuit::new_test_circuit(KindBinary::AND), false),
+        ] {
+            let garbled = garble(circ, None).unwrap();
+            let gate_id = garbled.circuit.get_gates()[0].get_id();
+
+            assert_eq!(garbled.is_gate_free(gate_id), Some(expected_is_free));
+            // inputs are NOT gates
+            for input in garbled.circuit.get_inputs() {
+                assert_eq!(garbled.is_gate_free(input.id), None);
+            }
+            assert_eq!(garbled.is_gate_free(usize::MAX), None);
+        }
+    }
+
+    #[test]
+    fn test_varint_len() {
+        assert_eq!(varint_len(0), 1);
+        assert_eq!(varint_len(127), 1);
+        assert_eq!(varint_len(128), 2);
+        assert_eq!(varint_len(16383), 2);
+        assert_eq!(varint_len(16384), 3);
+    }
+
+    #[test]
+    fn test_f_byte_size_vs_serialized() {
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let garbled = garble(circ, Some(42)).unwrap();
+
+        let serialized_len = postcard::to_allocvec(&garbled.garbled_circuit.f)
+            .unwrap()
+            .len();
+        let f_byte_size = garbled.f_byte_size();
+
+        // upper bound; the random words are a bit shorter once varint-encoded
+        assert!(f_byte_size >= serialized_len);
+        assert!(
+            f_byte_size - serialized_len <= serialized_len / 10,
+            "f_byte_size = {f_byte_size}, serialized = {serialized_len}"
+        );
+    }
+
+    /// The output wire of a XOR/XNOR gate MUST respect the shared delta, in all the circuits;
+    /// and the gate MUST be free ie no `Delta` in `F`.
+    #[test]
+    fn test_garble_with_delta_shared_between_circuits() {
+        let r_bytes = [42u8; KAPPA_BYTES];
+        let r = BlockL::new_from_le_bytes(&r_bytes);
+        let mut rng = ChaChaRng::seed_from_u64(42);
+
+        for gate_type in [KindBinary::XOR, KindBinary::XNOR] {
+            let circ = Circuit::new_test_circuit(gate_type);
+            let garbled = garble_with_delta(&circ, &r_bytes, &mut rng).unwrap();
+
+            for wire in &garbled.e.e {
+                assert_eq!(wire.value0().xor(wire.value1()), r);
+            }
+            let output_wire = &circ.get_outputs()[0];
+            let (value0, value1) = &garbled.garbled_circuit.d.d[output_wire];
+            assert_eq!(value0.xor(value1), r);
+            assert_eq!(garbled.is_gate_free(output_wire.id), Some(true));
+        }
+
+        // NOT free: the output labels are independent of `r`
+        let circ = Circuit::new_test_circuit(KindBinary::AND);
+        let garbled = garble_with_delta(&circ, &r_bytes, &mut rng).unwrap();
+        let output_wire = &circ.get_outputs()[0];
+        let (value0, value1) = &garbled.garbled_circuit.d.d[output_wire];
+        assert_ne!(value0.xor(value1), r);
+        assert_eq!(garbled.is_gate_free(output_wire.id), Some(false));
+    }
+
+    #[test]
+    fn test_garble_with_delta_evaluate_xor() {
+        let r_bytes = [42u8; KAPPA_BYTES];
+        let mut rng = ChaChaRng::seed_from_u64(42);
+
+        for (gate_type, expected_outputs) in [
+            (KindBinary::XOR, [false, true, true, false]),
+            (KindBinary::XNOR, [true, false, false, true]),
+        ] {
+            let garbled =
+                garble_with_delta(&Circuit::new_test_circuit(gate_type), &r_bytes, &mut rng)
+                    .unwrap();
+
+            for (inputs, expected_output) in
+                [[false, false], [false, true], [true, false], [true, true]]
+                    .into_iter()
+                    .zip(expected_outputs)
+            {
+                let inputs: Vec<WireValue> = inputs.into_iter().map(Into::into).collect();
+                let outputs = evaluate_full_chain(&garbled, &inputs).unwrap();
+                assert_eq!(outputs.len(), 1);
+                assert_eq!(outputs[0], expected_output);
+            }
+        }
+    }
+
+    /// Whatever mix of free XOR/XNOR and `Delta` gates the full adder contains, the outputs
+    /// MUST match `eval_plain` for all the inputs.
+    #[test]
+    fn test_garble_with_delta_full_adder_matches_eval_plain() {
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let garbled = garble_with_delta(&circ, &[42u8; KAPPA_BYTES], &mut rng).unwrap();
+
+        for inputs_idx in 0..(1u32 << circ.get_nb_inputs()) {
+            let inputs: Vec<u8> = (0..circ.get_nb_inputs())
+                .map(|bit| u8::from((inputs_idx >> bit) & 1 == 1))
+                .collect();
+            let inputs_wire_value: Vec<WireValue> = inputs.iter().map(Into::into).collect();
+
+            let outputs: Vec<u8> = evaluate_full_chain(&garbled, &inputs_wire_value)
+                .unwrap()
+                .iter()
+                .map(u8::from)
+                .collect();
+            assert_eq!(outputs, crate::eval_plain(&circ, &inputs).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_garble_with_delta_zero_is_rejected() {
+        let res = garble_with_delta(
+            &Circuit::new_test_circuit(KindBinary::XOR),
+            &[0u8; KAPPA_BYTES],
+            &mut ChaChaRng::seed_from_u64(42),
+        );
+
+        assert!(matches!(res, Err(GarblerError::FreeXorDeltaIsZero)));
+    }
+
+    #[test]
+    fn test_garble_with_labels_encodes_the_given_labels() {
+        let circ = Circuit::new_test_circuit(KindBinary::AND);
+        let labels: Vec<_> = (0..circ.get_nb_inputs())
+            .map(|idx| {
+                let idx = u8::try_from(idx).unwrap();
+                ([2 * idx; KAPPA_BYTES], [2 * idx + 1; KAPPA_BYTES])
+            })
+            .collect();
+
+        let garbled = garble_with_labels(&circ, &labels, Some(42)).unwrap();
+
+        for (wire_idx, expected_labels) in labels.iter().enumerate() {
+            assert_eq!(
+                garbled.input_labels_le_bytes(wire_idx).as_ref(),
+                Some(expected_labels)
+            );
+        }
+        // and it still evaluates correctly; cf `test_basic_and`
+        for (inputs, expected_output) in [
+            ([false, false], false),
+            ([false, true], false),
+            ([true, false], false),
+            ([true, true], true),
+        ] {
+            let inputs: Vec<WireValue> = inputs.into_iter().map(Into::into).collect();
+            let outputs = evaluate_full_chain(&garbled, &inputs).unwrap();
+            assert_eq!(outputs, vec![WireValue::from(expected_output)]);
+        }
+    }
+
+    /// `{:?}` MUST NOT print the labels; cf feature "`debug_secrets`"
+    #[cfg(not(feature = "debug_secrets"))]
+    #[test]
+    fn test_debug_redacts_labels() {
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let garbled = garble(circ, Some(42)).unwrap();
+
+        let debug = format!("{garbled:?}");
+
+        assert!(debug.contains("InputEncodingSet { len: 3, .. }"));
+        assert!(debug.contains("DecodedInfo { len: 2, .. }"));
+        let labels = garbled
+            .e
+            .e
+            .iter()
+            .flat_map(|wire| [wire.value0(), wire.value1()])
+            .chain(&garbled.d.d);
+        for label in labels {
+            for word in label.to_le_bytes().chunks_exact(8) {
+                let word = u64::from_le_bytes(word.try_into().unwrap());
+                assert!(!debug.contains(&word.to_string()), "{word} in {debug}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_garble_with_rng_draws_everything_from_rng() {
+        /// Forward everything to `inner`, counting the number of random bytes drawn
+        struct CountingRng {
+            inner: ChaChaRng,
+            nb_bytes: usize,
+        }
+
+        impl RngCore for CountingRng {
+            fn next_u32(&mut self) -> u32 {
+                self.nb_bytes += 4;
+                self.inner.next_u32()
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                self.nb_bytes += 8;
+                self.inner.next_u64()
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                self.nb_bytes += dest.len();
+                self.inner.fill_bytes(dest);
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+                self.nb_bytes += dest.len();
+                self.inner.try_fill_bytes(dest)
+            }
+        }
+
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let new_counting_rng = || CountingRng {
+            inner: ChaChaRng::seed_from_u64(42),
+            nb_bytes: 0,
+        };
+
+        let mut rng1 = new_counting_rng();
+        let garbled1 = garble_with_rng(&circ, &mut rng1).unwrap();
+        let mut rng2 = new_counting_rng();
+        let garbled2 = garble_with_rng(&circ, &mut rng2).unwrap();
+
+        // NOTHING else is random: the same RNG stream gives the same garbled circuit
+        assert_eq!(garbled1, garbled2);
+        assert_eq!(rng1.nb_bytes, rng2.nb_bytes);
+        // one block(= KAPPA_BYTES) for "delta", two per input, and at least one per output;
+        // the rest is the rejection sampling in `decoding_info`
+        assert_eq!(rng1.nb_bytes % KAPPA_BYTES, 0);
+        assert!(
+            rng1.nb_bytes >= KAPPA_BYTES * (1 + 2 * circ.get_nb_inputs() + circ.get_nb_outputs())
+        );
+    }
+
+    #[test]
+    fn test_garble_with_labels_invalid() {
+        let circ = Circuit::new_test_circuit(KindBinary::AND);
+
+        assert!(matches!(
+            garble_with_labels(&circ, &[([0; KAPPA_BYTES], [1; KAPPA_BYTES])], None),
+            Err(GarblerError::InputLabelsWrongLength {
+                len: 1,
+                expected_len: 2
+            })
+        ));
+        assert!(matches!(
+            garble_with_labels(
+                &circ,
+                &[
+                    ([0; KAPPA_BYTES], [1; KAPPA_BYTES]),
+                    ([2; KAPPA_BYTES], [2; KAPPA_BYTES])
+                ],
+                None
+            ),
+            Err(GarblerError::InputLabelsSame { wire_idx: 1 })
+        ));
+    }
 }
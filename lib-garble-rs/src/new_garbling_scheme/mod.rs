@@ -34,19 +34,30 @@
 //! interpretation would always be clear from the context.""
 
 mod block;
-mod circuit_for_eval;
 mod constant;
 mod delta;
+mod eval_schedule;
 mod random_oracle;
 mod wire_labels_set;
 mod wire_labels_set_bitslice;
+mod wire_storage;
 
+pub(crate) mod circuit_for_eval;
 pub(crate) mod evaluate;
+pub(crate) mod evaluate_streaming;
 pub(crate) mod garble;
 pub(crate) mod wire;
 pub(crate) mod wire_value;
 
+pub use block::KAPPA_BYTES;
+pub(crate) use block::KAPPA_NB_ELEMENTS;
+pub(crate) use delta::FreeXorWires;
+pub use delta::{
+    gate_truth_table, is_gate_type_free, GarblingMode, GateKindIsFree, GateTruthTable,
+};
+pub use eval_schedule::EvalSchedule;
 pub(super) use garble::GarblerError;
+pub use random_oracle::{Blake3Backend, RandomOracleBackend, Xxh3Backend, RO_OUTPUT_BYTES};
 
 #[cfg(feature = "key_length_search")]
 mod key_length;
@@ -212,8 +223,6 @@ mod tests {
     }
 
     #[test]
-    // TODO(new-garbling-scheme)[opt-0-1] should probably apply the same "free-BUF" for constant 0/1
-    //  right now the 0/1 gates are rewritten by skcd_parser so we can not build a circuit with them directy
     fn test_basic_zero() {
         // inputs, expected_output
         let tests: Vec<(Vec<wire_value::WireValue>, wire_value::WireValue)> = vec![
@@ -227,8 +236,6 @@ mod tests {
     }
 
     #[test]
-    // TODO(new-garbling-scheme)[opt-0-1] should probably apply the same "free-BUF" for constant 0/1
-    //  right now the 0/1 gates are rewritten by skcd_parser so we can not build a circuit with them directy
     fn test_basic_one() {
         // inputs, expected_output
         let tests: Vec<(Vec<wire_value::WireValue>, wire_value::WireValue)> = vec![
@@ -250,4 +257,183 @@ mod tests {
 
         garble(circ, None).unwrap();
     }
+
+    /// A stripped `∇g` MUST be reported as an error, NOT evaluated as a free XOR gate;
+    /// and a `FreeXor` gate is ONLY free in a circuit garbled with `garble_with_delta`.
+    #[test]
+    fn test_eval_missing_delta_returns_error() {
+        use rand::SeedableRng;
+
+        fn is_missing_delta(
+            gate_id: usize,
+            res: Result<Vec<wire_value::WireValue>, crate::InterstellarEvaluatorError>,
+        ) -> bool {
+            matches!(
+                res,
+                Err(crate::InterstellarEvaluatorError::EvaluateErrorMissingDelta { idx }) if idx == gate_id
+            )
+        }
+
+        let inputs = vec![wire_value::WireValue::from(1u8); 2];
+        let mut garbled = garble(Circuit::new_test_circuit(KindBinary::XOR), Some(42)).unwrap();
+        let gate_id = garbled.circuit.get_gates()[0].get_id();
+        garbled.garbled_circuit.f.f[0] = None;
+        assert!(is_missing_delta(
+            gate_id,
+            evaluate_full_chain(&garbled, &inputs)
+        ));
+
+        let mut garbled = garble::garble_with_delta(
+            &Circuit::new_test_circuit(KindBinary::XOR),
+            &[42u8; block::KAPPA_BYTES],
+            &mut rand_chacha::ChaChaRng::seed_from_u64(42),
+        )
+        .unwrap();
+        assert_eq!(
+            evaluate_full_chain(&garbled, &inputs).unwrap(),
+            vec![wire_value::WireValue::from(0u8)]
+        );
+        garbled.eval_metadata.is_free_xor = false;
+        assert!(is_missing_delta(
+            gate_id,
+            evaluate_full_chain(&garbled, &inputs)
+        ));
+    }
+
+    /// A malformed `GarbledCircuit`(here: one input label missing) MUST be reported as an error
+    /// instead of panicking on an out of range index.
+    #[cfg(feature = "strict_errors")]
+    #[test]
+    fn test_eval_missing_input_label_returns_error() {
+        let circ = circuit_types_rs::deserialize_from_buffer(include_bytes!(
+            "../../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let mut garbled = garble(circ, None).unwrap();
+        garbled.e.e.pop();
+
+        let inputs = vec![wire_value::WireValue::from(0u8); garbled.circuit.get_nb_inputs()];
+        assert!(matches!(
+            evaluate_full_chain(&garbled, &inputs),
+            Err(crate::InterstellarEvaluatorError::EvaluateErrorWireOutOfRange { .. })
+        ));
+    }
+
+    #[cfg(feature = "strict_errors")]
+    #[test]
+    fn test_eval_wrong_inputs_len_returns_error() {
+        let garbled = garble(Circuit::new_test_circuit(KindBinary::AND), None).unwrap();
+
+        let inputs = vec![wire_value::WireValue::from(0u8); 1];
+        assert!(matches!(
+            evaluate_full_chain(&garbled, &inputs),
+            Err(
+                crate::InterstellarEvaluatorError::EvaluateErrorWrongInputsLength {
+                    inputs_len: 1,
+                    expected_len: 2
+                }
+            )
+        ));
+    }
+
+    /// A sparse circuit: a handful of gates but ids up to 1_000_000.
+    /// Neither the wires(cf `WireStorage`) nor `F` are allocated per id.
+    #[test]
+    fn test_garble_eval_sparse_circuit() {
+        use circuit_types_rs::{GateType, WireRef};
+
+        let circuit = crate::circuit_builder::build_circuit(
+            vec![WireRef { id: 0 }, WireRef { id: 1 }],
+            vec![
+                (
+                    GateType::Binary {
+                        gate_type: KindBinary::XOR,
+                        input_a: WireRef { id: 0 },
+                        input_b: WireRef { id: 1 },
+                    },
+                    WireRef { id: 500_000 },
+                ),
+                (
+                    GateType::Unary {
+                        gate_type: KindUnary::INV,
+                        input_a: WireRef { id: 500_000 },
+                    },
+                    WireRef { id: 700_000 },
+                ),
+                (
+                    GateType::Binary {
+                        gate_type: KindBinary::AND,
+                        input_a: WireRef { id: 0 },
+                        input_b: WireRef { id: 700_000 },
+                    },
+                    WireRef { id: 1_000_000 },
+                ),
+            ],
+            vec![WireRef { id: 1_000_000 }],
+            None,
+        )
+        .unwrap();
+
+        let garbled = garble(circuit.clone(), Some(42)).unwrap();
+        assert_eq!(garbled.garbled_circuit.f.f.len(), 3);
+        assert_eq!(garbled.self_check(), Ok(()));
+
+        for inputs in [[0u8, 0], [0, 1], [1, 0], [1, 1]] {
+            let inputs_wire_value: Vec<wire_value::WireValue> =
+                inputs.iter().map(Into::into).collect();
+            let outputs: Vec<u8> = evaluate_full_chain(&garbled, &inputs_wire_value)
+                .unwrap()
+                .iter()
+                .map(u8::from)
+                .collect();
+
+            assert_eq!(outputs, crate::eval_plain(&circuit, &inputs).unwrap());
+            assert_eq!(outputs, vec![inputs[0] & u8::from(inputs[0] == inputs[1])]);
+        }
+    }
+
+    /// Property test: for many random circuits(cf `random_circuit`, ie all the gate types,
+    /// chained in random ways), garbled with a random seed(with or without Free-XOR)
+    /// and evaluated on random inputs, garble+eval MUST agree with `eval_plain`.
+    #[test]
+    fn test_garble_eval_matches_eval_plain() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaChaRng::seed_from_u64(42);
+        let mut scratch = garble::GarbleScratch::new();
+        for _ in 0..256 {
+            let num_inputs = rng.gen_range(1..=16);
+            let num_gates = rng.gen_range(1..=200);
+            let circ = &crate::random_circuit(&mut rng, num_inputs, num_gates).unwrap();
+
+            for _ in 0..4 {
+                let garbled = if rng.gen() {
+                    garble::garble_reuse(circ, Some(rng.gen()), &mut scratch).unwrap()
+                } else {
+                    garble::garble_with_delta(circ, &rng.gen(), &mut rng).unwrap()
+                };
+
+                for _ in 0..4 {
+                    let inputs: Vec<u8> = (0..circ.get_nb_inputs())
+                        .map(|_| u8::from(rng.gen::<bool>()))
+                        .collect();
+                    let inputs_wire_value: Vec<wire_value::WireValue> =
+                        inputs.iter().map(Into::into).collect();
+
+                    let outputs: Vec<u8> = evaluate_full_chain(&garbled, &inputs_wire_value)
+                        .unwrap()
+                        .iter()
+                        .map(u8::from)
+                        .collect();
+
+                    assert_eq!(
+                        outputs,
+                        crate::eval_plain(circ, &inputs).unwrap(),
+                        "{:?} with inputs {inputs:?}",
+                        crate::gate_type_histogram(circ)
+                    );
+                }
+            }
+        }
+    }
 }
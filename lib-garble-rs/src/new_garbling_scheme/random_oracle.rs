@@ -1,13 +1,64 @@
+use core::marker::PhantomData;
+
 use bytes::BytesMut;
-use rand::Rng;
-use rand_chacha::ChaChaRng;
+use rand::{Rng, RngCore};
 use xxhash_rust::xxh3::xxh3_128;
 
-use super::block::{BitsInternal, BlockL, BlockP, MyBitArrayL, KAPPA_NB_ELEMENTS};
+use super::block::{BitsInternal, BlockL, BlockP, KAPPA_BYTES, KAPPA_NB_ELEMENTS};
+use super::constant::KAPPA_FACTOR;
+
+/// The size in bytes of the output of RO ie of a `BlockP`
+pub const RO_OUTPUT_BYTES: usize = KAPPA_BYTES * KAPPA_FACTOR;
+
+/// The primitive(s) behind the Random Oracles RO and RO′.
+/// It works on bytes: `RandomOracleWith` lays out the labels(cf `write_compress_input`)
+/// and converts the result back; cf it for the details of what each function MUST compute.
+///
+/// The default is `Xxh3Backend`; feature "`ro_blake3`" selects `Blake3Backend` instead,
+/// for both garbling and eval.
+/// NOTE: garbling and eval MUST use the same backend! ie be built with the same features.
+pub trait RandomOracleBackend {
+    /// RO: `input` is `tweak || label_a [|| label_b]`, all little-endian -> `RO_OUTPUT_BYTES`
+    fn compress(input: &[u8]) -> [u8; RO_OUTPUT_BYTES];
+
+    /// MUST be the truncation of `compress` to its first `KAPPA_BYTES`.
+    /// This is called for each binary Gate at eval: a backend SHOULD override it
+    /// if it can skip computing the full output.
+    fn compress_truncated(input: &[u8]) -> [u8; KAPPA_BYTES] {
+        let mut truncated = [0; KAPPA_BYTES];
+        truncated.copy_from_slice(&Self::compress(input)[..KAPPA_BYTES]);
+        truncated
+    }
+
+    /// RO′: `input` is `l0_l1 || dj`, little-endian -> one bit
+    fn prime(input: &[u8]) -> bool;
+}
+
+/// Default backend: `xxh3_128`
+pub struct Xxh3Backend;
 
-pub(crate) struct RandomOracle {}
+/// `blake3`, in XOF mode for `compress`; cf feature "`ro_blake3`"
+pub struct Blake3Backend;
+
+/// The Random Oracles used by both garbling and eval.
+/// Dispatch to the hash in `B`; cf `RandomOracleBackend`
+pub(super) struct RandomOracleWith<B: RandomOracleBackend> {
+    backend: PhantomData<B>,
+}
+
+#[cfg(not(feature = "ro_blake3"))]
+pub(super) type RandomOracle = RandomOracleWith<Xxh3Backend>;
+#[cfg(feature = "ro_blake3")]
+pub(super) type RandomOracle = RandomOracleWith<Blake3Backend>;
+
+impl<B: RandomOracleBackend> RandomOracleWith<B> {
+    /// The largest data ever written to `buf` by one RO call:
+    /// the tweak + two labels(cf `write_compress_input`); `prime` only needs two labels.
+    /// Callers in hot loops SHOULD reserve their `BytesMut` to this once.
+    pub(super) const fn max_buf_len() -> usize {
+        core::mem::size_of::<usize>() + 2 * KAPPA_BYTES
+    }
 
-impl RandomOracle {
     /// First Random Oracle = RO0
     /// `ROg` : {0, 1}nℓ → {0, 1}ℓ′ in <https://eprint.iacr.org/2021/739.pdf>
     /// "The random oracle
@@ -29,95 +80,62 @@ impl RandomOracle {
         tweak: usize,
         buf: &mut BytesMut,
     ) -> BlockP {
-        let hash_0 = Self::random_oracle_g_core(label_a, label_b, tweak, buf);
+        Self::write_compress_input(label_a, label_b, tweak, buf);
 
-        // We need to construct the final `[u8; 128]` so for now we just concat
-        // `[u8; 128]` == `[0u8; KAPPA_NB_ELEMENTS * KAPPA_FACTOR * size_of::<BitsInternal>()]`
-        // -> We should re-hash in loop: https://github.com/Cyan4973/xxHash/issues/680
-        //
-        // TODO! is filling 8 * 128 bits OK from a 128 bits hash???
-        let hash_1 = xxh3_128(&hash_0.to_be_bytes());
-        let hash_2 = hash_1 ^ hash_0;
-        let hash_3 = hash_2 ^ hash_0;
-        let hash_4 = hash_3 ^ hash_0;
-        let hash_5 = hash_4 ^ hash_0;
-        let hash_6 = hash_5 ^ hash_0;
-        let hash_7 = hash_6 ^ hash_0;
+        BlockP::new_with_raw_bytes(B::compress(buf))
+    }
 
-        let hash_bytes_big: [u8; 128] = unsafe {
-            [
-                hash_0.to_le_bytes(),
-                hash_1.to_le_bytes(),
-                hash_2.to_le_bytes(),
-                hash_3.to_le_bytes(),
-                hash_4.to_le_bytes(),
-                hash_5.to_le_bytes(),
-                hash_6.to_le_bytes(),
-                hash_7.to_le_bytes(),
-            ]
-            .concat()
-            .try_into()
-            .unwrap_unchecked()
+    /// `random_oracle_g` for all 4 combinations of the labels of a binary gate's inputs,
+    /// in the order (a0,b0), (a0,b1), (a1,b0), (a1,b1).
+    /// Same data layout as `write_compress_input`, but the tweak is written once
+    /// and each label is converted to bytes once.
+    pub(super) fn random_oracle_g_binary_all(
+        labels_a: (&BlockL, &BlockL),
+        labels_b: (&BlockL, &BlockL),
+        tweak: usize,
+        buf: &mut BytesMut,
+    ) -> [BlockP; 4] {
+        let tweak_bytes = tweak.to_le_bytes();
+        let labels_a_bytes = [labels_a.0.to_le_bytes(), labels_a.1.to_le_bytes()];
+        let labels_b_bytes = [labels_b.0.to_le_bytes(), labels_b.1.to_le_bytes()];
+
+        buf.clear();
+        buf.reserve(tweak_bytes.len() + 2 * labels_a_bytes[0].len());
+        buf.extend_from_slice(&tweak_bytes);
+
+        let mut hash = |label_a_bytes: &[u8], label_b_bytes: &[u8]| {
+            buf.truncate(tweak_bytes.len());
+            buf.extend_from_slice(label_a_bytes);
+            buf.extend_from_slice(label_b_bytes);
+            BlockP::new_with_raw_bytes(B::compress(buf))
         };
 
-        BlockP::new_with_raw_bytes(hash_bytes_big)
+        [
+            hash(&labels_a_bytes[0], &labels_b_bytes[0]),
+            hash(&labels_a_bytes[0], &labels_b_bytes[1]),
+            hash(&labels_a_bytes[1], &labels_b_bytes[0]),
+            hash(&labels_a_bytes[1], &labels_b_bytes[1]),
+        ]
     }
 
     /// "Truncated" version of `random_oracle_g`
     /// This is used by eval to avoid allocating a `BlockP` just to convert(ie truncate) it
     /// into a `BlockL` right after.
-    /// Doing it that way avoids both an alloc, and more important: 7 rounds of `xxh3_128(or` XOR)
+    /// Doing it that way avoids both an alloc, and(with `Xxh3Backend`) 7 rounds of `xxh3_128(or` XOR)
     pub(super) fn random_oracle_g_truncated(
         label_a: &BlockL,
         label_b: Option<&BlockL>,
         tweak: usize,
         buf: &mut BytesMut,
     ) -> BlockL {
-        let hash_0 = Self::random_oracle_g_core(label_a, label_b, tweak, buf);
-
-        // https://stackoverflow.com/questions/75746412/copy-a-u128-into-u642
-        let words: MyBitArrayL = unsafe { core::mem::transmute::<u128, MyBitArrayL>(hash_0) };
+        Self::write_compress_input(label_a, label_b, tweak, buf);
 
-        BlockL::new_with(words)
+        // NOTE: DO NOT `transmute` into `MyBitArrayL`: the result would depend on the
+        // endianness of the machine, and garbler and evaluator MAY NOT run on the same architecture.
+        BlockL::new_from_le_bytes(&B::compress_truncated(buf))
     }
 
-    fn random_oracle_g_core(
-        label_a: &BlockL,
-        label_b: Option<&BlockL>,
-        tweak: usize,
-        buf: &mut BytesMut,
-    ) -> u128 {
-        // prepare the data: append `label_a` with `label_b` and `tweak`
-        // reuse `buf` to avoid alloc!
-        buf.clear();
-
-        let tweak_bytes_arr = tweak.to_le_bytes();
-        let tweak_bytes = tweak_bytes_arr.as_slice();
-        let label_a_bytes = label_a.as_bytes();
-
-        if let Some(label_b_block) = label_b {
-            let label_b_block_bytes = label_b_block.as_bytes();
-
-            buf.reserve(tweak_bytes.len() + label_a_bytes.len() + label_b_block_bytes.len());
-        } else {
-            buf.reserve(tweak_bytes.len() + label_a_bytes.len());
-        };
-
-        buf.extend_from_slice(tweak_bytes);
-        buf.extend_from_slice(label_a_bytes);
-
-        if let Some(label_b_block) = label_b {
-            let label_b_block_bytes = label_b_block.as_bytes();
-
-            buf.extend_from_slice(label_b_block_bytes);
-        };
-
-        // TODO! which hash to use? sha2, sha256?
-        // or maybe some MAC? cf `keyed_hash`?
-        xxh3_128(buf)
-    }
-
-    pub(super) fn new_random_block_l(rng: &mut ChaChaRng) -> BlockL {
+    pub(super) fn new_random_block_l(rng: &mut dyn RngCore) -> BlockL {
         let arr1: [BitsInternal; KAPPA_NB_ELEMENTS] = rng.gen();
         BlockL::new_with(arr1)
     }
@@ -141,12 +159,61 @@ impl RandomOracle {
         // prepare the data: append `l0_l1` with `dj`
         // reuse `buf` to avoid alloc!
         buf.clear();
-        let l0_l1_bytes = l0_l1.as_bytes();
-        let dj_bytes = dj.as_bytes();
+        let l0_l1_bytes = l0_l1.to_le_bytes();
+        let dj_bytes = dj.to_le_bytes();
         buf.reserve(l0_l1_bytes.len() + dj_bytes.len());
-        buf.extend_from_slice(l0_l1.as_bytes());
-        buf.extend_from_slice(dj.as_bytes());
-        let hash = xxh3_128(buf);
+        buf.extend_from_slice(&l0_l1_bytes);
+        buf.extend_from_slice(&dj_bytes);
+
+        B::prime(buf)
+    }
+
+    /// Write the input of RO in `buf`: `tweak || label_a [|| label_b]`
+    fn write_compress_input(
+        label_a: &BlockL,
+        label_b: Option<&BlockL>,
+        tweak: usize,
+        buf: &mut BytesMut,
+    ) {
+        // prepare the data: append `label_a` with `label_b` and `tweak`
+        // reuse `buf` to avoid alloc!
+        buf.clear();
+
+        // NOTE: use the canonical little-endian bytes of the labels, cf `BlockL::to_le_bytes`
+        let tweak_bytes_arr = tweak.to_le_bytes();
+        let tweak_bytes = tweak_bytes_arr.as_slice();
+        let label_a_bytes = label_a.to_le_bytes();
+        let label_b_bytes = label_b.map(BlockL::to_le_bytes);
+
+        buf.reserve(
+            tweak_bytes.len()
+                + label_a_bytes.len()
+                + label_b_bytes.as_ref().map_or(0, |bytes| bytes.len()),
+        );
+
+        buf.extend_from_slice(tweak_bytes);
+        buf.extend_from_slice(&label_a_bytes);
+
+        if let Some(label_b_bytes) = label_b_bytes {
+            buf.extend_from_slice(&label_b_bytes);
+        };
+    }
+}
+
+impl RandomOracleBackend for Xxh3Backend {
+    fn compress(input: &[u8]) -> [u8; RO_OUTPUT_BYTES] {
+        // TODO! which hash to use? sha2, sha256?
+        // or maybe some MAC? cf `keyed_hash`?
+        Self::expand(xxh3_128(input))
+    }
+
+    fn compress_truncated(input: &[u8]) -> [u8; KAPPA_BYTES] {
+        // the first bytes of `expand` ie no need for the 7 other rounds
+        xxh3_128(input).to_le_bytes()
+    }
+
+    fn prime(input: &[u8]) -> bool {
+        let hash = xxh3_128(input);
 
         // Extract the least significant bit from the hash
         // Technically we DO NOT need the LSB; we just need to be consistant b/w garbling and eval
@@ -174,40 +241,100 @@ impl RandomOracle {
         // // println!("random_oracle_prime: {:?}", x);
         // x
     }
+}
 
-    // /// Second Random Oracle = RO1
-    // /// "However, our second optimization shows that that this is unnecessary. Instead
-    // /// of sampling new labels KC0 and KC1, we can derive them directly from the values
-    // /// S0 and S1, even if the later have fewer than ` bits of entropy (as long as they
-    // /// have κ bits of entropy)."
-    // ///
-    // /// Used to generate:
-    // /// KC0 = RO1(S0)
-    // /// KC1 = RO1(S1)
-    // pub(super) fn random_oracle_1(sblock: &[WireValue]) -> BlockP {
-    //     // convert the &[bool] -> &[u8]
-    //     let mut bv = bitvec![u8, Msb0;];
-    //     for bit in sblock.into_iter() {
-    //         bv.push(*bit);
-    //     }
-
-    //     // TODO! which hash to use? sha2, sha256?
-    //     // or maybe some MAC? cf `keyed_hash`?
-    //     let mut hasher = blake3::Hasher::new();
-    //     hasher.update(bv.as_raw_slice());
-    //     let mut hash2 = hasher.finalize_xof();
-    //     // TODO! is filling 8 * 128 bits OK from a 256 bits hash???
-    //     let mut hash2_bytes = [0u8; KAPPA_BYTES * KAPPA_FACTOR];
-    //     hash2.fill(&mut hash2_bytes);
-
-    //     BlockP::new_with2(hash2_bytes)
-    // }
+impl Xxh3Backend {
+    /// Expand the 128 bits hash into a full `BlockP`
+    fn expand(hash_0: u128) -> [u8; RO_OUTPUT_BYTES] {
+        // We need to construct the final `[u8; 128]` so for now we just concat
+        // `[u8; 128]` == `[0u8; KAPPA_NB_ELEMENTS * KAPPA_FACTOR * size_of::<BitsInternal>()]`
+        // -> We should re-hash in loop: https://github.com/Cyan4973/xxHash/issues/680
+        //
+        // TODO! is filling 8 * 128 bits OK from a 128 bits hash???
+        let hash_1 = xxh3_128(&hash_0.to_be_bytes());
+        let hash_2 = hash_1 ^ hash_0;
+        let hash_3 = hash_2 ^ hash_0;
+        let hash_4 = hash_3 ^ hash_0;
+        let hash_5 = hash_4 ^ hash_0;
+        let hash_6 = hash_5 ^ hash_0;
+        let hash_7 = hash_6 ^ hash_0;
+
+        unsafe {
+            [
+                hash_0.to_le_bytes(),
+                hash_1.to_le_bytes(),
+                hash_2.to_le_bytes(),
+                hash_3.to_le_bytes(),
+                hash_4.to_le_bytes(),
+                hash_5.to_le_bytes(),
+                hash_6.to_le_bytes(),
+                hash_7.to_le_bytes(),
+            ]
+            .concat()
+            .try_into()
+            .unwrap_unchecked()
+        }
+    }
 }
 
+impl RandomOracleBackend for Blake3Backend {
+    fn compress(input: &[u8]) -> [u8; RO_OUTPUT_BYTES] {
+        let mut output = [0; RO_OUTPUT_BYTES];
+        blake3::Hasher::new()
+            .update(input)
+            .finalize_xof()
+            .fill(&mut output);
+        output
+    }
+
+    fn compress_truncated(input: &[u8]) -> [u8; KAPPA_BYTES] {
+        // a XOF: the first bytes do NOT depend on the length of the output
+        let mut output = [0; KAPPA_BYTES];
+        blake3::Hasher::new()
+            .update(input)
+            .finalize_xof()
+            .fill(&mut output);
+        output
+    }
+
+    fn prime(input: &[u8]) -> bool {
+        blake3::hash(input).as_bytes()[0] & 1 == 1
+    }
+}
+
+// /// Second Random Oracle = RO1
+// /// "However, our second optimization shows that that this is unnecessary. Instead
+// /// of sampling new labels KC0 and KC1, we can derive them directly from the values
+// /// S0 and S1, even if the later have fewer than ` bits of entropy (as long as they
+// /// have κ bits of entropy)."
+// ///
+// /// Used to generate:
+// /// KC0 = RO1(S0)
+// /// KC1 = RO1(S1)
+// pub(super) fn random_oracle_1(sblock: &[WireValue]) -> BlockP {
+//     // convert the &[bool] -> &[u8]
+//     let mut bv = bitvec![u8, Msb0;];
+//     for bit in sblock.into_iter() {
+//         bv.push(*bit);
+//     }
+
+//     // TODO! which hash to use? sha2, sha256?
+//     // or maybe some MAC? cf `keyed_hash`?
+//     let mut hasher = blake3::Hasher::new();
+//     hasher.update(bv.as_raw_slice());
+//     let mut hash2 = hasher.finalize_xof();
+//     // TODO! is filling 8 * 128 bits OK from a 256 bits hash???
+//     let mut hash2_bytes = [0u8; KAPPA_BYTES * KAPPA_FACTOR];
+//     hash2.fill(&mut hash2_bytes);
+
+//     BlockP::new_with2(hash2_bytes)
+// }
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
 
     fn get_test_blocks() -> (BlockL, BlockL, BlockL) {
         let block_a = BlockL::new_with([42, 0]);
@@ -228,6 +355,55 @@ mod tests {
         assert_ne!(hash1, hash2, "returning hashes SHOULD NOT be equal!");
     }
 
+    #[test]
+    fn test_random_oracle_g_binary_all_same_as_single_calls() {
+        let (block_a, block_b, block_common) = get_test_blocks();
+        let block_d = BlockL::new_with([13, 14]);
+        let mut buf = BytesMut::new();
+
+        let hashes = RandomOracle::random_oracle_g_binary_all(
+            (&block_a, &block_b),
+            (&block_common, &block_d),
+            42,
+            &mut buf,
+        );
+
+        assert_eq!(
+            hashes,
+            [
+                RandomOracle::random_oracle_g(&block_a, Some(&block_common), 42, &mut buf),
+                RandomOracle::random_oracle_g(&block_a, Some(&block_d), 42, &mut buf),
+                RandomOracle::random_oracle_g(&block_b, Some(&block_common), 42, &mut buf),
+                RandomOracle::random_oracle_g(&block_b, Some(&block_d), 42, &mut buf),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_random_oracle_g_binary_all_does_not_grow_buf() {
+        let (block_a, block_b, block_common) = get_test_blocks();
+        let mut buf = BytesMut::new();
+
+        RandomOracle::random_oracle_g_binary_all(
+            (&block_a, &block_b),
+            (&block_common, &block_a),
+            0,
+            &mut buf,
+        );
+        let capacity = buf.capacity();
+        for tweak in 1..100 {
+            RandomOracle::random_oracle_g_binary_all(
+                (&block_a, &block_b),
+                (&block_common, &block_a),
+                tweak,
+                &mut buf,
+            );
+        }
+
+        // ie no re-allocation once the buffer is warm
+        assert_eq!(buf.capacity(), capacity);
+    }
+
     #[test]
     fn test_random_oracle_0_same_blocks_same_tweaks_should_return_same_hashes() {
         let (block_a, block_b, _block_common) = get_test_blocks();
@@ -310,6 +486,81 @@ mod tests {
         assert!(count_true.abs_diff(count_false) < 100, "bad distribution!");
     }
 
+    /// Toy, NOT secure, backend: only here to check the dispatch.
+    /// `compress` is the input, zero-padded; `compress_truncated` is the default one.
+    struct ToyBackend;
+
+    impl RandomOracleBackend for ToyBackend {
+        fn compress(input: &[u8]) -> [u8; RO_OUTPUT_BYTES] {
+            let mut output = [0; RO_OUTPUT_BYTES];
+            output[..input.len()].copy_from_slice(input);
+            output
+        }
+
+        fn prime(input: &[u8]) -> bool {
+            (input[0] ^ input[KAPPA_BYTES]) & 1 == 1
+        }
+    }
+
+    /// `random_oracle_g_truncated` MUST be the truncation of `random_oracle_g`
+    fn assert_truncated_is_truncation<B: RandomOracleBackend>() {
+        let (block_a, block_b, _block_common) = get_test_blocks();
+        let mut buf = BytesMut::new();
+
+        for (label_b, tweak) in [(None, 0), (Some(&block_b), 2), (Some(&block_a), usize::MAX)] {
+            assert_eq!(
+                RandomOracleWith::<B>::random_oracle_g_truncated(
+                    &block_a, label_b, tweak, &mut buf
+                ),
+                BlockL::from(RandomOracleWith::<B>::random_oracle_g(
+                    &block_a, label_b, tweak, &mut buf
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_oracle_g_truncated_is_truncation() {
+        assert_truncated_is_truncation::<Xxh3Backend>();
+        assert_truncated_is_truncation::<Blake3Backend>();
+        assert_truncated_is_truncation::<ToyBackend>();
+    }
+
+    #[test]
+    fn test_random_oracle_custom_backend() {
+        let (block_a, block_b, _block_common) = get_test_blocks();
+        let mut buf = BytesMut::new();
+
+        // the first 16 bytes of: tweak(2 as a 64 bits usize, LE) || block_a
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(
+            RandomOracleWith::<ToyBackend>::random_oracle_g_truncated(&block_a, None, 2, &mut buf),
+            BlockL::new_with([2, 42])
+        );
+        // 42 ^ 43 == 1
+        assert!(RandomOracleWith::<ToyBackend>::random_oracle_prime(
+            &block_a, &block_b, &mut buf
+        ));
+        assert!(!RandomOracleWith::<ToyBackend>::random_oracle_prime(
+            &block_a, &block_a, &mut buf
+        ));
+
+        // and the other backends are NOT affected
+        assert_ne!(
+            RandomOracleWith::<Xxh3Backend>::random_oracle_g(&block_a, Some(&block_b), 2, &mut buf),
+            RandomOracleWith::<ToyBackend>::random_oracle_g(&block_a, Some(&block_b), 2, &mut buf)
+        );
+        assert_ne!(
+            RandomOracleWith::<Xxh3Backend>::random_oracle_g(&block_a, Some(&block_b), 2, &mut buf),
+            RandomOracleWith::<Blake3Backend>::random_oracle_g(
+                &block_a,
+                Some(&block_b),
+                2,
+                &mut buf
+            )
+        );
+    }
+
     // #[test]
     // fn test_random_oracle_1_same_blocks_should_return_same_hashes() {
     //     let block_a = vec![true; 16];
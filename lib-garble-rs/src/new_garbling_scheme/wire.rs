@@ -26,6 +26,13 @@ impl WireLabel {
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for WireLabel {
+    fn zeroize(&mut self) {
+        self.label.zeroize();
+    }
+}
+
 /// Like `WireLabel` by INTERNAL part
 /// So based on `l'` length block instead of `l`
 #[derive(Debug, Clone, PartialEq)]
@@ -51,6 +58,14 @@ pub(super) struct Wire {
     label1: WireLabel,
 }
 
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Wire {
+    fn zeroize(&mut self) {
+        self.label0.zeroize();
+        self.label1.zeroize();
+    }
+}
+
 impl Wire {
     /// Create a new `Wire`
     ///
@@ -0,0 +1,166 @@
+//! Per-wire storage used while garbling(`Wire`) and evaluating(`WireLabel`).
+//!
+//! The wires are indexed by id, and the ids are usually dense(inputs then one per gate)
+//! so a `Vec<Option<T>>` of `nb_wires` is the fastest.
+//! But for a sparse circuit(eg a handful of gates with ids around 1_000_000) that `Vec` would be
+//! mostly `None`; in that case we switch to a `HashMap` keyed by wire id.
+//! NOTE: `F` is NOT concerned: it is indexed by the position of the Gate, so it is never sparse.
+
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+
+/// Switch to `WireStorage::Sparse` when there are more than this many ids per used wire
+const SPARSITY_RATIO: usize = 16;
+
+pub(super) enum WireStorage<T> {
+    Dense(Vec<Option<T>>),
+    Sparse {
+        /// `set` MUST reject the same ids as `Dense`
+        nb_wires: usize,
+        wires: HashMap<usize, T>,
+    },
+}
+
+impl<T> WireStorage<T> {
+    pub(super) fn new() -> Self {
+        Self::Dense(Vec::new())
+    }
+
+    /// Clear everything, and pick the backing storage for a circuit with `nb_wires` wire ids
+    /// of which only `nb_used_wires`(ie inputs + gates) are actually set.
+    /// The allocation is reused when the backing does not change.
+    pub(super) fn reset(&mut self, nb_wires: usize, nb_used_wires: usize) {
+        let is_sparse = nb_wires / SPARSITY_RATIO > nb_used_wires;
+        match self {
+            Self::Dense(wires) if !is_sparse => {
+                wires.clear();
+                wires.resize_with(nb_wires, Default::default);
+            }
+            Self::Sparse {
+                nb_wires: current_nb_wires,
+                wires,
+            } if is_sparse => {
+                *current_nb_wires = nb_wires;
+                wires.clear();
+            }
+            _ if is_sparse => {
+                *self = Self::Sparse {
+                    nb_wires,
+                    wires: HashMap::with_capacity(nb_used_wires),
+                };
+            }
+            _ => {
+                let mut wires = Vec::new();
+                wires.resize_with(nb_wires, Default::default);
+                *self = Self::Dense(wires);
+            }
+        }
+    }
+
+    pub(super) fn get(&self, id: usize) -> Option<&T> {
+        match self {
+            Self::Dense(wires) => wires.get(id).and_then(Option::as_ref),
+            Self::Sparse { wires, .. } => wires.get(&id),
+        }
+    }
+
+    /// Return false if `id` is out of range ie not a valid wire of the current circuit
+    pub(super) fn set(&mut self, id: usize, value: T) -> bool {
+        match self {
+            Self::Dense(wires) => match wires.get_mut(id) {
+                Some(wire) => {
+                    *wire = Some(value);
+                    true
+                }
+                None => false,
+            },
+            Self::Sparse { nb_wires, wires } => {
+                if id >= *nb_wires {
+                    return false;
+                }
+                wires.insert(id, value);
+                true
+            }
+        }
+    }
+
+    /// Drop the value of `id`, eg a wire label after its last use; cf `eval_schedule`
+    pub(super) fn remove(&mut self, id: usize) {
+        match self {
+            Self::Dense(wires) => {
+                if let Some(wire) = wires.get_mut(id) {
+                    *wire = None;
+                }
+            }
+            Self::Sparse { wires, .. } => {
+                wires.remove(&id);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn is_sparse(&self) -> bool {
+        matches!(self, Self::Sparse { .. })
+    }
+}
+
+/// Clear the values in place(the allocation is kept); cf `impl Drop for GarbleScratch`
+#[cfg(feature = "zeroize")]
+impl<T: zeroize::Zeroize> zeroize::Zeroize for WireStorage<T> {
+    fn zeroize(&mut self) {
+        match self {
+            Self::Dense(wires) => wires
+                .iter_mut()
+                .flatten()
+                .for_each(zeroize::Zeroize::zeroize),
+            Self::Sparse { wires, .. } => wires.values_mut().for_each(zeroize::Zeroize::zeroize),
+        }
+    }
+}
+
+impl<T> Default for WireStorage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dense_circuit_stays_dense() {
+        let mut storage = WireStorage::new();
+        storage.reset(8, 8);
+
+        assert!(!storage.is_sparse());
+        assert!(storage.set(7, 42u32));
+        assert!(!storage.set(8, 42));
+        assert_eq!(storage.get(7), Some(&42));
+        assert_eq!(storage.get(0), None);
+        storage.remove(7);
+        assert_eq!(storage.get(7), None);
+    }
+
+    /// 3 inputs and one gate with id 1_000_000: MUST NOT allocate a slot per id
+    #[test]
+    fn test_sparse_circuit_uses_hashmap() {
+        let mut storage = WireStorage::new();
+        storage.reset(1_000_001, 4);
+
+        assert!(storage.is_sparse());
+        for id in [0, 1, 2, 1_000_000] {
+            assert!(storage.set(id, id));
+        }
+        assert!(!storage.set(1_000_001, 0));
+        assert_eq!(storage.get(1_000_000), Some(&1_000_000));
+        assert_eq!(storage.get(3), None);
+        storage.remove(1_000_000);
+        assert_eq!(storage.get(1_000_000), None);
+
+        // and back to dense for the next circuit; nothing leaks from the previous one
+        storage.reset(4, 4);
+        assert!(!storage.is_sparse());
+        assert_eq!(storage.get(0), None);
+    }
+}
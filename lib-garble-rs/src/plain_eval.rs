@@ -0,0 +1,187 @@
+//! Reference evaluation of a `Circuit` on plaintext bits, ie WITHOUT garbling.
+//! Used to cross-check the garbled evaluation; cf `GarbledCircuit::verify_against_plain`
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use circuit_types_rs::{Circuit, GateType, KindBinary, KindUnary, WireRef};
+
+use crate::InterstellarError;
+
+/// Evaluate `circuit` on `inputs`(one `u8` 0/1 per input, in the order of `circuit.get_inputs()`)
+/// NOTE: the gates are computed with plain `bool` ops, NOT with `gate_truth_table`(which is what
+/// the garbling uses) so that this is an independent reference.
+///
+/// Return: one `u8` 0/1 per output, in the order of `circuit.get_outputs()`
+///
+/// # Errors
+/// - `PlainInputsWrongLength` if `inputs` does not match the number of inputs of the circuit
+/// - `PlainEvalMissingWire` if a gate(or output) references a wire which was not computed before
+///
+pub fn eval_plain(circuit: &Circuit, inputs: &[u8]) -> Result<Vec<u8>, InterstellarError> {
+    if inputs.len() != circuit.get_nb_inputs() {
+        return Err(InterstellarError::PlainInputsWrongLength {
+            inputs_len: inputs.len(),
+            expected_len: circuit.get_nb_inputs(),
+        });
+    }
+
+    let mut wires: Vec<Option<bool>> = vec![None; circuit.get_nb_wires()];
+    for (input, value) in circuit.get_inputs().iter().zip(inputs) {
+        set_wire(&mut wires, input.id, *value != 0);
+    }
+
+    let get_wire = |wires: &[Option<bool>], wire: &WireRef| {
+        wires
+            .get(wire.id)
+            .copied()
+            .flatten()
+            .ok_or(InterstellarError::PlainEvalMissingWire { wire_id: wire.id })
+    };
+
+    for gate in circuit.get_gates() {
+        let value = match gate.get_type() {
+            GateType::Binary {
+                gate_type,
+                input_a,
+                input_b,
+            } => {
+                let a = get_wire(&wires, input_a)?;
+                let b = get_wire(&wires, input_b)?;
+                match gate_type {
+                    KindBinary::XOR => a ^ b,
+                    KindBinary::XNOR => a == b,
+                    KindBinary::AND => a & b,
+                    KindBinary::NAND => !(a & b),
+                    KindBinary::OR => a | b,
+                    KindBinary::NOR => !(a | b),
+                }
+            }
+            GateType::Unary { gate_type, input_a } => {
+                let a = get_wire(&wires, input_a)?;
+                match gate_type {
+                    KindUnary::INV => !a,
+                    KindUnary::BUF => a,
+                }
+            }
+            GateType::Constant { value } => *value,
+        };
+        set_wire(&mut wires, gate.get_id(), value);
+    }
+
+    circuit
+        .get_outputs()
+        .iter()
+        .map(|output| get_wire(&wires, output).map(u8::from))
+        .collect()
+}
+
+/// Whether `circuit` and `other` compute the same function, by exhaustively comparing
+/// `eval_plain` on every input combination; eg to check a rewritten(pruned, folded...) circuit.
+/// Circuits with a different number of inputs or outputs are never equal.
+///
+/// # Errors
+/// - `InputSpaceTooLarge` if the circuits have more than `max_inputs` inputs
+///   (or more than the number of bits of a `usize`)
+/// - cf `eval_plain`
+///
+pub fn functionally_equals(
+    circuit: &Circuit,
+    other: &Circuit,
+    max_inputs: usize,
+) -> Result<bool, InterstellarError> {
+    let nb_inputs = circuit.get_nb_inputs();
+    if nb_inputs != other.get_nb_inputs() || circuit.get_nb_outputs() != other.get_nb_outputs() {
+        return Ok(false);
+    }
+    if nb_inputs > max_inputs || nb_inputs >= usize::BITS as usize {
+        return Err(InterstellarError::InputSpaceTooLarge {
+            nb_inputs,
+            max_inputs,
+        });
+    }
+
+    let mut inputs = vec![0u8; nb_inputs];
+    for combination in 0..(1usize << nb_inputs) {
+        for (idx, input) in inputs.iter_mut().enumerate() {
+            *input = u8::from((combination >> idx) & 1 == 1);
+        }
+        if eval_plain(circuit, &inputs)? != eval_plain(other, &inputs)? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn set_wire(wires: &mut Vec<Option<bool>>, id: usize, value: bool) {
+    if id >= wires.len() {
+        wires.resize(id + 1, None);
+    }
+    wires[id] = Some(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{FULL_ADDER_2BITS_ALL_EXPECTED_OUTPUTS, FULL_ADDER_2BITS_ALL_INPUTS};
+    use circuit_types_rs::KindBinary;
+
+    #[test]
+    fn test_eval_plain_full_adder_2bits() {
+        let circuit = crate::parse_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        for (inputs, expected_outputs) in FULL_ADDER_2BITS_ALL_INPUTS
+            .iter()
+            .zip(FULL_ADDER_2BITS_ALL_EXPECTED_OUTPUTS)
+        {
+            assert_eq!(eval_plain(&circuit, inputs).unwrap(), expected_outputs);
+        }
+    }
+
+    #[test]
+    fn test_functionally_equals() {
+        let full_adder = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        let circuit = crate::parse_skcd(full_adder).unwrap();
+        let other = crate::parse_skcd(full_adder).unwrap();
+        let xor = Circuit::new_test_circuit(KindBinary::XOR);
+
+        assert_eq!(functionally_equals(&circuit, &other, 3), Ok(true));
+        assert_eq!(
+            functionally_equals(&xor, &Circuit::new_test_circuit(KindBinary::XOR), 2),
+            Ok(true)
+        );
+        assert_eq!(
+            functionally_equals(&xor, &Circuit::new_test_circuit(KindBinary::XNOR), 2),
+            Ok(false)
+        );
+        // different arity
+        assert_eq!(functionally_equals(&circuit, &xor, 3), Ok(false));
+        assert_eq!(
+            functionally_equals(&circuit, &other, 2),
+            Err(InterstellarError::InputSpaceTooLarge {
+                nb_inputs: 3,
+                max_inputs: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_eval_plain_wrong_inputs_length() {
+        let circuit = crate::parse_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            eval_plain(&circuit, &[0, 1]),
+            Err(InterstellarError::PlainInputsWrongLength {
+                inputs_len: 2,
+                expected_len: 3
+            })
+        );
+    }
+}
@@ -106,10 +106,12 @@ const MAP_DIGIT_TO7_SEGS: [[u8; 7]; 10] = [
 /// 0u16, 1, 1, 1, 0, 1, 0, //
 /// // second digit: 7 segments: 2
 /// 1u16, 0, 1, 1, 1, 0, 1, //
-pub(super) fn digits_to_segments_bits(digits: &[u8]) -> Result<Vec<u8>, SegmentsError> {
-    // 7 BITS per digit input
-    let mut res = Vec::with_capacity(digits.len() * 7);
-
+///
+/// NOTE: the 7 BITS per digit input are APPENDED to `res`
+pub(super) fn digits_to_segments_bits_into(
+    digits: &[u8],
+    res: &mut Vec<u8>,
+) -> Result<(), SegmentsError> {
     for digit in digits {
         // let seven_seg =
         SegmentsSevenKind::try_from(*digit).map_err(|e| SegmentsError { number: e.number })?;
@@ -118,5 +120,5 @@ pub(super) fn digits_to_segments_bits(digits: &[u8]) -> Result<Vec<u8>, Segments
         res.extend_from_slice(&MAP_DIGIT_TO7_SEGS[*digit as usize]);
     }
 
-    Ok(res)
+    Ok(())
 }
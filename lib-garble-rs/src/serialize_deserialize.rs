@@ -8,12 +8,23 @@
 ///   NOTE: works in `no_std/sgx` only when using pregenerated .rs
 use alloc::vec::Vec;
 
-use postcard::{from_bytes, to_allocvec};
+use postcard::{from_bytes, take_from_bytes, to_allocvec};
 use serde::{Deserialize, Serialize};
 
+use circuit_types_rs::{Circuit, DisplayConfig};
+
+use crate::new_garbling_scheme::circuit_for_eval::CircuitForEvalHeader;
+use crate::new_garbling_scheme::evaluate_streaming;
+#[cfg(feature = "std")]
+use crate::new_garbling_scheme::wire_value::WireValue;
+use crate::new_garbling_scheme::KAPPA_NB_ELEMENTS;
 use crate::EncodedGarblerInputs;
+#[cfg(feature = "std")]
+use crate::EvaluatorInput;
 use crate::GarbledCircuit;
 use crate::InterstellarError;
+#[cfg(feature = "std")]
+use crate::InterstellarEvaluatorError;
 
 /// That is the "package" sent to the client for evaluation
 #[derive(PartialEq, Debug, Serialize, Deserialize)]
@@ -22,8 +33,21 @@ pub struct EvaluableGarbledCircuit {
     encoded_garbler_inputs: EncodedGarblerInputs,
 }
 
+/// Header of every `serialize_for_evaluator` blob: this magic, the format version, then the number
+/// of words of a label(ie KAPPA).
+/// NOTE: fixed-size and at the START so that it can be checked BEFORE decoding the rest:
+/// postcard would happily mis-decode a blob garbled with another KAPPA.
+const BLOB_HEADER_MAGIC: [u8; 4] = *b"LGRS";
+const BLOB_HEADER_VERSION: u8 = 1;
+const BLOB_HEADER_LEN: usize = BLOB_HEADER_MAGIC.len() + 2;
+
+#[allow(clippy::cast_possible_truncation)]
+const BLOB_HEADER_NB_WORDS: u8 = KAPPA_NB_ELEMENTS as u8;
+
 /// Serialize
 /// Our use case only requires a subset of the whole (de)serialization so no need to expose the whole module
+/// The blob starts with a header containing the KAPPA of this build; cf `deserialize_for_evaluator`
+///
 ///# Errors
 ///
 /// `postcard::Error` if the serialization failed
@@ -48,34 +72,184 @@ pub fn serialize_for_evaluator(
         encoded_garbler_inputs,
     };
 
-    let buf: Vec<u8> = to_allocvec(&eval_garb)
-        .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })?;
+    let mut buf = Vec::from(BLOB_HEADER_MAGIC);
+    buf.push(BLOB_HEADER_VERSION);
+    buf.push(BLOB_HEADER_NB_WORDS);
+    buf.extend_from_slice(
+        &to_allocvec(&eval_garb)
+            .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })?,
+    );
 
     Ok(buf)
 }
 
+/// Check the header written by `serialize_for_evaluator`, and return what follows it
+fn strip_blob_header(buf: &[u8]) -> Result<&[u8], InterstellarError> {
+    if buf.len() < BLOB_HEADER_LEN {
+        return Err(InterstellarError::BlobHeaderInvalid);
+    }
+    let (header, body) = buf.split_at(BLOB_HEADER_LEN);
+    match header {
+        [magic @ .., version, nb_words]
+            if magic == BLOB_HEADER_MAGIC && *version == BLOB_HEADER_VERSION =>
+        {
+            if *nb_words != BLOB_HEADER_NB_WORDS {
+                return Err(InterstellarError::KappaMismatch {
+                    blob: (*nb_words).into(),
+                    build: KAPPA_NB_ELEMENTS,
+                });
+            }
+            Ok(body)
+        }
+        _ => Err(InterstellarError::BlobHeaderInvalid),
+    }
+}
+
 /// Deserialize
 /// Our use case only requires a subset of the whole (de)serialization so no need to expose the whole module
 ///
 /// # Errors
 ///
 /// `postcard::Error` if the deserialization failed
+/// `BlobHeaderInvalid` if `buf` does NOT start with the header; cf `serialize_for_evaluator`
+/// `KappaMismatch` if `buf` was garbled with another KAPPA than this build
+/// `TrailingData` if `buf` is NOT entirely consumed
+/// `GarbledCircuitInconsistent` with feature "`validate_on_deserialize`"; cf `GarbledCircuit::self_check`
 ///
 pub fn deserialize_for_evaluator(
     buf: &[u8],
 ) -> Result<(GarbledCircuit, EncodedGarblerInputs), InterstellarError> {
-    let (garb, encoded_garbler_inputs): (GarbledCircuit, EncodedGarblerInputs) = from_bytes(buf)
-        .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })?;
+    let buf = strip_blob_header(buf)?;
+    let ((garb, encoded_garbler_inputs), rest): ((GarbledCircuit, EncodedGarblerInputs), &[u8]) =
+        take_from_bytes(buf)
+            .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })?;
+    // postcard stops at the end of the struct; DO NOT silently ignore what follows
+    if !rest.is_empty() {
+        return Err(InterstellarError::TrailingData {
+            extra_bytes: rest.len(),
+        });
+    }
+
+    #[cfg(feature = "validate_on_deserialize")]
+    garb.self_check()?;
 
     Ok((garb, encoded_garbler_inputs))
 }
 
+/// The metadata of a serialized garbled circuit; cf `peek_garbled_metadata`
+#[derive(PartialEq, Debug, Clone)]
+pub struct GarbledMeta {
+    pub nb_inputs: usize,
+    pub nb_outputs: usize,
+    pub nb_wires: usize,
+    pub nb_gates: usize,
+    /// None for "generic circuits"
+    pub display_config: Option<DisplayConfig>,
+}
+
+/// Read ONLY the metadata(dimensions, inputs counts) at the start of a buffer produced by
+/// `serialize_for_evaluator`; eg to set up the UI before the full(and large) `deserialize_for_evaluator`.
+///
+/// # Errors
+///
+/// `postcard::Error` if the deserialization failed
+/// `BlobHeaderInvalid`/`KappaMismatch` cf `deserialize_for_evaluator`
+///
+pub fn peek_garbled_metadata(buf: &[u8]) -> Result<GarbledMeta, InterstellarError> {
+    let (header, _rest): (CircuitForEvalHeader, &[u8]) =
+        take_from_bytes(strip_blob_header(buf)?)
+            .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })?;
+
+    Ok(GarbledMeta {
+        nb_inputs: header.inputs.len(),
+        nb_outputs: header.nb_outputs,
+        nb_wires: header.nb_wires,
+        nb_gates: header.gates.len(),
+        display_config: header.display_config,
+    })
+}
+
+/// Serialize a parsed(but NOT garbled) `Circuit`
+/// This allows a server to cache the result of `parse_skcd` and skip re-parsing
+/// when only the garbler inputs change.
+///
+/// # Errors
+///
+/// `postcard::Error` if the serialization failed
+///
+pub fn serialize_circuit(circuit: &Circuit) -> Result<Vec<u8>, InterstellarError> {
+    to_allocvec(circuit)
+        .map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })
+}
+
+/// Reload a `Circuit` serialized with `serialize_circuit`
+///
+/// # Errors
+///
+/// `postcard::Error` if the deserialization failed
+///
+pub fn deserialize_circuit(buf: &[u8]) -> Result<Circuit, InterstellarError> {
+    from_bytes(buf).map_err(|err| InterstellarError::SerializerDeserializerInternalError { err })
+}
+
+/// EXPERIMENTAL: serialize `garb` for `eval_streaming`, ie gate by gate, with the list of the wires
+/// that can be discarded after each gate.
+/// NOTE: this is NOT the same format as `serialize_for_evaluator`; the garbler inputs are NOT included.
+///
+/// # Errors
+///
+/// `postcard::Error` if the serialization failed
+///
+pub fn serialize_for_streaming(garb: &GarbledCircuit) -> Result<Vec<u8>, InterstellarError> {
+    evaluate_streaming::serialize_for_streaming(&garb.garbled)
+}
+
+/// EXPERIMENTAL: evaluate a blob produced by `serialize_for_streaming` in a single pass over
+/// `reader`, keeping only the labels of the wires still needed by the next gates.
+/// Meant for memory constrained clients, eg reading the blob from a socket or a file without
+/// ever holding it entirely; `GarbledCircuit::eval` is faster otherwise.
+///
+/// `reader` SHOULD be buffered(eg `std::io::BufReader`); a `&[u8]` also works.
+///
+/// # Errors
+///
+/// - `postcard::Error` if the deserialization(or reading) failed
+/// - `EvaluateErrorWrongInputsLength` if the inputs do not match the circuit
+/// - `EvaluateErrorMissingLabel`/`EvaluateErrorMissingDelta` if the blob is inconsistent
+///
+#[cfg(feature = "std")]
+pub fn eval_streaming<R: std::io::Read>(
+    reader: R,
+    encoded_garbler_inputs: &EncodedGarblerInputs,
+    evaluator_inputs: &[EvaluatorInput],
+    outputs: &mut Vec<u8>,
+) -> Result<(), InterstellarEvaluatorError> {
+    let evaluator_inputs_wire_value: Vec<WireValue> = evaluator_inputs
+        .iter()
+        .map(core::convert::Into::into)
+        .collect();
+
+    let outputs_wire_value = evaluate_streaming::eval_streaming(
+        reader,
+        &encoded_garbler_inputs.encoded,
+        &evaluator_inputs_wire_value,
+    )?;
+
+    outputs.clear();
+    outputs.extend(outputs_wire_value.iter().map(u8::from));
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        garble_skcd, garble_skcd_with_seed, garbled_display_circuit_prepare_garbler_inputs,
+        garble_circuit, garble_skcd, garble_skcd_with_seed,
+        garbled_display_circuit_prepare_garbler_inputs, parse_skcd, prepare_evaluator_inputs,
+        EvalCache,
     };
+    use circuit_types_rs::{GateType, KindBinary, WireRef};
 
     /// test that specific(=postcard) (de)serialization works
     #[test]
@@ -84,7 +258,7 @@ mod tests {
             "../examples/data/result_abc_full_adder.postcard.bin"
         ))
         .unwrap();
-        let encoded_garbler_inputs = ref_garb.encode_inputs(&[]);
+        let encoded_garbler_inputs = ref_garb.encode_inputs(&[]).unwrap();
 
         let buf = serialize_for_evaluator(ref_garb.clone(), encoded_garbler_inputs).unwrap();
         let (new_garb, _new_encoded_garbler_inputs) = deserialize_for_evaluator(&buf).unwrap();
@@ -102,7 +276,7 @@ mod tests {
         ))
         .unwrap();
         let garbler_inputs = vec![0; ref_garb.num_inputs() as usize];
-        let encoded_garbler_inputs = ref_garb.encode_inputs(&garbler_inputs);
+        let encoded_garbler_inputs = ref_garb.encode_inputs(&garbler_inputs).unwrap();
 
         let buf = serialize_for_evaluator(ref_garb.clone(), encoded_garbler_inputs).unwrap();
         let (new_garb, _new_encoded_garbler_inputs) = deserialize_for_evaluator(&buf).unwrap();
@@ -115,6 +289,289 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deserialize_for_evaluator_trailing_data() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let mut buf = serialize_for_evaluator(garb, encoded_garbler_inputs).unwrap();
+        buf.extend_from_slice(&[0xde, 0xad, 0xbe]);
+
+        assert_eq!(
+            deserialize_for_evaluator(&buf),
+            Err(InterstellarError::TrailingData { extra_bytes: 3 })
+        );
+    }
+
+    #[test]
+    fn test_counts_after_deserialize_full_adder() {
+        let skcd_buf = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+        let circuit = parse_skcd(skcd_buf).unwrap();
+        let garb = garble_skcd(skcd_buf).unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let buf = serialize_for_evaluator(garb, encoded_garbler_inputs).unwrap();
+
+        let (garb, _encoded_garbler_inputs) = deserialize_for_evaluator(&buf).unwrap();
+
+        assert_eq!(garb.gate_count(), circuit.get_gates().len());
+        assert_eq!(garb.wire_count(), circuit.get_nb_wires());
+        assert_eq!(garb.input_count(), circuit.get_nb_inputs());
+        assert_eq!(garb.output_count(), circuit.get_nb_outputs());
+        assert_eq!(garb.input_count(), 3);
+        assert_eq!(garb.output_count(), 2);
+    }
+
+    #[test]
+    fn test_deserialize_for_evaluator_kappa_header() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let mut buf = serialize_for_evaluator(garb, encoded_garbler_inputs).unwrap();
+        assert_eq!(buf[..BLOB_HEADER_MAGIC.len()], BLOB_HEADER_MAGIC);
+
+        // as if garbled with KAPPA = 256
+        buf[BLOB_HEADER_LEN - 1] = 4;
+        assert_eq!(
+            deserialize_for_evaluator(&buf),
+            Err(InterstellarError::KappaMismatch {
+                blob: 4,
+                build: KAPPA_NB_ELEMENTS
+            })
+        );
+        assert_eq!(
+            peek_garbled_metadata(&buf),
+            Err(InterstellarError::KappaMismatch {
+                blob: 4,
+                build: KAPPA_NB_ELEMENTS
+            })
+        );
+
+        // no header at all, eg a blob from an older release
+        assert_eq!(
+            deserialize_for_evaluator(&buf[BLOB_HEADER_LEN..]),
+            Err(InterstellarError::BlobHeaderInvalid)
+        );
+        assert_eq!(
+            deserialize_for_evaluator(&buf[..2]),
+            Err(InterstellarError::BlobHeaderInvalid)
+        );
+
+        // unknown format version
+        buf[BLOB_HEADER_MAGIC.len()] = BLOB_HEADER_VERSION + 1;
+        assert_eq!(
+            deserialize_for_evaluator(&buf),
+            Err(InterstellarError::BlobHeaderInvalid)
+        );
+    }
+
+    #[test]
+    fn test_peek_garbled_metadata_display_message_120x52_2digits() {
+        let mut garb = garble_skcd(include_bytes!(
+            "../examples/data/result_display_message_120x52_2digits.postcard.bin"
+        ))
+        .unwrap();
+        let garbler_inputs = vec![0; garb.num_inputs()];
+        let encoded_garbler_inputs = garb.encode_inputs(&garbler_inputs).unwrap();
+        let buf = serialize_for_evaluator(garb.clone(), encoded_garbler_inputs).unwrap();
+
+        let meta = peek_garbled_metadata(&buf).unwrap();
+
+        let display_config = meta.display_config.unwrap();
+        assert_eq!((display_config.width, display_config.height), (120, 52));
+        assert_eq!(&display_config, garb.get_display_config().unwrap());
+        assert_eq!(meta.nb_outputs, 120 * 52);
+        assert_eq!(
+            meta.nb_inputs,
+            garb.num_inputs() + garb.num_evaluator_inputs()
+        );
+    }
+
+    #[test]
+    fn test_peek_garbled_metadata_full_adder() {
+        let mut garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let buf = serialize_for_evaluator(garb, encoded_garbler_inputs).unwrap();
+
+        let meta = peek_garbled_metadata(&buf).unwrap();
+
+        assert_eq!(meta.display_config, None);
+        assert_eq!(meta.nb_inputs, 3);
+        assert_eq!(meta.nb_outputs, 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_eval_streaming_full_adder_2bits() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let buf = serialize_for_streaming(&garb).unwrap();
+
+        let mut eval_cache = EvalCache::new();
+        let mut outputs = vec![];
+        let mut streaming_outputs = vec![];
+        for inputs in crate::tests::FULL_ADDER_2BITS_ALL_INPUTS {
+            garb.eval(
+                &encoded_garbler_inputs,
+                &inputs,
+                &mut outputs,
+                &mut eval_cache,
+            )
+            .unwrap();
+            eval_streaming(
+                buf.as_slice(),
+                &encoded_garbler_inputs,
+                &inputs,
+                &mut streaming_outputs,
+            )
+            .unwrap();
+
+            assert_eq!(streaming_outputs, outputs);
+        }
+    }
+
+    /// A `Read` handing out at most `chunk_len` bytes per call, eg like a socket
+    #[cfg(feature = "std")]
+    struct ChunkedReader<'a> {
+        buf: &'a [u8],
+        chunk_len: usize,
+    }
+
+    #[cfg(feature = "std")]
+    impl std::io::Read for ChunkedReader<'_> {
+        fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+            let len = out.len().min(self.chunk_len).min(self.buf.len());
+            let (chunk, rest) = self.buf.split_at(len);
+            out[..len].copy_from_slice(chunk);
+            self.buf = rest;
+            Ok(len)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_eval_streaming_chunked_reader_display_message_120x52_2digits() {
+        let garb = garble_skcd_with_seed(
+            include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin"),
+            42,
+        )
+        .unwrap();
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "test message").unwrap();
+        let evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+        let buf = serialize_for_streaming(&garb).unwrap();
+
+        let mut outputs = vec![];
+        garb.eval(
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            &mut outputs,
+            &mut EvalCache::new(),
+        )
+        .unwrap();
+
+        for chunk_len in [1, 7, 4096] {
+            let mut streaming_outputs = vec![];
+            eval_streaming(
+                std::io::BufReader::with_capacity(
+                    chunk_len,
+                    ChunkedReader {
+                        buf: &buf,
+                        chunk_len,
+                    },
+                ),
+                &encoded_garbler_inputs,
+                &evaluator_inputs,
+                &mut streaming_outputs,
+            )
+            .unwrap();
+
+            assert_eq!(streaming_outputs, outputs);
+        }
+
+        // truncated blob: MUST fail, NOT block or panic
+        assert!(matches!(
+            eval_streaming(
+                ChunkedReader {
+                    buf: &buf[..buf.len() / 2],
+                    chunk_len: 7,
+                },
+                &encoded_garbler_inputs,
+                &evaluator_inputs,
+                &mut vec![],
+            ),
+            Err(InterstellarEvaluatorError::BaseError { .. })
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_eval_streaming_display_message_120x52_2digits() {
+        let garb = garble_skcd_with_seed(
+            include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin"),
+            42,
+        )
+        .unwrap();
+        let encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&garb, &[4, 2], "test message").unwrap();
+        let evaluator_inputs = prepare_evaluator_inputs(&garb).unwrap();
+        let buf = serialize_for_streaming(&garb).unwrap();
+
+        let mut outputs = vec![];
+        garb.eval(
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            &mut outputs,
+            &mut EvalCache::new(),
+        )
+        .unwrap();
+        let mut streaming_outputs = vec![];
+        eval_streaming(
+            buf.as_slice(),
+            &encoded_garbler_inputs,
+            &evaluator_inputs,
+            &mut streaming_outputs,
+        )
+        .unwrap();
+
+        assert_eq!(streaming_outputs, outputs);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_eval_streaming_wrong_inputs_length() {
+        let garb = garble_skcd(include_bytes!(
+            "../examples/data/result_abc_full_adder.postcard.bin"
+        ))
+        .unwrap();
+        let encoded_garbler_inputs = garb.encode_inputs(&[]).unwrap();
+        let buf = serialize_for_streaming(&garb).unwrap();
+
+        assert!(matches!(
+            eval_streaming(
+                buf.as_slice(),
+                &encoded_garbler_inputs,
+                &[0, 1],
+                &mut vec![]
+            ),
+            Err(InterstellarEvaluatorError::EvaluateErrorWrongInputsLength {
+                inputs_len: 2,
+                expected_len: 3
+            })
+        ));
+    }
+
+    // the golden blob was garbled with `Xxh3Backend`
+    #[cfg(not(feature = "ro_blake3"))]
     #[test]
     fn test_serialize_golden_display_message_120x52_2digits() {
         let ref_garb = garble_skcd_with_seed(
@@ -138,6 +595,109 @@ mod tests {
         assert_eq!(buf, ref_buf, "failed {buf:#?} vs {ref_buf:#?}");
     }
 
+    /// The golden blob was garbled on a little-endian machine.
+    /// Together with `test_serialize_golden_display_message_120x52_2digits` this is meant to
+    /// ALSO run on a big-endian target(eg `cross test --target powerpc64-unknown-linux-gnu`):
+    /// a blob garbled on one architecture MUST evaluate the same on the other.
+    // the golden blob was garbled with `Xxh3Backend`
+    #[cfg(not(feature = "ro_blake3"))]
+    #[test]
+    fn test_deserialize_golden_evaluates_like_local_display_message_120x52_2digits() {
+        let (golden_garb, golden_encoded_garbler_inputs) = deserialize_for_evaluator(
+            include_bytes!("../examples/data/display_message_120x52_2digits.garbled.pb.bin"),
+        )
+        .unwrap();
+
+        let local_garb = garble_skcd_with_seed(
+            include_bytes!("../examples/data/result_display_message_120x52_2digits.postcard.bin"),
+            424242,
+        )
+        .unwrap();
+        let local_encoded_garbler_inputs =
+            garbled_display_circuit_prepare_garbler_inputs(&local_garb, &[4, 2], "test message")
+                .unwrap();
+
+        let evaluator_inputs = prepare_evaluator_inputs(&local_garb).unwrap();
+        let mut eval_cache = EvalCache::new();
+        let mut golden_outputs = vec![];
+        golden_garb
+            .eval(
+                &golden_encoded_garbler_inputs,
+                &evaluator_inputs,
+                &mut golden_outputs,
+                &mut eval_cache,
+            )
+            .unwrap();
+        let mut local_outputs = vec![];
+        local_garb
+            .eval(
+                &local_encoded_garbler_inputs,
+                &evaluator_inputs,
+                &mut local_outputs,
+                &mut eval_cache,
+            )
+            .unwrap();
+
+        assert_eq!(golden_outputs, local_outputs);
+    }
+
+    /// test that a cached `Circuit` garbles exactly like a freshly parsed one
+    #[test]
+    fn test_serialize_deserialize_circuit_garbles_the_same() {
+        let skcd_buf = include_bytes!("../examples/data/result_abc_full_adder.postcard.bin");
+
+        let buf = serialize_circuit(&parse_skcd(skcd_buf).unwrap()).unwrap();
+        let reloaded_circuit = deserialize_circuit(&buf).unwrap();
+
+        assert_eq!(
+            garble_circuit(reloaded_circuit, Some(42)).unwrap(),
+            garble_skcd_with_seed(skcd_buf, 42).unwrap()
+        );
+    }
+
+    /// Pin the encoding of `serialize_circuit`: postcard varints(7 bits groups, least significant
+    /// first) whatever the endianness of the machine; a cached `Circuit` MUST reload anywhere.
+    /// NOTE: one kind of gate only: the gates counts of `Metadata` are a `HashMap`, cf `CircuitLayout`
+    #[test]
+    fn test_serialize_circuit_golden_bytes() {
+        let circuit = crate::circuit_builder::build_circuit(
+            vec![WireRef { id: 0 }, WireRef { id: 1 }],
+            vec![(
+                GateType::Binary {
+                    gate_type: KindBinary::XOR,
+                    input_a: WireRef { id: 0 },
+                    input_b: WireRef { id: 1 },
+                },
+                WireRef { id: 300 },
+            )],
+            vec![WireRef { id: 300 }],
+            None,
+        )
+        .unwrap();
+
+        #[rustfmt::skip]
+        let golden: &[u8] = &[
+            // inputs: [0, 1]
+            0x02, 0x00, 0x01,
+            // outputs: [300]
+            0x01, 0xac, 0x02,
+            // gates: [XOR(0, 1) -> 300]
+            0x01, 0x00, 0x01, 0x00, 0x01, 0xac, 0x02,
+            // wires: [0, 1, 300]
+            0x03, 0x00, 0x01, 0xac, 0x02,
+            // metadata: outputs (300, 300), no unary, one XOR, no constant, max gate id 300
+            0xac, 0x02, 0xac, 0x02, 0x00, 0x01, 0x01, 0x01, 0x00, 0x00, 0xac, 0x02,
+            // config: None
+            0x00,
+        ];
+
+        assert_eq!(serialize_circuit(&circuit).unwrap(), golden);
+        assert_eq!(
+            serialize_circuit(&deserialize_circuit(golden).unwrap()).unwrap(),
+            golden
+        );
+    }
+
     /// test that the client DOES NOT have access to Encoder's `garbler_inputs`
     #[test]
     // TODO(security) [security] we SHOULD NOT be able to call `encoding_internal` after `(de)serialize_for_evaluator`
@@ -149,7 +709,7 @@ mod tests {
         ))
         .unwrap();
         let garbler_inputs = vec![0; ref_garb.num_inputs() as usize];
-        let encoded_garbler_inputs = ref_garb.encode_inputs(&garbler_inputs);
+        let encoded_garbler_inputs = ref_garb.encode_inputs(&garbler_inputs).unwrap();
 
         let buf = serialize_for_evaluator(ref_garb.clone(), encoded_garbler_inputs).unwrap();
         let (new_garb, _new_encoded_garbler_inputs) = deserialize_for_evaluator(&buf).unwrap();
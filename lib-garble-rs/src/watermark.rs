@@ -8,15 +8,22 @@ use crate::garble::GarblerInput;
 
 const FONT_BYTES: &[u8] = include_bytes!("../examples/data/BF_Modernista-Regular.ttf");
 const WATERMARK_COLOR: [u8; 1] = [255u8];
+/// Size(in pixels) of the font used by `my_draw_text_mut`
+const FONT_SIZE: f32 = 40.;
 
-/// The given integer is NOT a valid 7 segments option[ie 0-9]
 #[derive(Debug, Snafu)]
-#[snafu(display("Can open read the .ttf"))]
-pub(super) struct FontTtfErr {}
+pub(super) enum WatermarkErr {
+    #[snafu(display("Can open read the .ttf"))]
+    FontTtf,
+    #[snafu(display(
+        "watermark text too long: {nb_chars} chars on a line, but the display can show at most {max_chars}"
+    ))]
+    TextTooLong { nb_chars: usize, max_chars: usize },
+}
 
 /// Init a Font using the hardcoded .ttf from "data/"
-fn new_font<'a>() -> Result<Font<'a>, FontTtfErr> {
-    Font::try_from_bytes(FONT_BYTES).ok_or(FontTtfErr {})
+fn new_font<'a>() -> Result<Font<'a>, WatermarkErr> {
+    Font::try_from_bytes(FONT_BYTES).ok_or(WatermarkErr::FontTtf)
 }
 
 /// imageproc's `draw_text_mut` DOES NOT support multiline so we need to handle it on our side
@@ -50,16 +57,12 @@ fn my_draw_text_mut_with_newline(
 ///
 /// Return: a GRAYSCALE image; len = `img_height` * `img_width`
 #[allow(clippy::cast_possible_wrap)]
-fn my_draw_text_mut(image: &mut GrayImage, text: &str) -> Result<(), FontTtfErr> {
+fn my_draw_text_mut(image: &mut GrayImage, text: &str) -> Result<(), WatermarkErr> {
     let font = new_font()?;
 
     // TODO(interstellar) adjust pos and size; ideally measure the final text then center it as best as we can
     // eg use "text_size" etc
-    let font_size = 40.;
-    let scale = Scale {
-        x: font_size,
-        y: font_size,
-    };
+    let scale = Scale::uniform(FONT_SIZE);
     let text_pos_x = 0;
     let text_pos_y = 0;
 
@@ -95,6 +98,71 @@ fn convert_image_to_garbler_inputs(image: GrayImage) -> Vec<GarblerInput> {
         .collect()
 }
 
+/// How the glyphs of the watermark are rendered
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum WatermarkStyle {
+    /// The glyphs are fully set
+    #[default]
+    Filled,
+    /// ONLY the boundary pixels of each glyph are set
+    /// Some displays need this for legibility over the digits.
+    Outline,
+}
+
+/// Rendering options for the watermark
+/// NOTE: whatever the options, the result is still one bit per pixel ie `width * height` bits
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct WatermarkOptions {
+    pub style: WatermarkStyle,
+    /// Maximum number of chars per line of text; a longer line is rejected BEFORE rendering
+    /// instead of being silently clipped.
+    /// None: no limit(a line too long for the display is clipped);
+    /// cf `watermark_max_chars` for the limit of a given display.
+    pub max_chars: Option<usize>,
+}
+
+/// Maximum number of chars per line that can fit on a `img_width` display, from the font metrics:
+/// `img_width` divided by the advance width of the narrowest printable ASCII glyph
+/// at the size used by `my_draw_text_mut`.
+/// Conservative on purpose: ONLY a line which can NOT fit whatever its glyphs is longer than that.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub(crate) fn max_chars_for_width(img_width: u32) -> Result<usize, WatermarkErr> {
+    let font = new_font()?;
+    let scale = Scale::uniform(FONT_SIZE);
+    let min_glyph_width = ('!'..='~')
+        .map(|c| font.glyph(c).scaled(scale).h_metrics().advance_width)
+        .fold(f32::INFINITY, f32::min)
+        .max(1.);
+
+    Ok((img_width as f32 / min_glyph_width) as usize)
+}
+
+/// Reject a text whose longest line is longer than `WatermarkOptions::max_chars`, if set
+/// NOTE: lines are counted the same way as `my_draw_text_mut_with_newline`
+fn check_text_len(text: &str, options: &WatermarkOptions) -> Result<(), WatermarkErr> {
+    let Some(max_chars) = options.max_chars else {
+        return Ok(());
+    };
+    let nb_chars = text
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    ensure!(
+        nb_chars <= max_chars,
+        TextTooLongSnafu {
+            nb_chars,
+            max_chars
+        }
+    );
+
+    Ok(())
+}
+
 /// NOTE: our use case is to create a "watermark", that's why we create(and discard) the image here
 /// instead of passing it as parameter.
 /// cf `convert_image_to_garbler_inputs`
@@ -102,7 +170,18 @@ pub(crate) fn new_watermark(
     img_width: u32,
     img_height: u32,
     text: &str,
-) -> Result<Vec<GarblerInput>, FontTtfErr> {
+) -> Result<Vec<GarblerInput>, WatermarkErr> {
+    new_watermark_with_options(img_width, img_height, text, &WatermarkOptions::default())
+}
+
+/// cf `new_watermark`
+pub(crate) fn new_watermark_with_options(
+    img_width: u32,
+    img_height: u32,
+    text: &str,
+    options: &WatermarkOptions,
+) -> Result<Vec<GarblerInput>, WatermarkErr> {
+    check_text_len(text, options)?;
     let mut image = GrayImage::new(img_width, img_height);
 
     my_draw_text_mut(&mut image, text)?;
@@ -112,12 +191,184 @@ pub(crate) fn new_watermark(
         "watermark: wrong size!"
     );
 
-    Ok(convert_image_to_garbler_inputs(image))
+    let watermark = convert_image_to_garbler_inputs(image);
+    Ok(match options.style {
+        WatermarkStyle::Filled => watermark,
+        WatermarkStyle::Outline => outline(&watermark, img_width as usize),
+    })
+}
+
+/// Same as `new_watermark_with_options` but APPEND the bits to `res`
+/// NOTE: the image used to render the text is still allocated on each call
+pub(crate) fn new_watermark_into(
+    img_width: u32,
+    img_height: u32,
+    text: &str,
+    options: &WatermarkOptions,
+    res: &mut Vec<GarblerInput>,
+) -> Result<(), WatermarkErr> {
+    check_text_len(text, options)?;
+    match options.style {
+        WatermarkStyle::Filled => {
+            let mut image = GrayImage::new(img_width, img_height);
+            my_draw_text_mut(&mut image, text)?;
+            // cf `convert_image_to_garbler_inputs`
+            res.extend(image.iter().map(|pixel| u8::from(*pixel > 0)));
+        }
+        WatermarkStyle::Outline => res.extend(new_watermark_with_options(
+            img_width, img_height, text, options,
+        )?),
+    }
+
+    Ok(())
+}
+
+/// Cheap edge detection: keep a pixel set ONLY if at least one of its 4 neighbours
+/// is NOT set(or is outside the image).
+fn outline(watermark: &[GarblerInput], img_width: usize) -> Vec<GarblerInput> {
+    let img_height = if img_width == 0 {
+        0
+    } else {
+        watermark.len() / img_width
+    };
+    let is_on = |x: usize, y: usize| watermark[y * img_width + x] > 0;
+
+    watermark
+        .iter()
+        .enumerate()
+        .map(|(idx, pixel)| {
+            if *pixel == 0 {
+                return 0;
+            }
+            let x = idx % img_width;
+            let y = idx / img_width;
+            let is_interior = x > 0
+                && y > 0
+                && x + 1 < img_width
+                && y + 1 < img_height
+                && is_on(x - 1, y)
+                && is_on(x + 1, y)
+                && is_on(x, y - 1)
+                && is_on(x, y + 1);
+
+            u8::from(!is_interior)
+        })
+        .collect()
+}
+
+/// Nearest-neighbor scaling of a 1-bit watermark(row-major, `src_w * src_h` pixels) to `dst_w * dst_h`,
+/// eg to adapt a precomputed watermark to the `display_config` of another circuit.
+/// A pixel missing from `bits`(ie `bits` is shorter than `src_w * src_h`) is treated as NOT set.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn resample(bits: &[u8], src_w: u32, src_h: u32, dst_w: u32, dst_h: u32) -> Vec<u8> {
+    // NOTE: x < dst_w so x * src_w / dst_w < src_w ie it fits back in a usize
+    let src_coord = |dst: u32, src_len: u32, dst_len: u32| {
+        (u64::from(dst) * u64::from(src_len) / u64::from(dst_len)) as usize
+    };
+
+    (0..dst_h)
+        .flat_map(|y| (0..dst_w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let src_x = src_coord(x, src_w, dst_w);
+            let src_y = src_coord(y, src_h, dst_h);
+            bits.get(src_y * src_w as usize + src_x)
+                .copied()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Summary of how much a watermark "lights" the display
+/// eg to reject messages that would be illegible b/c too few pixels are set.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WatermarkCoverage {
+    /// Number of pixels set(ie == 1)
+    pub nb_pixels_on: usize,
+    /// Total number of pixels ie `width * height`
+    pub nb_pixels: usize,
+    /// The smallest rectangle containing all the pixels set; None if no pixel is set
+    pub bounding_box: Option<WatermarkBoundingBox>,
+}
+
+/// Inclusive bounds, in pixels
+#[derive(Debug, PartialEq, Clone)]
+pub struct WatermarkBoundingBox {
+    pub x_min: u32,
+    pub y_min: u32,
+    pub x_max: u32,
+    pub y_max: u32,
+}
+
+impl WatermarkCoverage {
+    /// "ratio" of pixels set; in [0-1]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fraction(&self) -> f32 {
+        if self.nb_pixels == 0 {
+            return 0.;
+        }
+
+        self.nb_pixels_on as f32 / self.nb_pixels as f32
+    }
+}
+
+/// Compute the `WatermarkCoverage` of a given text
+/// NOTE: it uses the same rendering as `new_watermark` so the result matches
+/// what would end up in the `garbler_inputs`.
+pub(crate) fn watermark_coverage(
+    img_width: u32,
+    img_height: u32,
+    text: &str,
+) -> Result<WatermarkCoverage, WatermarkErr> {
+    let watermark = new_watermark(img_width, img_height, text)?;
+
+    Ok(compute_coverage(&watermark, img_width))
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn compute_coverage(watermark: &[GarblerInput], img_width: u32) -> WatermarkCoverage {
+    let mut nb_pixels_on = 0;
+    let mut bounding_box: Option<WatermarkBoundingBox> = None;
+
+    for (idx, _pixel) in watermark
+        .iter()
+        .enumerate()
+        .filter(|(_, pixel)| **pixel > 0)
+    {
+        nb_pixels_on += 1;
+        // NOTE: idx < width * height so both x and y fit in u32
+        let x = (idx % img_width as usize) as u32;
+        let y = (idx / img_width as usize) as u32;
+
+        bounding_box = Some(match bounding_box {
+            None => WatermarkBoundingBox {
+                x_min: x,
+                y_min: y,
+                x_max: x,
+                y_max: y,
+            },
+            Some(bbox) => WatermarkBoundingBox {
+                x_min: bbox.x_min.min(x),
+                y_min: bbox.y_min.min(y),
+                x_max: bbox.x_max.max(x),
+                y_max: bbox.y_max.max(y),
+            },
+        });
+    }
+
+    WatermarkCoverage {
+        nb_pixels_on,
+        nb_pixels: watermark.len(),
+        bounding_box,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::format;
+    use alloc::vec;
     use image::EncodableLayout;
     use png_utils::read_png_to_bytes;
 
@@ -177,6 +428,170 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_outline_square() {
+        #[rustfmt::skip]
+        let watermark = vec![
+            0u8, 0, 0, 0, 0,
+            0, 1, 1, 1, 0,
+            0, 1, 1, 1, 0,
+            0, 1, 1, 1, 0,
+            0, 0, 0, 0, 0,
+        ];
+
+        #[rustfmt::skip]
+        assert_eq!(outline(&watermark, 5), vec![
+            0u8, 0, 0, 0, 0,
+            0, 1, 1, 1, 0,
+            0, 1, 0, 1, 0,
+            0, 1, 1, 1, 0,
+            0, 0, 0, 0, 0,
+        ]);
+    }
+
+    #[test]
+    fn test_resample_upscale_2x2_to_4x4() {
+        let watermark = vec![1u8, 0, 0, 1];
+
+        #[rustfmt::skip]
+        assert_eq!(resample(&watermark, 2, 2, 4, 4), vec![
+            1u8, 1, 0, 0,
+            1, 1, 0, 0,
+            0, 0, 1, 1,
+            0, 0, 1, 1,
+        ]);
+        // and back
+        assert_eq!(
+            resample(&resample(&watermark, 2, 2, 4, 4), 4, 4, 2, 2),
+            watermark
+        );
+    }
+
+    #[test]
+    fn test_resample_empty() {
+        assert!(resample(&[], 0, 0, 4, 4).iter().all(|pixel| *pixel == 0));
+        assert!(resample(&[1, 1], 2, 1, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_new_watermark_outline_sets_fewer_pixels_than_filled() {
+        let filled = new_watermark_with_options(
+            120,
+            52,
+            "Hello",
+            &WatermarkOptions {
+                style: WatermarkStyle::Filled,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let outlined = new_watermark_with_options(
+            120,
+            52,
+            "Hello",
+            &WatermarkOptions {
+                style: WatermarkStyle::Outline,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(filled.len(), 120 * 52);
+        assert_eq!(outlined.len(), 120 * 52);
+        let nb_on = |watermark: &[GarblerInput]| watermark.iter().filter(|p| **p > 0).count();
+        assert!(nb_on(&outlined) > 0);
+        assert!(nb_on(&outlined) < nb_on(&filled));
+        // an outline pixel is ALWAYS a filled pixel
+        assert!(outlined.iter().zip(&filled).all(|(o, f)| *o <= *f));
+    }
+
+    #[test]
+    fn test_new_watermark_text_too_long() {
+        let max_chars = max_chars_for_width(120).unwrap();
+        assert!(max_chars > 0 && max_chars < 120);
+        let text = "a".repeat(max_chars + 1);
+        let options = WatermarkOptions {
+            max_chars: Some(max_chars),
+            ..Default::default()
+        };
+
+        // default: no limit, the text is clipped
+        assert!(new_watermark(120, 52, &text).is_ok());
+        assert!(matches!(
+            new_watermark_with_options(120, 52, &text, &options),
+            Err(WatermarkErr::TextTooLong { nb_chars, max_chars: max })
+                if nb_chars == max_chars + 1 && max == max_chars
+        ));
+        // per line
+        assert!(new_watermark_with_options(
+            120,
+            52,
+            &format!("{}\n{}", &text[1..], &text[1..]),
+            &options
+        )
+        .is_ok());
+        // explicit limit
+        assert!(matches!(
+            new_watermark_with_options(
+                120,
+                52,
+                "Hello",
+                &WatermarkOptions {
+                    max_chars: Some(4),
+                    ..Default::default()
+                }
+            ),
+            Err(WatermarkErr::TextTooLong {
+                nb_chars: 5,
+                max_chars: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_watermark_coverage_blank() {
+        let coverage = watermark_coverage(120, 52, "").unwrap();
+
+        assert_eq!(coverage.nb_pixels_on, 0);
+        assert_eq!(coverage.nb_pixels, 120 * 52);
+        assert_eq!(coverage.bounding_box, None);
+        assert!(coverage.fraction() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_watermark_coverage_message() {
+        let coverage = watermark_coverage(120, 52, "Hello").unwrap();
+
+        assert!(coverage.nb_pixels_on > 0);
+        assert!(coverage.fraction() > 0.01 && coverage.fraction() < 0.5);
+        let bbox = coverage.bounding_box.unwrap();
+        assert!(bbox.x_min <= bbox.x_max && bbox.x_max < 120);
+        assert!(bbox.y_min <= bbox.y_max && bbox.y_max < 52);
+    }
+
+    #[test]
+    fn test_compute_coverage_bounding_box() {
+        #[rustfmt::skip]
+        let watermark = vec![
+            0u8, 0, 0, 0,
+            0, 1, 0, 0,
+            0, 0, 1, 1,
+        ];
+
+        let coverage = compute_coverage(&watermark, 4);
+
+        assert_eq!(coverage.nb_pixels_on, 3);
+        assert_eq!(
+            coverage.bounding_box,
+            Some(WatermarkBoundingBox {
+                x_min: 1,
+                y_min: 1,
+                x_max: 3,
+                y_max: 2,
+            })
+        );
+    }
+
     #[test]
     fn test_draw_text_french() {
         test_my_draw_text_mut(